@@ -1,5 +1,8 @@
 // closures.rs
 
+use std::collections::HashMap;
+use std::hash::Hash;
+
 fn main() {
     {
         fn add_one_v1(x: u32) -> u32 { x + 1 }
@@ -11,9 +14,41 @@ fn main() {
 
     {
         // Storing Closures Using Generic Parameters and the Fn Traits
-        let mut catcher = Cacher::new(|x: u32| -> u32 { x + 1 });
-        println! {"{}", catcher.value(10)}
-        println! {"{:?}", catcher.value}
+        let mut cacher = Cacher::new(|x: u32| -> u32 { x + 1 });
+        println! {"{}", cacher.value(10)}
+        println! {"{}", cacher.value(20)}
+        println! {"{:?}", cacher.values.get(&10)}
+
+        // every argument has its own value, a single cached value would give 11 for 20 too
+        assert_eq!(cacher.value(20), 21);
+        assert_eq!(cacher.value(10), 11);
+        assert_eq!(cacher.values.len(), 2);
+
+        // FnMut closures, the calculation runs once per argument
+        let mut calls = 0;
+        let mut lengths = Cacher::new(|s: String| {
+            calls += 1;
+            s.len()
+        });
+        assert_eq!(lengths.value(String::from("hello")), 5);
+        assert_eq!(lengths.value(String::from("hello")), 5);
+        assert_eq!(lengths.value(String::from("hi")), 2);
+        drop(lengths);
+        assert_eq!(calls, 2);
+
+        // fallible closures, errors are not cached and the next call tries again
+        let mut attempts = 0;
+        let mut numbers = Cacher::new(|s: &str| {
+            attempts += 1;
+            s.trim().parse::<i32>()
+        });
+        assert_eq!(numbers.try_value(" 42"), Ok(42));
+        assert!(numbers.try_value("x").is_err());
+        assert!(numbers.try_value("x").is_err());
+        assert_eq!(numbers.try_value(" 42"), Ok(42));
+        assert_eq!(numbers.values.len(), 1);
+        drop(numbers);
+        assert_eq!(attempts, 3);
     }
 
     {
@@ -25,33 +60,59 @@ fn main() {
     }
 }
 
-struct Cacher<T>
-    where
-        T: Fn(u32) -> u32,
-{
-    calculation: T,
-    value: Option<u32>,
+// Values of the calculation by argument, the calculation runs once for every argument.
+struct Cacher<F, K, V> {
+    calculation: F,
+    values: HashMap<K, V>,
 }
 
-impl<T> Cacher<T>
+impl<F, K, V> Cacher<F, K, V>
     where
-        T: Fn(u32) -> u32,
+        K: Eq + Hash + Clone,
+        V: Clone,
 {
-    fn new(calculation: T) -> Cacher<T> {
+    fn new(calculation: F) -> Cacher<F, K, V> {
         Cacher {
             calculation,
-            value: None,
+            values: HashMap::new(),
         }
     }
+}
 
-    fn value(&mut self, arg: u32) -> u32 {
-        match self.value {
-            Some(v) => v,
+// Fn closures are FnMut too.
+impl<F, K, V> Cacher<F, K, V>
+    where
+        F: FnMut(K) -> V,
+        K: Eq + Hash + Clone,
+        V: Clone,
+{
+    fn value(&mut self, arg: K) -> V {
+        match self.values.get(&arg) {
+            Some(v) => v.clone(),
             None => {
-                let v = (self.calculation)(arg);
-                self.value = Some(v);
+                let v = (self.calculation)(arg.clone());
+                self.values.insert(arg, v.clone());
                 v
             }
         }
     }
 }
+
+// Only Ok values are cached, an error may not happen the next time.
+impl<F, K, V, E> Cacher<F, K, V>
+    where
+        F: FnMut(K) -> Result<V, E>,
+        K: Eq + Hash + Clone,
+        V: Clone,
+{
+    fn try_value(&mut self, arg: K) -> Result<V, E> {
+        match self.values.get(&arg) {
+            Some(v) => Ok(v.clone()),
+            None => {
+                let v = (self.calculation)(arg.clone())?;
+                self.values.insert(arg, v.clone());
+                Ok(v)
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+// parallel-word-count.rs
+// Word counting from common-collections.rs combined with message passing from concurrency.rs.
+// Input is split into chunks, every worker thread counts words of the chunks it takes into
+// its own HashMap and sends it to the receiver which merges them into the final result.
+//
+// rustc parallel-word-count.rs
+// ./parallel-word-count --jobs 8 --verify ../basics/*.rs
+
+use std::collections::HashSet;
+use std::ops::Range;
+use std::panic;
+use std::process;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+#[path = "../basics/args.rs"]
+mod args;
+#[path = "../basics/crash_report.rs"]
+mod crash_report;
+#[path = "../basics/input.rs"]
+mod input;
+#[path = "../basics/word_count.rs"]
+mod word_count;
+
+use word_count::{Format, WordCounts};
+
+// Chunks smaller than this are not worth a context switch.
+const MIN_CHUNK_SIZE: usize = 64 * 1024;
+
+struct Chunk {
+    text: Arc<String>,
+    range: Range<usize>,
+}
+
+fn main() {
+    crash_report::install();
+
+    let matches = args::Command::new("parallel-word-count")
+        .about("Count words in files using all cores and print the most frequent ones.")
+        .option("jobs", Some('j'), "N", "Number of worker threads [default: number of cores]")
+        .option("top", Some('n'), "N", "Number of words to print [default: 10]")
+        .option("format", Some('f'), "FORMAT", "Output format text, csv or json [default: text]")
+        .option("stopwords", Some('s'), "FILE", "File with words to ignore, may be repeated")
+        .flag("english", Some('e'), "Ignore common English words")
+        .flag("verify", None, "Compare the result with the single-threaded counter")
+        .multiple("FILE", "Files to read, standard input if none or -")
+        .parse();
+
+    let jobs: usize = matches.get("jobs").unwrap_or_else(|e| e.exit())
+        .unwrap_or_else(|| thread::available_parallelism().map(|n| n.get()).unwrap_or(1));
+    if jobs == 0 {
+        matches.error(args::ErrorKind::Other(String::from("at least one job is required"))).exit();
+    }
+    let top: usize = matches.get("top").unwrap_or_else(|e| e.exit()).unwrap_or(10);
+    let format: Format = matches.get("format").unwrap_or_else(|e| e.exit()).unwrap_or(Format::Text);
+
+    let mut stopwords = HashSet::new();
+    if matches.flag("english") {
+        stopwords.extend(word_count::parse_stopwords(word_count::ENGLISH_STOPWORDS));
+    }
+    for path in matches.values("stopwords") {
+        stopwords.extend(word_count::parse_stopwords(&read_or_exit(path)));
+    }
+
+    let mut files = matches.values("FILE");
+    if files.is_empty() {
+        files.push("-");
+    }
+    let texts: Vec<Arc<String>> = files.iter().map(|path| Arc::new(read_or_exit(path))).collect();
+
+    let start = Instant::now();
+    let counts = count_parallel(&texts, &Arc::new(stopwords.clone()), jobs);
+    let parallel_time = start.elapsed();
+
+    if matches.flag("verify") {
+        let start = Instant::now();
+        let mut expected = WordCounts::new();
+        for text in &texts {
+            expected.add_text(text, &stopwords);
+        }
+        let single_time = start.elapsed();
+        if counts != expected {
+            eprintln!("parallel-word-count: parallel result differs from the single-threaded one");
+            process::exit(1);
+        }
+        eprintln!("verified: {} jobs {:?}, single thread {:?}", jobs, parallel_time, single_time);
+    }
+
+    print!("{}", counts.report(top, format));
+}
+
+fn count_parallel(texts: &[Arc<String>], stopwords: &Arc<HashSet<String>>, jobs: usize) -> WordCounts {
+    let total: usize = texts.iter().map(|text| text.len()).sum();
+    // a few chunks per job so faster workers can take more of them
+    let chunk_size = (total / (jobs * 4)).max(MIN_CHUNK_SIZE);
+
+    let mut chunks = Vec::new();
+    for text in texts {
+        for range in chunk_ranges(text, chunk_size) {
+            chunks.push(Chunk { text: Arc::clone(text), range });
+        }
+    }
+    let queue = Arc::new(Mutex::new(chunks));
+
+    let (tx, rx) = mpsc::channel();
+    let mut workers = Vec::new();
+    for _ in 0..jobs {
+        let tx = mpsc::Sender::clone(&tx);
+        let queue = Arc::clone(&queue);
+        let stopwords = Arc::clone(stopwords);
+        workers.push(thread::spawn(move || {
+            let mut counts = WordCounts::new();
+            loop {
+                // the lock is released at the end of the statement, before counting
+                let chunk = queue.lock().unwrap().pop();
+                match chunk {
+                    Some(chunk) => counts.add_text(&chunk.text[chunk.range], &stopwords),
+                    None => break,
+                }
+            }
+            tx.send(counts).unwrap();
+        }));
+    }
+    // receiver ends when all workers dropped their transmitters
+    drop(tx);
+
+    let mut counts = WordCounts::new();
+    for received in rx {
+        counts.merge(received);
+    }
+    // a worker which panicked sent nothing, the counts would be silently incomplete
+    for worker in workers {
+        if let Err(panic) = worker.join() {
+            panic::resume_unwind(panic);
+        }
+    }
+    counts
+}
+
+// Split text into ranges of about chunk_size bytes. Ranges end at a character which is never part
+// of a word, so no word is cut and the counts equal counting the whole text at once.
+fn chunk_ranges(text: &str, chunk_size: usize) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    while start < text.len() {
+        let mut end = (start + chunk_size).min(text.len());
+        while end < text.len() && !(text.is_char_boundary(end) && starts_with_separator(&text[end..])) {
+            end += 1;
+        }
+        ranges.push(start..end);
+        start = end;
+    }
+    ranges
+}
+
+fn starts_with_separator(rest: &str) -> bool {
+    rest.chars().next().is_none_or(word_count::is_separator)
+}
+
+fn read_or_exit(path: &str) -> String {
+    match input::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("parallel-word-count: {}: {}", path, e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,503 @@
+// args.rs
+// Small command-line argument parser shared by the example binaries.
+//
+//     mod args;
+//
+//     fn main() {
+//         let matches = args::Command::new("fibonacci")
+//             .about("Generate the nth Fibonacci number.")
+//             .flag("verbose", Some('v'), "Print more details")
+//             .option("base", Some('b'), "NUMBER", "Number base of the output")
+//             .positional("N", "Position in the Fibonacci sequence")
+//             .parse();
+//         let n: u32 = matches.required("N").unwrap_or_else(|e| e.exit());
+//         ...
+//     }
+//
+// Supported syntax: flags (`-v`, `--verbose`, `-abc`), options with values (`-b 16`, `-b16`,
+// `--base 16`, `--base=16`), positional arguments, subcommands and `--` which ends option parsing.
+// A separate value must not look like an option, a value starting with - is given as `-b-x` or
+// `--base=-x`, only negative numbers like `-b -5` work either way.
+// `-h` and `--help` are always available and print generated help.
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::process;
+use std::str::FromStr;
+
+#[derive(Debug, Clone)]
+struct Named {
+    long: String,
+    short: Option<char>,
+    // None for flags, name of the value for options
+    value_name: Option<String>,
+    help: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Arity {
+    Required,
+    Optional,
+    Multiple,
+}
+
+#[derive(Debug, Clone)]
+struct Positional {
+    name: String,
+    help: String,
+    arity: Arity,
+}
+
+// Definition of a command, its arguments and its subcommands.
+#[derive(Debug, Clone)]
+pub struct Command {
+    name: String,
+    about: String,
+    named: Vec<Named>,
+    positionals: Vec<Positional>,
+    subcommands: Vec<Command>,
+}
+
+impl Command {
+    pub fn new(name: &str) -> Command {
+        Command {
+            name: String::from(name),
+            about: String::new(),
+            named: vec![Named {
+                long: String::from("help"),
+                short: Some('h'),
+                value_name: None,
+                help: String::from("Print help"),
+            }],
+            positionals: Vec::new(),
+            subcommands: Vec::new(),
+        }
+    }
+
+    pub fn about(mut self, about: &str) -> Command {
+        self.about = String::from(about);
+        self
+    }
+
+    // Argument without value, e.g. `--verbose`.
+    pub fn flag(mut self, long: &str, short: Option<char>, help: &str) -> Command {
+        self.named.push(Named {
+            long: String::from(long),
+            short,
+            value_name: None,
+            help: String::from(help),
+        });
+        self
+    }
+
+    // Argument with value, e.g. `--base 16`, may be repeated.
+    pub fn option(mut self, long: &str, short: Option<char>, value_name: &str, help: &str) -> Command {
+        self.named.push(Named {
+            long: String::from(long),
+            short,
+            value_name: Some(String::from(value_name)),
+            help: String::from(help),
+        });
+        self
+    }
+
+    // Required positional argument.
+    pub fn positional(self, name: &str, help: &str) -> Command {
+        self.push_positional(name, help, Arity::Required)
+    }
+
+    // Positional argument which may be omitted.
+    pub fn optional(self, name: &str, help: &str) -> Command {
+        self.push_positional(name, help, Arity::Optional)
+    }
+
+    // Positional argument collecting all remaining values, must be the last one.
+    pub fn multiple(self, name: &str, help: &str) -> Command {
+        self.push_positional(name, help, Arity::Multiple)
+    }
+
+    fn push_positional(mut self, name: &str, help: &str, arity: Arity) -> Command {
+        self.positionals.push(Positional {
+            name: String::from(name),
+            help: String::from(help),
+            arity,
+        });
+        self
+    }
+
+    pub fn subcommand(mut self, command: Command) -> Command {
+        self.subcommands.push(command);
+        self
+    }
+
+    // Parse the arguments of this process, print help or usage error and exit when needed.
+    pub fn parse(&self) -> Matches {
+        match self.parse_from(env::args().skip(1)) {
+            Ok(matches) => matches,
+            Err(e) => e.exit(),
+        }
+    }
+
+    // Parse given arguments, the program name must not be included.
+    pub fn parse_from<I, S>(&self, args: I) -> Result<Matches, Error>
+        where
+            I: IntoIterator<Item=S>,
+            S: Into<String>,
+    {
+        let args: Vec<String> = args.into_iter().map(|s| s.into()).collect();
+        self.parse_args(&args, &self.name)
+    }
+
+    fn parse_args(&self, args: &[String], path: &str) -> Result<Matches, Error> {
+        let mut matches = Matches {
+            usage: self.usage_for(path),
+            flags: HashMap::new(),
+            options: HashMap::new(),
+            positionals: HashMap::new(),
+            subcommand: None,
+            order: Vec::new(),
+        };
+        let mut free: Vec<String> = Vec::new();
+        let mut options_done = false;
+        let mut i = 0;
+
+        while i < args.len() {
+            let arg = &args[i];
+            i += 1;
+
+            if options_done || !is_option(arg) {
+                if free.is_empty() && self.positionals.is_empty() && !self.subcommands.is_empty() {
+                    let sub = self.subcommands.iter()
+                        .find(|c| &c.name == arg)
+                        .ok_or_else(|| self.error(path, ErrorKind::UnknownCommand(arg.clone())))?;
+                    let sub_path = format!("{} {}", path, sub.name);
+                    let sub_matches = sub.parse_args(&args[i..], &sub_path)?;
+                    matches.subcommand = Some((sub.name.clone(), Box::new(sub_matches)));
+                    break;
+                }
+                free.push(arg.clone());
+            } else if arg == "--" {
+                options_done = true;
+            } else if let Some(long) = arg.strip_prefix("--") {
+                let (name, inline) = match long.find('=') {
+                    Some(pos) => (&long[..pos], Some(String::from(&long[pos + 1..]))),
+                    None => (long, None),
+                };
+                let named = self.named.iter()
+                    .find(|n| n.long == name)
+                    .ok_or_else(|| self.error(path, ErrorKind::UnknownArgument(arg.clone())))?;
+                match (&named.value_name, inline) {
+                    (None, None) => self.set_flag(&mut matches, named, path)?,
+                    (None, Some(_)) => return Err(self.error(path, ErrorKind::UnexpectedValue(format!("--{}", name)))),
+                    (Some(_), Some(value)) => matches.push_option(named, value),
+                    (Some(_), None) => {
+                        let value = self.next_value(args, &mut i, &format!("--{}", name), path)?;
+                        matches.push_option(named, value);
+                    }
+                }
+            } else {
+                // cluster of short arguments, an option takes the rest or the next argument
+                let shorts: Vec<char> = arg[1..].chars().collect();
+                for (pos, c) in shorts.iter().enumerate() {
+                    let named = self.named.iter()
+                        .find(|n| n.short == Some(*c))
+                        .ok_or_else(|| self.error(path, ErrorKind::UnknownArgument(format!("-{}", c))))?;
+                    if named.value_name.is_none() {
+                        self.set_flag(&mut matches, named, path)?;
+                        continue;
+                    }
+                    let rest: String = shorts[pos + 1..].iter().collect();
+                    let value = if rest.is_empty() {
+                        self.next_value(args, &mut i, &format!("-{}", c), path)?
+                    } else {
+                        rest
+                    };
+                    matches.push_option(named, value);
+                    break;
+                }
+            }
+        }
+
+        self.assign_positionals(&mut matches, free, path)?;
+        Ok(matches)
+    }
+
+    fn set_flag(&self, matches: &mut Matches, named: &Named, path: &str) -> Result<(), Error> {
+        if named.long == "help" {
+            return Err(self.error(path, ErrorKind::Help(self.help_for(path))));
+        }
+        *matches.flags.entry(named.long.clone()).or_insert(0) += 1;
+        matches.order.push(named.long.clone());
+        Ok(())
+    }
+
+    fn next_value(&self, args: &[String], i: &mut usize, name: &str, path: &str) -> Result<String, Error> {
+        match args.get(*i) {
+            // `--output --verbose` is a missing value, not the output file --verbose
+            Some(value) if !is_option(value) => {
+                *i += 1;
+                Ok(value.clone())
+            }
+            _ => Err(self.error(path, ErrorKind::MissingValue(String::from(name)))),
+        }
+    }
+
+    fn assign_positionals(&self, matches: &mut Matches, free: Vec<String>, path: &str) -> Result<(), Error> {
+        let mut free = free.into_iter();
+        for positional in &self.positionals {
+            let values: Vec<String> = match positional.arity {
+                Arity::Multiple => free.by_ref().collect(),
+                _ => free.next().into_iter().collect(),
+            };
+            if values.is_empty() && positional.arity == Arity::Required {
+                return Err(self.error(path, ErrorKind::MissingArgument(positional.name.clone())));
+            }
+            if !values.is_empty() {
+                matches.positionals.insert(positional.name.clone(), values);
+            }
+        }
+        match free.next() {
+            Some(extra) => Err(self.error(path, ErrorKind::UnexpectedArgument(extra))),
+            None => Ok(()),
+        }
+    }
+
+    fn error(&self, path: &str, kind: ErrorKind) -> Error {
+        Error { kind, usage: self.usage_for(path) }
+    }
+
+    pub fn usage(&self) -> String {
+        self.usage_for(&self.name)
+    }
+
+    fn usage_for(&self, path: &str) -> String {
+        let mut usage = format!("Usage: {} [OPTIONS]", path);
+        for positional in &self.positionals {
+            usage.push_str(&match positional.arity {
+                Arity::Required => format!(" <{}>", positional.name),
+                Arity::Optional => format!(" [{}]", positional.name),
+                Arity::Multiple => format!(" [{}]...", positional.name),
+            });
+        }
+        if !self.subcommands.is_empty() {
+            usage.push_str(" <COMMAND>");
+        }
+        usage
+    }
+
+    pub fn help(&self) -> String {
+        self.help_for(&self.name)
+    }
+
+    fn help_for(&self, path: &str) -> String {
+        let mut help = String::new();
+        if !self.about.is_empty() {
+            help.push_str(&format!("{}\n\n", self.about));
+        }
+        help.push_str(&self.usage_for(path));
+        help.push('\n');
+
+        let commands: Vec<(String, &str)> = self.subcommands.iter()
+            .map(|c| (c.name.clone(), c.about.as_str()))
+            .collect();
+        help.push_str(&section("Commands", &commands));
+
+        let arguments: Vec<(String, &str)> = self.positionals.iter()
+            .map(|p| (format!("<{}>", p.name), p.help.as_str()))
+            .collect();
+        help.push_str(&section("Arguments", &arguments));
+
+        let options: Vec<(String, &str)> = self.named.iter()
+            .map(|n| {
+                let short = match n.short {
+                    Some(c) => format!("-{}, ", c),
+                    None => String::from("    "),
+                };
+                let value = match &n.value_name {
+                    Some(v) => format!(" <{}>", v),
+                    None => String::new(),
+                };
+                (format!("{}--{}{}", short, n.long, value), n.help.as_str())
+            })
+            .collect();
+        help.push_str(&section("Options", &options));
+        help
+    }
+}
+
+fn is_option(arg: &str) -> bool {
+    // `-` alone means stdin and `-5` or `-40F` are values, not options
+    arg.len() > 1 && arg.starts_with('-') && !arg[1..].starts_with(|c: char| c.is_ascii_digit() || c == '.')
+}
+
+fn section(title: &str, rows: &[(String, &str)]) -> String {
+    if rows.is_empty() {
+        return String::new();
+    }
+    let width = rows.iter().map(|(left, _)| left.chars().count()).max().unwrap_or(0);
+    let mut text = format!("\n{}:\n", title);
+    for (left, right) in rows {
+        text.push_str(&format!("  {:width$}  {}\n", left, right, width = width));
+    }
+    text
+}
+
+// Result of parsing, flags and options are accessed by their long names.
+#[derive(Debug, Clone)]
+pub struct Matches {
+    usage: String,
+    flags: HashMap<String, usize>,
+    options: HashMap<String, Vec<String>>,
+    positionals: HashMap<String, Vec<String>>,
+    subcommand: Option<(String, Box<Matches>)>,
+    // long names of flags and options as they were given
+    order: Vec<String>,
+}
+
+impl Matches {
+    fn push_option(&mut self, named: &Named, value: String) {
+        self.options.entry(named.long.clone()).or_default().push(value);
+        self.order.push(named.long.clone());
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.occurrences(name) > 0
+    }
+
+    // How many times was the flag used, e.g. `-vvv` is 3.
+    pub fn occurrences(&self, name: &str) -> usize {
+        *self.flags.get(name).unwrap_or(&0)
+    }
+
+    // Last value of an option or the first value of a positional argument.
+    pub fn value(&self, name: &str) -> Option<&str> {
+        match self.options.get(name) {
+            Some(values) => values.last().map(|v| v.as_str()),
+            None => self.positionals.get(name).and_then(|v| v.first()).map(|v| v.as_str()),
+        }
+    }
+
+    // All values of a repeated option or a multiple positional argument.
+    pub fn values(&self, name: &str) -> Vec<&str> {
+        self.options.get(name)
+            .or_else(|| self.positionals.get(name))
+            .map(|values| values.iter().map(|v| v.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    // Typed value, `Ok(None)` when the argument is not present.
+    pub fn get<T>(&self, name: &str) -> Result<Option<T>, Error>
+        where
+            T: FromStr,
+            T::Err: fmt::Display,
+    {
+        match self.value(name) {
+            None => Ok(None),
+            Some(value) => self.convert(name, value).map(Some),
+        }
+    }
+
+    // Typed value which must be present.
+    pub fn required<T>(&self, name: &str) -> Result<T, Error>
+        where
+            T: FromStr,
+            T::Err: fmt::Display,
+    {
+        match self.value(name) {
+            None => Err(self.error(ErrorKind::MissingArgument(String::from(name)))),
+            Some(value) => self.convert(name, value),
+        }
+    }
+
+    // All values converted to the given type.
+    pub fn get_all<T>(&self, name: &str) -> Result<Vec<T>, Error>
+        where
+            T: FromStr,
+            T::Err: fmt::Display,
+    {
+        self.values(name).iter().map(|value| self.convert(name, value)).collect()
+    }
+
+    fn convert<T>(&self, name: &str, value: &str) -> Result<T, Error>
+        where
+            T: FromStr,
+            T::Err: fmt::Display,
+    {
+        value.parse::<T>().map_err(|e| self.error(ErrorKind::InvalidValue {
+            name: String::from(name),
+            value: String::from(value),
+            reason: e.to_string(),
+        }))
+    }
+
+    // Long names of used flags and options in the order they were given, for order dependent
+    // arguments like `--trim --upper`.
+    pub fn order(&self) -> Vec<&str> {
+        self.order.iter().map(|name| name.as_str()).collect()
+    }
+
+    pub fn subcommand(&self) -> Option<(&str, &Matches)> {
+        self.subcommand.as_ref().map(|(name, matches)| (name.as_str(), matches.as_ref()))
+    }
+
+    // Usage error for checks done by the program itself.
+    pub fn error(&self, kind: ErrorKind) -> Error {
+        Error { kind, usage: self.usage.clone() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+    // not an error, `--help` was requested, contains the help text
+    Help(String),
+    UnknownArgument(String),
+    UnknownCommand(String),
+    MissingArgument(String),
+    MissingValue(String),
+    UnexpectedValue(String),
+    UnexpectedArgument(String),
+    InvalidValue { name: String, value: String, reason: String },
+    Other(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    pub kind: ErrorKind,
+    usage: String,
+}
+
+impl Error {
+    // Print help to stdout and exit with 0 or print the error with usage to stderr and exit with 2.
+    pub fn exit(&self) -> ! {
+        match &self.kind {
+            ErrorKind::Help(help) => {
+                print!("{}", help);
+                process::exit(0);
+            }
+            _ => {
+                eprintln!("error: {}\n\n{}\n\nFor more information, try '--help'.", self, self.usage);
+                process::exit(2);
+            }
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            ErrorKind::Help(help) => write!(f, "{}", help),
+            ErrorKind::UnknownArgument(arg) => write!(f, "unexpected argument '{}' found", arg),
+            ErrorKind::UnknownCommand(name) => write!(f, "unrecognized subcommand '{}'", name),
+            ErrorKind::MissingArgument(name) => write!(f, "the required argument <{}> was not provided", name),
+            ErrorKind::MissingValue(name) => write!(f, "a value is required for '{}' but none was supplied", name),
+            ErrorKind::UnexpectedValue(name) => write!(f, "'{}' doesn't take a value", name),
+            ErrorKind::UnexpectedArgument(arg) => write!(f, "unexpected argument '{}' found", arg),
+            ErrorKind::InvalidValue { name, value, reason } => {
+                write!(f, "invalid value '{}' for '{}': {}", value, name, reason)
+            }
+            ErrorKind::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
@@ -0,0 +1,207 @@
+// borrow-check.rs
+// Moves and borrows checked the way Rust does, see borrow_check.rs.
+//
+// rustc borrow-check.rs
+// ./borrow-check analyze ownership.rs references-and-borrowing.rs
+// ./borrow-check check             the commented-out lines of those files give the errors of rustc
+
+use std::fs;
+use std::process;
+
+mod args;
+mod borrow_check;
+mod convert;
+mod crash_report;
+mod lexer;
+mod literal;
+
+const OWNERSHIP: &str = include_str!("ownership.rs");
+const REFERENCES: &str = include_str!("references-and-borrowing.rs");
+
+fn main() {
+    crash_report::install();
+
+    let matches = args::Command::new("borrow-check")
+        .about("Find use after move, conflicting borrows and dangling references.")
+        .subcommand(args::Command::new("analyze")
+            .about("Print the errors of the files with an explanation, nothing when there are none")
+            .multiple("FILE", "Rust source files in the language of borrow_check.rs"))
+        .subcommand(args::Command::new("check")
+            .about("Check the analyzer with ownership.rs and references-and-borrowing.rs"))
+        .parse();
+
+    match matches.subcommand() {
+        Some(("analyze", sub)) => {
+            let mut failed = false;
+            for path in sub.values("FILE") {
+                let source = fs::read_to_string(path).unwrap_or_else(|e| {
+                    eprintln!("borrow-check: {}: {}", path, e);
+                    process::exit(2);
+                });
+                let diagnostics = borrow_check::check(&source).unwrap_or_else(|e| vec![e]);
+                for diagnostic in &diagnostics {
+                    println!("{}:{}", path, diagnostic);
+                }
+                failed |= !diagnostics.is_empty();
+            }
+            if failed {
+                process::exit(1);
+            }
+        }
+        Some(("check", _)) => {
+            check();
+            println!("ok");
+        }
+        _ => matches.error(args::ErrorKind::Other(String::from("a command is required"))).exit(),
+    }
+}
+
+// The source with the replacements, which must all be found.
+fn edit(source: &str, replacements: &[(&str, &str)]) -> String {
+    let mut source = source.to_string();
+    for (from, to) in replacements {
+        assert!(source.contains(from), "{}", from);
+        source = source.replacen(from, to, 1);
+    }
+    source
+}
+
+// The diagnostics without their help.
+fn errors(source: &str) -> Vec<String> {
+    let diagnostics = borrow_check::check(source).unwrap_or_else(|e| vec![e]);
+    diagnostics.iter()
+        .map(|d| d.to_string().lines().filter(|line| !line.starts_with("  help: ")).collect::<Vec<_>>().join("\n"))
+        .collect()
+}
+
+fn errors_in_main(body: &str) -> Vec<String> {
+    errors(&format!("fn main() {{\n{}\n}}\n", body))
+}
+
+fn check() {
+    let none: [&str; 0] = [];
+    assert_eq!(errors(OWNERSHIP), none);
+    assert_eq!(errors(REFERENCES), none);
+
+    // ownership.rs
+    let source = edit(OWNERSHIP, &[(
+        "// println!(\"{}, world!\", s1); - variable is moved so we can't use it",
+        "println!(\"{}, world!\", s1); // variable is moved so we can't use it",
+    )]);
+    let diagnostics = borrow_check::check(&source).unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].to_string(), "\
+24:32: borrow of moved value: `s1`
+  23:18: value moved into `s2` here
+  help: `s1` is a String, which doesn't implement Copy, so the value moved to `s2` and `s1` can't be used \
+anymore. Use `s2`, or `s1.clone()` to keep a copy in both");
+    let source = edit(OWNERSHIP, &[(
+        "//  println!(\"{}, world!\", s); this is not valid",
+        "println!(\"{}, world!\", s); // this is not valid",
+    )]);
+    assert_eq!(errors(&source), ["51:32: borrow of moved value: `s`\n  49:25: value moved into `takes_ownership` here"]);
+
+    // references-and-borrowing.rs
+    let source = edit(REFERENCES, &[
+        ("//let r2 = &mut s; -- second borrow", "let r2 = &mut s; // second borrow"),
+        ("//println!(\"{}, {}\", r1, r2);", "println!(\"{}, {}\", r1, r2);"),
+    ]);
+    assert_eq!(errors(&source), ["25:18: cannot borrow `s` as mutable more than once at a time\n  \
+                                  24:18: first mutable borrow occurs here\n  \
+                                  26:28: first mutable borrow later used here, by `r1`"]);
+    let source = edit(REFERENCES, &[
+        ("// let r3 = &mut s; // BIG PROBLEM", "let r3 = &mut s; // BIG PROBLEM"),
+        ("// println!(\"{}, {}, and {}\", r1, r2, r3);", "println!(\"{}, {}, and {}\", r1, r2, r3);"),
+    ]);
+    assert_eq!(errors(&source), ["35:18: cannot borrow `s` as mutable because it is also borrowed as immutable\n  \
+                                  33:18: immutable borrow occurs here\n  \
+                                  36:36: immutable borrow later used here, by `r1`"]);
+    let source = edit(REFERENCES, &[
+        ("fn dangle() -> String {\n//fn dangle() -> &String {", "//fn dangle() -> String {\nfn dangle() -> &String {"),
+        ("    //&s\n", "    &s\n"),
+        ("\n    s\n}", "\n}"),
+    ]);
+    assert_eq!(errors(&source), ["77:5: cannot return reference to local variable `s`\n  82:1: `s` is dropped here"]);
+    // r1 and r2 are no longer used after this point, so r3 is fine, but not before it
+    let source = edit(REFERENCES, &[("println!(\"{}, world!\", r3);", "println!(\"{}, {}\", r3, r1);")]);
+    assert_eq!(errors(&source), ["49:18: cannot borrow `s` as mutable because it is also borrowed as immutable\n  \
+                                  44:18: immutable borrow occurs here\n  \
+                                  50:32: immutable borrow later used here, by `r1`"]);
+    let source = edit(REFERENCES, &[("let mut s = String::from(\"hello\");\n        change(&mut s);", "let s = String::from(\"hello\");\n        change(&mut s);")]);
+    assert_eq!(errors(&source), ["12:16: cannot borrow `s` as mutable, as it is not declared as mutable"]);
+    let source = edit(REFERENCES, &[("calculate_length(&s1)", "calculate_length(s1)")]);
+    assert_eq!(errors(&source), ["6:36: mismatched types: expected `&String`, found `String`", "7:47: borrow of moved value: `s1`\n  \
+                                  6:36: value moved into `calculate_length` here"]);
+    let source = edit(REFERENCES, &[("fn change(some_string: &mut String)", "fn change(some_string: &String)")]);
+    assert_eq!(errors(&source), ["70:5: cannot borrow `*some_string` as mutable, as it is behind a `&` reference"]);
+
+    // moves
+    assert_eq!(errors_in_main("let s = String::from(\"a\");\nlet r = &s;\nlet t = s;\nprintln!(\"{}\", r);"),
+               ["4:9: cannot move out of `s` because it is borrowed\n  3:9: borrow of `s` occurs here\n  5:16: borrow later used here, by `r`"]);
+    assert_eq!(errors_in_main("let mut s = String::new();\nlet t = s;\ns = String::from(\"b\");\nprintln!(\"{} {}\", s, t);"), none);
+    assert_eq!(errors_in_main("let s = String::new();\nlet t = (s, 1);\nlet u = s;"),
+               ["4:9: use of moved value: `s`\n  3:10: value moved into `t` here"]);
+    assert_eq!(errors_in_main("let x = 5;\nlet y = x;\nlet s = \"copied\";\nlet t = s;\nprintln!(\"{} {} {} {}\", x, y, s, t);"), none);
+
+    // borrows
+    assert_eq!(errors_in_main("let mut s = String::new();\nlet r = &mut s;\nprintln!(\"{}\", s);\nr.push_str(\"!\");"),
+               ["4:16: cannot borrow `s` as immutable because it is also borrowed as mutable\n  \
+                 3:9: mutable borrow occurs here\n  5:1: mutable borrow later used here, by `r`"]);
+    assert_eq!(errors_in_main("let mut x = 5;\nlet r = &mut x;\nlet y = x;\nprintln!(\"{}\", r);"),
+               ["4:9: cannot use `x` because it was mutably borrowed\n  3:9: mutable borrow occurs here\n  \
+                 5:16: mutable borrow later used here, by `r`"]);
+    assert_eq!(errors_in_main("let mut s = String::new();\nlet r = &s;\ns = String::new();\nprintln!(\"{}\", r);"),
+               ["4:3: cannot assign to `s` because it is borrowed\n  3:9: `s` is borrowed here\n  5:16: borrow later used here, by `r`"]);
+    assert_eq!(errors_in_main("let mut s = String::new();\nlet r = &s;\nlet r2 = r;\ns.push_str(\"x\");\nprintln!(\"{}\", r2);"),
+               ["5:1: cannot borrow `s` as mutable because it is also borrowed as immutable\n  \
+                 3:9: immutable borrow occurs here\n  6:16: immutable borrow later used here, by `r2`"]);
+    // a returned reference borrows from the argument
+    let source = "fn first(s: &String) -> &String {\n    s\n}\n\nfn main() {\n    let mut s = String::new();\n    \
+                  let r = first(&s);\n    s.push_str(\"x\");\n    println!(\"{}\", r);\n}\n";
+    assert_eq!(errors(source), ["8:5: cannot borrow `s` as mutable because it is also borrowed as immutable\n  \
+                                 7:19: immutable borrow occurs here\n  9:20: immutable borrow later used here, by `r`"]);
+
+    // &String works as &str, and the borrow still counts
+    let source = "fn first_word(s: &str) -> &str {\n    s\n}\n\nfn main() {\n    let mut a = String::from(\"hello\");\n    \
+                  let b = String::from(\" world\");\n    a.push_str(&b);\n    let c = String::from(&b);\n    \
+                  let w = first_word(&a);\n    let t: &str = &c;\n    println!(\"{} {} {}\", w, t, b);\n}\n";
+    assert_eq!(errors(source), none);
+    let source = edit(source, &[("println!(\"{} {} {}\", w, t, b);", "a.push_str(t);\n    println!(\"{}\", w);")]);
+    assert_eq!(errors(&source), ["12:5: cannot borrow `a` as mutable because it is also borrowed as immutable\n  \
+                                  10:24: immutable borrow occurs here\n  13:20: immutable borrow later used here, by `w`"]);
+    assert_eq!(errors_in_main("let s = String::new();\nlet t: &mut str = &s;"),
+               ["3:19: mismatched types: expected `&mut str`, found `&String`"]);
+
+    // a &mut reference passed to a &mut parameter is reborrowed, not moved
+    let source = "fn change(s: &mut String) {\n    s.push_str(\"!\");\n}\n\nfn main() {\n    let mut s = String::new();\n    \
+                  let r = &mut s;\n    change(r);\n    change(r);\n    println!(\"{}\", s);\n}\n";
+    assert_eq!(errors(source), none);
+    let source = edit(source, &[("    change(r);\n    change(r);", "    change(r);\n    println!(\"{}\", s);\n    change(r);")]);
+    assert_eq!(errors(&source), ["9:20: cannot borrow `s` as immutable because it is also borrowed as mutable\n  \
+                                  7:13: mutable borrow occurs here\n  10:12: mutable borrow later used here, by `r`"]);
+    // a binding still moves it
+    assert_eq!(errors_in_main("let mut s = String::new();\nlet r = &mut s;\nlet r2 = r;\nr.push_str(\"!\");"),
+               ["5:1: borrow of moved value: `r`\n  4:10: value moved into `r2` here"]);
+
+    // dangling references
+    assert_eq!(errors_in_main("let r;\n{\n    let s = String::from(\"a\");\n    r = &s;\n}\nprintln!(\"{}\", r);"),
+               ["5:9: `s` does not live long enough\n  6:1: `s` dropped here while still borrowed\n  \
+                 7:16: borrow later used here, by `r`"]);
+    assert_eq!(errors_in_main("let r = {\n    let s = String::from(\"a\");\n    &s\n};"),
+               ["4:5: `s` does not live long enough\n  5:1: `s` dropped here while still borrowed"]);
+    assert_eq!(errors("fn f(s: String) -> &String {\n    &s\n}\n"),
+               ["2:5: cannot return reference to function parameter `s`\n  3:1: `s` is dropped here"]);
+
+    // other mistakes
+    assert_eq!(errors_in_main("let x = 1;\nx = 2;"), ["3:3: cannot assign twice to immutable variable `x`"]);
+    assert_eq!(errors_in_main("let s: String;\nprintln!(\"{}\", s);"), ["3:16: used binding `s` isn't initialized"]);
+    assert_eq!(errors_in_main("let n = missing(1);\nprintln!(\"{}\", m);"),
+               ["2:9: cannot find function `missing` in this file", "3:16: cannot find value `m` in this scope"]);
+    assert_eq!(errors_in_main("let (a, b) = String::new();"), ["2:6: mismatched types: expected a tuple of 2, found `String`"]);
+    assert_eq!(errors_in_main("let s = String::new();\nlet n = s.capacity();"),
+               ["3:11: no method named `capacity`, only clone, len and push_str are supported"]);
+    assert_eq!(errors_in_main("let x = ;"), ["2:9: expected an expression, found `;`"]);
+    assert_eq!(errors("fn main() {\n    let v: Vec<i32> = Vec::new();\n}\n"),
+               ["2:12: expected String, str, bool, an integer type, a reference or a tuple, found `Vec`"]);
+    assert_eq!(errors("fn main() {\n    let s = \"open\n}\n"), ["2:13: unterminated string"]);
+}
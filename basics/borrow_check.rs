@@ -0,0 +1,1055 @@
+// borrow_check.rs
+// Moves and borrows of a small part of Rust checked the way the borrow checker does, enough for
+// ownership.rs and references-and-borrowing.rs including their commented-out lines:
+//
+// let s1 = String::from("hello");
+// let s2 = s1;
+// println!("{}, world!", s1);          borrow of moved value: `s1`
+//
+// The language has functions, let, assignments, blocks, String, &str, integers, bools, tuples,
+// & and &mut, String::from, String::new, clone, len, push_str and println!. Without branches or
+// loops the code runs in the order of the text. Like in Rust a borrow lasts until the last use of
+// the reference and not until the end of its block, so every function is checked twice and the
+// first time only finds the uses of every variable.
+//
+// Every diagnostic has the place of the error, notes pointing at the moves and borrows involved
+// and a help text with the rule which was broken.
+//
+// A &mut reference passed to a &mut parameter is reborrowed and stays usable after the call, as
+// in Rust. Not handled: structs, other methods, lifetimes in signatures, references inside of
+// tuples and other reborrows, like `let r2 = &mut *r;`.
+//
+// Binaries using this module must also declare `mod convert;`, `mod lexer;` and `mod literal;`.
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::lexer::{self, Span, Token, TokenKind};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    // both start at 1, column is in chars
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+    // the other places involved, like the move before a use
+    pub notes: Vec<Note>,
+    // the rule and how to follow it
+    pub help: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Note {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)?;
+        for note in &self.notes {
+            write!(f, "\n  {}:{}: {}", note.line, note.column, note.message)?;
+        }
+        if let Some(help) = &self.help {
+            write!(f, "\n  help: {}", help)?;
+        }
+        Ok(())
+    }
+}
+
+fn diagnostic(span: Span, message: String) -> Diagnostic {
+    Diagnostic { line: span.line, column: span.column, message, notes: Vec::new(), help: None }
+}
+
+fn note(span: Span, message: String) -> Note {
+    Note { line: span.line, column: span.column, message }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Int(String),
+    Bool,
+    Str,
+    String,
+    Ref(Box<Type>, bool),
+    // () too
+    Tuple(Vec<Type>),
+    // after an error
+    Unknown,
+}
+
+impl Type {
+    fn unit() -> Type {
+        Type::Tuple(Vec::new())
+    }
+
+    // Copy values are copied where others are moved, shared references are Copy and &mut aren't.
+    fn is_copy(&self) -> bool {
+        match self {
+            Type::Int(_) | Type::Bool | Type::Unknown | Type::Ref(_, false) => true,
+            Type::Tuple(types) => types.iter().all(Type::is_copy),
+            Type::Str | Type::String | Type::Ref(_, true) => false,
+        }
+    }
+
+    fn has_ref(&self) -> bool {
+        match self {
+            Type::Ref(..) => true,
+            Type::Tuple(types) => types.iter().any(Type::has_ref),
+            _ => false,
+        }
+    }
+
+    // A value of type found can be used where expected is needed, &mut T also works as &T and
+    // &String as &str by deref coercion.
+    fn fits(&self, found: &Type) -> bool {
+        match (self, found) {
+            (Type::Unknown, _) | (_, Type::Unknown) | (Type::Int(_), Type::Int(_)) => true,
+            (Type::Ref(a, false), Type::Ref(b, _)) | (Type::Ref(a, true), Type::Ref(b, true))
+                if **a == Type::Str && **b == Type::String => true,
+            (Type::Ref(a, false), Type::Ref(b, _)) | (Type::Ref(a, true), Type::Ref(b, true)) => a.fits(b),
+            (Type::Tuple(a), Type::Tuple(b)) => a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.fits(b)),
+            (a, b) => a == b,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Int(name) => f.write_str(name),
+            Type::Bool => f.write_str("bool"),
+            Type::Str => f.write_str("str"),
+            Type::String => f.write_str("String"),
+            Type::Ref(target, false) => write!(f, "&{}", target),
+            Type::Ref(target, true) => write!(f, "&mut {}", target),
+            Type::Tuple(types) => {
+                let types: Vec<String> = types.iter().map(Type::to_string).collect();
+                write!(f, "({})", types.join(", "))
+            }
+            Type::Unknown => f.write_str("_"),
+        }
+    }
+}
+
+struct Function {
+    name: String,
+    params: Vec<Param>,
+    ret: Type,
+    body: Block,
+}
+
+struct Param {
+    name: String,
+    mutable: bool,
+    ty: Type,
+    span: Span,
+}
+
+struct Block {
+    statements: Vec<Statement>,
+    // the value of the block, () without it
+    tail: Option<Box<Expr>>,
+    // the }
+    close: Span,
+}
+
+enum Statement {
+    // let x = ... or let (a, mut b) = ..., value is None for let x;
+    Let { names: Vec<(String, bool, Span)>, tuple: bool, annotation: Option<Type>, value: Option<Expr> },
+    Expr(Expr),
+}
+
+struct Expr {
+    kind: ExprKind,
+    span: Span,
+}
+
+enum ExprKind {
+    Int,
+    Str,
+    Bool,
+    Var(String),
+    // &x is Borrow(false, x), &mut x is Borrow(true, x)
+    Borrow(bool, Box<Expr>),
+    Call(String, Vec<Expr>),
+    StringFrom(Box<Expr>),
+    StringNew,
+    Method(Box<Expr>, String, Vec<Expr>),
+    // () too
+    Tuple(Vec<Expr>),
+    Block(Block),
+    Println(Vec<Expr>),
+    Assign(String, Box<Expr>),
+}
+
+// Diagnostics of every function in the order of the source, or the first syntax error.
+pub fn check(source: &str) -> Result<Vec<Diagnostic>, Diagnostic> {
+    let tokens = lexer::lexer(source)
+        .filter(|t| !matches!(t, Ok(Token { kind: TokenKind::LineComment, .. }) | Ok(Token { kind: TokenKind::BlockComment, .. })))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| Diagnostic { line: e.line, column: e.column, message: e.message, notes: Vec::new(), help: None })?;
+    let line_start = source.rfind('\n').map_or(0, |i| i + 1);
+    let end = Span {
+        start: source.len(),
+        end: source.len(),
+        line: source.matches('\n').count() + 1,
+        column: source[line_start..].chars().count() + 1,
+    };
+    let functions = Parser { tokens, pos: 0, end }.program()?;
+    let by_name: HashMap<&str, &Function> = functions.iter().map(|f| (f.name.as_str(), f)).collect();
+
+    let mut diagnostics = Vec::new();
+    for function in &functions {
+        // the first run finds the uses, the second one which borrows are still in use
+        let uses = Checker::new(&by_name, function, Vec::new()).run().uses;
+        diagnostics.extend(Checker::new(&by_name, function, uses).run().diagnostics);
+    }
+    Ok(diagnostics)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+    // after the last char of the source
+    end: Span,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.pos)
+    }
+
+    fn is(&self, text: &str) -> bool {
+        self.peek().is_some_and(|t| t.text == text)
+    }
+
+    fn is_at(&self, offset: usize, text: &str) -> bool {
+        self.tokens.get(self.pos + offset).is_some_and(|t| t.text == text)
+    }
+
+    fn span(&self) -> Span {
+        self.peek().map_or(self.end, |t| t.span)
+    }
+
+    fn advance(&mut self) -> Token<'a> {
+        self.pos += 1;
+        self.tokens[self.pos - 1]
+    }
+
+    fn error(&self, expected: &str) -> Diagnostic {
+        let found = match self.peek() {
+            Some(token) => format!("`{}`", token.text),
+            None => String::from("the end of the file"),
+        };
+        diagnostic(self.span(), format!("expected {}, found {}", expected, found))
+    }
+
+    fn expect(&mut self, text: &str) -> Result<Token<'a>, Diagnostic> {
+        if self.is(text) {
+            Ok(self.advance())
+        } else {
+            Err(self.error(&format!("`{}`", text)))
+        }
+    }
+
+    fn ident(&mut self) -> Result<(String, Span), Diagnostic> {
+        match self.peek() {
+            Some(token) if token.kind == TokenKind::Ident => {
+                let token = self.advance();
+                Ok((token.text.to_string(), token.span))
+            }
+            _ => Err(self.error("a name")),
+        }
+    }
+
+    fn program(&mut self) -> Result<Vec<Function>, Diagnostic> {
+        let mut functions: Vec<Function> = Vec::new();
+        while self.peek().is_some() {
+            self.expect("fn")?;
+            let (name, span) = self.ident()?;
+            if functions.iter().any(|f| f.name == name) {
+                return Err(diagnostic(span, format!("the function `{}` is defined twice", name)));
+            }
+            self.expect("(")?;
+            let mut params = Vec::new();
+            while !self.is(")") {
+                let mutable = self.is("mut");
+                if mutable {
+                    self.advance();
+                }
+                let (name, span) = self.ident()?;
+                self.expect(":")?;
+                params.push(Param { name, mutable, ty: self.ty()?, span });
+                if !self.is(")") {
+                    self.expect(",")?;
+                }
+            }
+            self.advance();
+            let mut ret = Type::unit();
+            if self.is("->") {
+                self.advance();
+                ret = self.ty()?;
+            }
+            let body = self.block()?;
+            functions.push(Function { name, params, ret, body });
+        }
+        Ok(functions)
+    }
+
+    fn ty(&mut self) -> Result<Type, Diagnostic> {
+        if self.is("&") {
+            self.advance();
+            let mutable = self.is("mut");
+            if mutable {
+                self.advance();
+            }
+            return Ok(Type::Ref(Box::new(self.ty()?), mutable));
+        }
+        if self.is("(") {
+            self.advance();
+            let mut types = Vec::new();
+            while !self.is(")") {
+                types.push(self.ty()?);
+                if !self.is(")") {
+                    self.expect(",")?;
+                }
+            }
+            self.advance();
+            return Ok(Type::Tuple(types));
+        }
+        let ty = match self.peek().map(|t| t.text) {
+            Some("String") => Type::String,
+            Some("str") => Type::Str,
+            Some("bool") => Type::Bool,
+            Some(name) if crate::literal::INTEGER_SUFFIXES.contains(&name) => Type::Int(name.to_string()),
+            _ => return Err(self.error("String, str, bool, an integer type, a reference or a tuple")),
+        };
+        self.advance();
+        Ok(ty)
+    }
+
+    fn block(&mut self) -> Result<Block, Diagnostic> {
+        self.expect("{")?;
+        let mut statements = Vec::new();
+        loop {
+            if self.is("}") {
+                let close = self.advance().span;
+                return Ok(Block { statements, tail: None, close });
+            }
+            if self.is(";") {
+                self.advance();
+            } else if self.is("let") {
+                statements.push(self.let_statement()?);
+            } else {
+                let expr = self.expr()?;
+                if self.is(";") {
+                    self.advance();
+                } else if self.is("}") {
+                    let close = self.advance().span;
+                    return Ok(Block { statements, tail: Some(Box::new(expr)), close });
+                } else if !matches!(expr.kind, ExprKind::Block(_)) {
+                    return Err(self.error("`;`"));
+                }
+                statements.push(Statement::Expr(expr));
+            }
+        }
+    }
+
+    fn let_statement(&mut self) -> Result<Statement, Diagnostic> {
+        self.expect("let")?;
+        let tuple = self.is("(");
+        if tuple {
+            self.advance();
+        }
+        let mut names = Vec::new();
+        loop {
+            let mutable = self.is("mut");
+            if mutable {
+                self.advance();
+            }
+            let (name, span) = self.ident()?;
+            names.push((name, mutable, span));
+            if !tuple || self.is(")") {
+                break;
+            }
+            self.expect(",")?;
+        }
+        if tuple {
+            self.expect(")")?;
+        }
+        let mut annotation = None;
+        if self.is(":") {
+            self.advance();
+            annotation = Some(self.ty()?);
+        }
+        let mut value = None;
+        if self.is("=") {
+            self.advance();
+            value = Some(self.expr()?);
+        }
+        self.expect(";")?;
+        Ok(Statement::Let { names, tuple, annotation, value })
+    }
+
+    fn expr(&mut self) -> Result<Expr, Diagnostic> {
+        if self.peek().is_some_and(|t| t.kind == TokenKind::Ident) && self.is_at(1, "=") {
+            let (name, _) = self.ident()?;
+            let span = self.advance().span;
+            let value = self.expr()?;
+            return Ok(Expr { kind: ExprKind::Assign(name, Box::new(value)), span });
+        }
+        self.unary()
+    }
+
+    fn unary(&mut self) -> Result<Expr, Diagnostic> {
+        if self.is("&") {
+            let span = self.advance().span;
+            let mutable = self.is("mut");
+            if mutable {
+                self.advance();
+            }
+            let operand = self.unary()?;
+            return Ok(Expr { kind: ExprKind::Borrow(mutable, Box::new(operand)), span });
+        }
+        let mut expr = self.primary()?;
+        while self.is(".") {
+            self.advance();
+            let (name, span) = self.ident()?;
+            let args = self.args()?;
+            expr = Expr { kind: ExprKind::Method(Box::new(expr), name, args), span };
+        }
+        Ok(expr)
+    }
+
+    // (a, b, c)
+    fn args(&mut self) -> Result<Vec<Expr>, Diagnostic> {
+        self.expect("(")?;
+        let mut args = Vec::new();
+        while !self.is(")") {
+            args.push(self.expr()?);
+            if !self.is(")") {
+                self.expect(",")?;
+            }
+        }
+        self.advance();
+        Ok(args)
+    }
+
+    fn primary(&mut self) -> Result<Expr, Diagnostic> {
+        let token = match self.peek() {
+            Some(&token) => token,
+            None => return Err(self.error("an expression")),
+        };
+        let span = token.span;
+        let kind = match (token.kind, token.text) {
+            (TokenKind::Number, _) => {
+                self.advance();
+                ExprKind::Int
+            }
+            (TokenKind::Str, _) | (TokenKind::RawStr, _) => {
+                self.advance();
+                ExprKind::Str
+            }
+            (TokenKind::Keyword, "true") | (TokenKind::Keyword, "false") => {
+                self.advance();
+                ExprKind::Bool
+            }
+            (TokenKind::Ident, "String") if self.is_at(1, "::") => {
+                self.advance();
+                self.advance();
+                let (name, _) = self.ident()?;
+                let mut args = self.args()?;
+                match (name.as_str(), args.len()) {
+                    ("from", 1) => ExprKind::StringFrom(Box::new(args.remove(0))),
+                    ("new", 0) => ExprKind::StringNew,
+                    _ => return Err(diagnostic(span, String::from("only String::from(s) and String::new() are supported"))),
+                }
+            }
+            (TokenKind::Ident, "println") if self.is_at(1, "!") => {
+                self.advance();
+                self.advance();
+                let mut args = self.args()?;
+                if args.is_empty() || !matches!(args[0].kind, ExprKind::Str) {
+                    return Err(diagnostic(span, String::from("println! needs a format string")));
+                }
+                args.remove(0);
+                ExprKind::Println(args)
+            }
+            (TokenKind::Ident, _) if self.is_at(1, "(") => {
+                let (name, _) = self.ident()?;
+                ExprKind::Call(name, self.args()?)
+            }
+            (TokenKind::Ident, _) => ExprKind::Var(self.ident()?.0),
+            (TokenKind::Punct, "(") => {
+                let mut items = self.args()?;
+                if items.len() == 1 && !self.tokens[self.pos - 2].text.ends_with(',') {
+                    // (x) is x
+                    return Ok(items.remove(0));
+                }
+                ExprKind::Tuple(items)
+            }
+            (TokenKind::Punct, "{") => ExprKind::Block(self.block()?),
+            _ => return Err(self.error("an expression")),
+        };
+        Ok(Expr { kind, span })
+    }
+}
+
+// Where a moved value goes, for the explanations.
+#[derive(Debug, Clone, PartialEq)]
+enum Into {
+    Binding(String),
+    Argument(String),
+    // argument for a &mut parameter, a &mut reference is reborrowed instead of moved
+    MutArgument(String),
+    Return(String),
+    Temporary,
+    // not moved, println! and methods borrow their receiver
+    Read,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum State {
+    // let x;
+    Uninit,
+    Init,
+    Moved { at: Span, into: Into },
+}
+
+struct Var {
+    name: String,
+    mutable: bool,
+    ty: Type,
+    state: State,
+    // the loans the value of the variable holds, when it is a reference
+    loans: Vec<usize>,
+    // the number of blocks around it
+    depth: usize,
+    param: bool,
+}
+
+// A borrow of owner, in use as long as one of its holders is used later.
+struct Loan {
+    owner: usize,
+    mutable: bool,
+    span: Span,
+    holders: Vec<usize>,
+}
+
+struct Value {
+    ty: Type,
+    loans: Vec<usize>,
+}
+
+impl Value {
+    fn of(ty: Type) -> Value {
+        Value { ty, loans: Vec::new() }
+    }
+}
+
+struct Checker<'p> {
+    functions: &'p HashMap<&'p str, &'p Function>,
+    function: &'p Function,
+    vars: Vec<Var>,
+    // the variables of every block, the innermost last
+    scopes: Vec<Vec<usize>>,
+    loans: Vec<Loan>,
+    // of every variable in the order of the source, from the first run
+    known_uses: Vec<Vec<Span>>,
+    uses: Vec<Vec<Span>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'p> Checker<'p> {
+    fn new(functions: &'p HashMap<&'p str, &'p Function>, function: &'p Function, known_uses: Vec<Vec<Span>>) -> Checker<'p> {
+        Checker {
+            functions,
+            function,
+            vars: Vec::new(),
+            scopes: Vec::new(),
+            loans: Vec::new(),
+            known_uses,
+            uses: Vec::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn run(mut self) -> Checker<'p> {
+        let function = self.function;
+        self.scopes.push(Vec::new());
+        for param in &function.params {
+            self.declare(&param.name, param.mutable, param.ty.clone(), State::Init, Vec::new());
+            self.vars.last_mut().unwrap().param = true;
+        }
+        let value = self.block(&function.body, Into::Return(function.name.clone()), true);
+        let span = function.body.tail.as_ref().map_or(function.body.close, |tail| tail.span);
+        self.expect_type(&function.ret, &value.ty, span);
+        self
+    }
+
+    fn report(&mut self, span: Span, message: String, notes: Vec<Note>, help: Option<String>) {
+        self.diagnostics.push(Diagnostic { notes, help, ..diagnostic(span, message) });
+    }
+
+    fn declare(&mut self, name: &str, mutable: bool, ty: Type, state: State, loans: Vec<usize>) {
+        let id = self.vars.len();
+        for &loan in &loans {
+            self.loans[loan].holders.push(id);
+        }
+        let depth = self.scopes.len();
+        self.vars.push(Var { name: name.to_string(), mutable, ty, state, loans, depth, param: false });
+        self.uses.push(Vec::new());
+        self.scopes.last_mut().unwrap().push(id);
+    }
+
+    // The latest variable of the name, a use of it.
+    fn resolve(&mut self, name: &str, span: Span) -> Option<usize> {
+        let found = self.scopes.iter().rev().flat_map(|scope| scope.iter().rev()).find(|&&id| self.vars[id].name == name);
+        match found {
+            Some(&id) => {
+                self.uses[id].push(span);
+                Some(id)
+            }
+            None => {
+                self.report(span, format!("cannot find value `{}` in this scope", name), Vec::new(), None);
+                None
+            }
+        }
+    }
+
+    // The first borrow of owner with a holder used after the position, the holder and that use.
+    fn live_loan(&self, owner: usize, at: usize) -> Option<(usize, usize, Span)> {
+        self.loans.iter().enumerate().filter(|(_, loan)| loan.owner == owner).find_map(|(id, loan)| {
+            loan.holders.iter().find_map(|&holder| {
+                let uses = self.known_uses.get(holder)?;
+                uses.iter().find(|span| span.start > at).map(|&span| (id, holder, span))
+            })
+        })
+    }
+
+    fn expect_type(&mut self, expected: &Type, found: &Type, span: Span) {
+        if expected.fits(found) {
+            return;
+        }
+        let help = match (expected, found) {
+            (Type::Ref(target, mutable), found) if target.fits(found) => {
+                Some(format!("borrow the value with {} instead of moving it", if *mutable { "&mut" } else { "&" }))
+            }
+            (expected, Type::Ref(target, _)) if expected.fits(target) => {
+                Some(String::from("a reference can't give away the value it points to, pass the value or a clone of it"))
+            }
+            _ => None,
+        };
+        self.report(span, format!("mismatched types: expected `{}`, found `{}`", expected, found), Vec::new(), help);
+    }
+
+    // Whether the variable has a value, borrow says if it is borrowed or used by value.
+    fn available(&mut self, id: usize, span: Span, borrow: bool) -> bool {
+        let var = &self.vars[id];
+        let (name, ty) = (var.name.clone(), var.ty.clone());
+        match var.state.clone() {
+            State::Init => true,
+            State::Uninit => {
+                self.report(span, format!("used binding `{}` isn't initialized", name), Vec::new(),
+                            Some(format!("assign a value to `{}` before it is used", name)));
+                false
+            }
+            State::Moved { at, into } => {
+                let (moved, help) = match into {
+                    Into::Binding(to) => (
+                        format!("value moved into `{}` here", to),
+                        format!("`{}` is a {}, which doesn't implement Copy, so the value moved to `{}` and `{}` \
+                                 can't be used anymore. Use `{}`, or `{}.clone()` to keep a copy in both",
+                                name, ty, to, name, to, name),
+                    ),
+                    Into::Argument(function) | Into::MutArgument(function) => (
+                        format!("value moved into `{}` here", function),
+                        format!("`{}` is a {}, which doesn't implement Copy, so calling `{}` moved the value into \
+                                 its parameter and `{}` owns it now. Let `{}` take a reference &{} if it only reads the value",
+                                name, ty, function, function, function, ty),
+                    ),
+                    _ => (
+                        String::from("value moved here"),
+                        format!("`{}` is a {}, which doesn't implement Copy, so its value was moved away", name, ty),
+                    ),
+                };
+                let message = format!("{} of moved value: `{}`", if borrow { "borrow" } else { "use" }, name);
+                self.report(span, message, vec![note(at, moved)], Some(help));
+                false
+            }
+        }
+    }
+
+    // The value is read in place or copied, which a &mut borrow in use doesn't allow.
+    fn read(&mut self, id: usize, span: Span, borrow: bool) {
+        if !self.available(id, span, borrow) {
+            return;
+        }
+        if let Some((loan, holder, later)) = self.live_loan(id, span.start) {
+            if self.loans[loan].mutable {
+                let (name, holder) = (self.vars[id].name.clone(), self.vars[holder].name.clone());
+                let message = if borrow {
+                    format!("cannot borrow `{}` as immutable because it is also borrowed as mutable", name)
+                } else {
+                    format!("cannot use `{}` because it was mutably borrowed", name)
+                };
+                let notes = vec![
+                    note(self.loans[loan].span, String::from("mutable borrow occurs here")),
+                    note(later, format!("mutable borrow later used here, by `{}`", holder)),
+                ];
+                let help = format!("while the &mut reference `{}` is in use, `{}` can only be reached through it. \
+                                    Use `{}` here, or move this below the last use of `{}`", holder, name, holder, holder);
+                self.report(span, message, notes, Some(help));
+            }
+        }
+    }
+
+    // A &mut borrow, needs let mut and no other borrow in use.
+    fn borrow_mut(&mut self, id: usize, span: Span) {
+        if !self.available(id, span, true) {
+            return;
+        }
+        let var = &self.vars[id];
+        let name = var.name.clone();
+        if !var.mutable {
+            let declaration = if var.param { format!("fn {}(mut {}: ...)", self.function.name, name) } else { format!("let mut {}", name) };
+            let help = format!("values can only be changed through variables declared as mutable, declare it with `{}`", declaration);
+            self.report(span, format!("cannot borrow `{}` as mutable, as it is not declared as mutable", name), Vec::new(), Some(help));
+            return;
+        }
+        if let Some((loan, holder, later)) = self.live_loan(id, span.start) {
+            let holder = self.vars[holder].name.clone();
+            let (message, kind, help) = if self.loans[loan].mutable {
+                (
+                    format!("cannot borrow `{}` as mutable more than once at a time", name),
+                    "first mutable borrow",
+                    format!("only one &mut reference to a value can be in use at a time, so two of them can't change \
+                             it in turns. Use `{}`, or borrow again after the last use of `{}`", holder, holder),
+                )
+            } else {
+                (
+                    format!("cannot borrow `{}` as mutable because it is also borrowed as immutable", name),
+                    "immutable borrow",
+                    format!("a value can't be changed while shared references to it are in use, their readers \
+                             don't expect it to change. Borrow it as mutable after the last use of `{}`", holder),
+                )
+            };
+            let notes = vec![
+                note(self.loans[loan].span, format!("{} occurs here", kind)),
+                note(later, format!("{} later used here, by `{}`", kind, holder)),
+            ];
+            self.report(span, message, notes, Some(help));
+        }
+    }
+
+    fn move_out(&mut self, id: usize, span: Span, into: Into) {
+        if !self.available(id, span, false) {
+            return;
+        }
+        if let Some((loan, holder, later)) = self.live_loan(id, span.start) {
+            let (name, holder) = (self.vars[id].name.clone(), self.vars[holder].name.clone());
+            let notes = vec![
+                note(self.loans[loan].span, format!("borrow of `{}` occurs here", name)),
+                note(later, format!("borrow later used here, by `{}`", holder)),
+            ];
+            let help = format!("`{}` would point to a value which moved away. Move it after the last use of `{}`, \
+                                or move a clone", holder, holder);
+            self.report(span, format!("cannot move out of `{}` because it is borrowed", name), notes, Some(help));
+        }
+        self.vars[id].state = State::Moved { at: span, into };
+    }
+
+    fn block(&mut self, block: &Block, into: Into, body: bool) -> Value {
+        self.scopes.push(Vec::new());
+        for statement in &block.statements {
+            self.statement(statement);
+        }
+        let value = match &block.tail {
+            Some(tail) => self.eval(tail, into),
+            None => Value::of(Type::unit()),
+        };
+
+        // the value must not borrow what is dropped now
+        for &loan in &value.loans {
+            let owner = &self.vars[self.loans[loan].owner];
+            let (name, span, function) = (owner.name.clone(), self.loans[loan].span, self.function);
+            if body {
+                let kind = if owner.param { "function parameter" } else { "local variable" };
+                let help = format!("`{}` is dropped when `{}` returns, the reference would point to freed memory. \
+                                    Return the {} itself to move it to the caller", name, function.name, owner.ty);
+                let notes = vec![note(block.close, format!("`{}` is dropped here", name))];
+                self.report(span, format!("cannot return reference to {} `{}`", kind, name), notes, Some(help));
+            } else if owner.depth == self.scopes.len() {
+                self.does_not_live_long_enough(loan, block.close, None);
+            }
+        }
+        // and neither may the variables outside of the block which are used later
+        for id in self.scopes.last().unwrap().clone() {
+            if self.vars[id].state == State::Init {
+                if let Some((loan, holder, later)) = self.live_loan(id, block.close.start) {
+                    self.does_not_live_long_enough(loan, block.close, Some((holder, later)));
+                }
+            }
+        }
+        self.scopes.pop();
+        value
+    }
+
+    fn does_not_live_long_enough(&mut self, loan: usize, close: Span, later: Option<(usize, Span)>) {
+        let name = self.vars[self.loans[loan].owner].name.clone();
+        let mut notes = vec![note(close, format!("`{}` dropped here while still borrowed", name))];
+        let user = match later {
+            Some((holder, later)) => {
+                let holder = self.vars[holder].name.clone();
+                notes.push(note(later, format!("borrow later used here, by `{}`", holder)));
+                format!("`{}`", holder)
+            }
+            None => String::from("the value of the block"),
+        };
+        let help = format!("`{}` is dropped at the end of its block while {} still refers to it. Declare `{}` \
+                            in the outer block", name, user, name);
+        self.report(self.loans[loan].span, format!("`{}` does not live long enough", name), notes, Some(help));
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Let { names, tuple, annotation, value } => {
+                let pattern: Vec<&str> = names.iter().map(|(name, ..)| name.as_str()).collect();
+                let pattern = if *tuple { format!("({})", pattern.join(", ")) } else { pattern.join("") };
+                let (value, state) = match value {
+                    Some(expr) => {
+                        let value = self.eval(expr, Into::Binding(pattern));
+                        if let Some(annotation) = annotation {
+                            self.expect_type(annotation, &value.ty, expr.span);
+                        }
+                        (value, State::Init)
+                    }
+                    None => (Value::of(annotation.clone().unwrap_or(Type::Unknown)), State::Uninit),
+                };
+                let types = match (&value.ty, *tuple) {
+                    (_, false) => vec![value.ty.clone()],
+                    (Type::Tuple(types), true) if types.len() == names.len() => types.clone(),
+                    (ty, true) => {
+                        if *ty != Type::Unknown {
+                            let message = format!("mismatched types: expected a tuple of {}, found `{}`", names.len(), ty);
+                            self.report(names[0].2, message, Vec::new(), None);
+                        }
+                        vec![Type::Unknown; names.len()]
+                    }
+                };
+                for ((name, mutable, _), ty) in names.iter().zip(types) {
+                    self.declare(name, *mutable, ty, state.clone(), value.loans.clone());
+                }
+            }
+            Statement::Expr(expr) => {
+                self.eval(expr, Into::Temporary);
+            }
+        }
+    }
+
+    fn eval(&mut self, expr: &Expr, into: Into) -> Value {
+        let span = expr.span;
+        match &expr.kind {
+            ExprKind::Int => Value::of(Type::Int(String::from("i32"))),
+            ExprKind::Str => Value::of(Type::Ref(Box::new(Type::Str), false)),
+            ExprKind::Bool => Value::of(Type::Bool),
+            ExprKind::Var(name) => {
+                let id = match self.resolve(name, span) {
+                    Some(id) => id,
+                    None => return Value::of(Type::Unknown),
+                };
+                let ty = self.vars[id].ty.clone();
+                let reborrow = matches!((&into, &ty), (Into::MutArgument(_), Type::Ref(_, true)));
+                if into == Into::Read || reborrow {
+                    self.read(id, span, true);
+                } else if ty.is_copy() {
+                    self.read(id, span, false);
+                } else {
+                    self.move_out(id, span, into);
+                }
+                Value { ty, loans: self.vars[id].loans.clone() }
+            }
+            ExprKind::Borrow(mutable, operand) => {
+                let name = match &operand.kind {
+                    ExprKind::Var(name) => name,
+                    // a borrow of a temporary value
+                    _ => {
+                        let value = self.eval(operand, Into::Temporary);
+                        return Value { ty: Type::Ref(Box::new(value.ty), *mutable), loans: value.loans };
+                    }
+                };
+                let id = match self.resolve(name, operand.span) {
+                    Some(id) => id,
+                    None => return Value::of(Type::Unknown),
+                };
+                if *mutable {
+                    self.borrow_mut(id, span);
+                } else {
+                    self.read(id, span, true);
+                }
+                self.loans.push(Loan { owner: id, mutable: *mutable, span, holders: Vec::new() });
+                let mut loans = vec![self.loans.len() - 1];
+                loans.extend(&self.vars[id].loans);
+                Value { ty: Type::Ref(Box::new(self.vars[id].ty.clone()), *mutable), loans }
+            }
+            ExprKind::Call(name, args) => {
+                let function = match self.functions.get(name.as_str()) {
+                    Some(&function) => function,
+                    None => {
+                        self.report(span, format!("cannot find function `{}` in this file", name), Vec::new(), None);
+                        for arg in args {
+                            self.eval(arg, Into::Argument(name.clone()));
+                        }
+                        return Value::of(Type::Unknown);
+                    }
+                };
+                if args.len() != function.params.len() {
+                    let message = format!("`{}` takes {} arguments but {} were supplied", name, function.params.len(), args.len());
+                    self.report(span, message, Vec::new(), None);
+                }
+                // a returned reference borrows from the arguments which are references
+                let mut loans = Vec::new();
+                for (i, arg) in args.iter().enumerate() {
+                    let into = match function.params.get(i) {
+                        Some(param) if matches!(param.ty, Type::Ref(_, true)) => Into::MutArgument(name.clone()),
+                        _ => Into::Argument(name.clone()),
+                    };
+                    let value = self.eval(arg, into);
+                    if let Some(param) = function.params.get(i) {
+                        self.expect_type(&param.ty, &value.ty, arg.span);
+                    }
+                    if value.ty.has_ref() {
+                        loans.extend(value.loans);
+                    }
+                }
+                if !function.ret.has_ref() {
+                    loans.clear();
+                }
+                Value { ty: function.ret.clone(), loans }
+            }
+            ExprKind::StringFrom(arg) => {
+                let value = self.eval(arg, Into::Argument(String::from("String::from")));
+                self.expect_type(&Type::Ref(Box::new(Type::Str), false), &value.ty, arg.span);
+                Value::of(Type::String)
+            }
+            ExprKind::StringNew => Value::of(Type::String),
+            ExprKind::Method(receiver, name, args) => self.method(receiver, name, args, span),
+            ExprKind::Tuple(items) => {
+                let mut types = Vec::new();
+                let mut loans = Vec::new();
+                for item in items {
+                    let value = self.eval(item, into.clone());
+                    types.push(value.ty);
+                    loans.extend(value.loans);
+                }
+                Value { ty: Type::Tuple(types), loans }
+            }
+            ExprKind::Block(block) => self.block(block, into, false),
+            ExprKind::Println(args) => {
+                for arg in args {
+                    self.eval(arg, Into::Read);
+                }
+                Value::of(Type::unit())
+            }
+            ExprKind::Assign(name, value) => {
+                let value = self.eval(value, Into::Binding(name.clone()));
+                if let Some(id) = self.resolve(name, span) {
+                    self.assign(id, value, span);
+                }
+                Value::of(Type::unit())
+            }
+        }
+    }
+
+    fn assign(&mut self, id: usize, value: Value, span: Span) {
+        let var = &self.vars[id];
+        let name = var.name.clone();
+        if !var.mutable && var.state != State::Uninit {
+            let help = format!("declare it with `let mut {}` to assign it again", name);
+            self.report(span, format!("cannot assign twice to immutable variable `{}`", name), Vec::new(), Some(help));
+            return;
+        }
+        if var.state == State::Init {
+            if let Some((loan, holder, later)) = self.live_loan(id, span.start) {
+                let holder = self.vars[holder].name.clone();
+                let notes = vec![
+                    note(self.loans[loan].span, format!("`{}` is borrowed here", name)),
+                    note(later, format!("borrow later used here, by `{}`", holder)),
+                ];
+                let help = format!("the old value would be dropped while `{}` still refers to it", holder);
+                self.report(span, format!("cannot assign to `{}` because it is borrowed", name), notes, Some(help));
+            }
+        }
+        let ty = self.vars[id].ty.clone();
+        self.expect_type(&ty, &value.ty, span);
+        for loan in &mut self.loans {
+            loan.holders.retain(|&holder| holder != id);
+        }
+        for &loan in &value.loans {
+            self.loans[loan].holders.push(id);
+        }
+        let var = &mut self.vars[id];
+        if var.ty == Type::Unknown {
+            var.ty = value.ty;
+        }
+        var.state = State::Init;
+        var.loans = value.loans;
+    }
+
+    // clone and len read the receiver, push_str changes it.
+    fn method(&mut self, receiver: &Expr, name: &str, args: &[Expr], span: Span) -> Value {
+        let ty = match &receiver.kind {
+            ExprKind::Var(var) => match self.resolve(var, receiver.span) {
+                Some(id) => {
+                    let ty = self.vars[id].ty.clone();
+                    match (name, &ty) {
+                        ("push_str", Type::Ref(_, false)) => {
+                            let message = format!("cannot borrow `*{}` as mutable, as it is behind a `&` reference", var);
+                            let help = format!("`{}` is a shared reference, change its type to &mut {} to change the value through it",
+                                               var, deref(&ty));
+                            self.report(receiver.span, message, Vec::new(), Some(help));
+                        }
+                        ("push_str", Type::Ref(_, true)) => {
+                            self.available(id, receiver.span, true);
+                        }
+                        ("push_str", _) => self.borrow_mut(id, receiver.span),
+                        _ => self.read(id, receiver.span, true),
+                    }
+                    ty
+                }
+                None => Type::Unknown,
+            },
+            _ => self.eval(receiver, Into::Temporary).ty,
+        };
+        let expected = match name {
+            "clone" | "len" => 0,
+            "push_str" => 1,
+            _ => {
+                self.report(span, format!("no method named `{}`, only clone, len and push_str are supported", name), Vec::new(), None);
+                return Value::of(Type::Unknown);
+            }
+        };
+        if args.len() != expected {
+            self.report(span, format!("`{}` takes {} arguments but {} were supplied", name, expected, args.len()), Vec::new(), None);
+        }
+        for arg in args {
+            let value = self.eval(arg, Into::Argument(name.to_string()));
+            self.expect_type(&Type::Ref(Box::new(Type::Str), false), &value.ty, arg.span);
+        }
+        match name {
+            "clone" => Value::of(deref(&ty)),
+            "len" => Value::of(Type::Int(String::from("usize"))),
+            _ => Value::of(Type::unit()),
+        }
+    }
+}
+
+fn deref(ty: &Type) -> Type {
+    match ty {
+        Type::Ref(target, _) => (**target).clone(),
+        ty => ty.clone(),
+    }
+}
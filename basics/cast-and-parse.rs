@@ -1,5 +1,10 @@
 // cast-and-parse.rs
 
+mod convert;
+mod literal;
+
+use convert::Rounding;
+
 fn main() {
     {
         let decimal = 65.4321_f32;
@@ -27,6 +32,21 @@ fn main() {
         //println!("  -1 as a u8 is : {}", (-1i8) as u8);
     }
 
+    {
+        // convert.rs tells what `as` would lose instead of losing it silently
+        let decimal = 65.4321_f32;
+        match convert::checked::<f32, u8>(decimal) {
+            Ok(integer) => println!("{} is exactly {}", decimal, integer),
+            Err(e) => println!("error: {}", e),
+        }
+        println!("rounded to nearest u8: {:?}", convert::rounded::<f32, u8>(decimal, Rounding::NearestEven));
+        println!("1000 checked as u8: {:?}", convert::checked::<i32, u8>(1000).map_err(|e| e.to_string()));
+        println!("1000 saturated as u8: {:?}", convert::saturating::<i32, u8>(1000, Rounding::TowardZero));
+        // the same bits as `as`, but spelled out
+        println!("1000 wrapped as u8: {:?}", convert::wrapping::<i32, u8>(1000, Rounding::TowardZero));
+        println!("  -1 wrapped as u8: {:?}", convert::wrapping::<i8, u8>(-1, Rounding::TowardZero));
+    }
+
     {
         // we should parse from
         let decimal = "65.4321".parse::<f32>().unwrap();
@@ -35,5 +55,13 @@ fn main() {
         println!("value {}", decimal);
         let decimal = String::from("65.4321")[..5].parse::<f32>().unwrap();
         println!("value {}", decimal);
+
+        // literal.rs also knows the syntax of Rust literals, the suffix gives the type
+        for text in &["0xffffffffu32", "-2_147_483_648i32", "1e-3_f64", "256u8"] {
+            match literal::parse(text) {
+                Ok(value) => println!("{} is {} of type {}", text, value, value.type_name()),
+                Err(e) => println!("{} is invalid at {}", text, e),
+            }
+        }
     }
 }
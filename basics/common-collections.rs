@@ -70,9 +70,10 @@ fn main() {
 
         // Slicing Strings
         let hello = "Здравствуйте";
-        let s = &hello[0..4];
+        let s = &hello[0..4]; // &hello[0..3] panics, see unicode-slicing.rs for slicing without panic
         println!("{}", s);
 
+        // chars are not letters, "स्ते" is printed as four of them
         for c in "नमस्ते".chars() {
             println!("{}", c);
         }
@@ -122,6 +123,7 @@ fn main() {
         scores.entry(String::from("Blue")).or_insert(50);
         println!("{:?}", scores);
 
+        // word-frequency.rs turns this into a complete tool
         let text = "hello world wonderful world";
         let mut map = HashMap::new();
         for word in text.split_whitespace() {
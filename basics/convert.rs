@@ -0,0 +1,387 @@
+// convert.rs
+// Conversions between all integer and float types without the silent surprises of `as`, see
+// cast-and-parse.rs. Every conversion says what happens when the value doesn't fit:
+//
+// checked      the value must be represented exactly, otherwise an error
+// rounded      the value is rounded by the given rule, an error when it is out of range
+// saturating   rounded and clamped to the smallest or largest value of the type
+// wrapping     rounded and only the low bits are kept, like `1000 as u8`, only to integers
+//
+// NaN is an error in all of them, `as` turns it to 0 silently.
+#![allow(dead_code)]
+
+use std::cmp::Ordering;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rounding {
+    // 2.7 -> 2, -2.7 -> -2, like `as`
+    TowardZero,
+    // 2.7 -> 2, -2.7 -> -3
+    Down,
+    // 2.3 -> 3, -2.3 -> -2
+    Up,
+    // nearest, 2.5 -> 2 and 3.5 -> 4, like float operations
+    NearestEven,
+    // nearest, 2.5 -> 3 and -2.5 -> -3, like f64::round
+    NearestAway,
+}
+
+impl Rounding {
+    pub const ALL: [Rounding; 5] = [
+        Rounding::TowardZero,
+        Rounding::Down,
+        Rounding::Up,
+        Rounding::NearestEven,
+        Rounding::NearestAway,
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConversionError {
+    // value is smaller than the minimum or larger than the maximum of the type
+    Overflow { value: String, target: &'static str, range: String },
+    NotANumber { target: &'static str },
+    // float has a fractional part and the target is an integer
+    FractionLost { value: String, target: &'static str },
+    // integer or float doesn't have an exact float of the target type
+    PrecisionLost { value: String, target: &'static str },
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConversionError::Overflow { value, target, range } => {
+                write!(f, "{} is out of range of {} ({})", value, target, range)
+            }
+            ConversionError::NotANumber { target } => write!(f, "NaN can't be converted to {}", target),
+            ConversionError::FractionLost { value, target } => {
+                write!(f, "{} has a fractional part which {} can't hold", value, target)
+            }
+            ConversionError::PrecisionLost { value, target } => {
+                write!(f, "{} can't be represented exactly as {}", value, target)
+            }
+        }
+    }
+}
+
+// Any value of any numeric type, exactly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Int(i128),
+    // only values larger than i128::MAX
+    UInt(u128),
+    Float(f64),
+}
+
+impl Value {
+    fn from_u128(n: u128) -> Value {
+        if n <= i128::MAX as u128 { Value::Int(n as i128) } else { Value::UInt(n) }
+    }
+
+    // Sign and magnitude of an integer value.
+    fn sign_magnitude(self) -> (bool, u128) {
+        match self {
+            Value::Int(i) => (i < 0, i.unsigned_abs()),
+            Value::UInt(u) => (false, u),
+            Value::Float(_) => unreachable!("float has no integer magnitude"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Integer { bits: u32, signed: bool },
+    Float { bits: u32 },
+}
+
+pub trait Numeric: Copy + fmt::Display {
+    const NAME: &'static str;
+    const KIND: Kind;
+    // the smallest and largest finite values
+    const MIN: Self;
+    const MAX: Self;
+
+    fn to_value(self) -> Value;
+    // The value must be in range, an integer for integer types and an exact float for float types.
+    fn from_value(value: Value) -> Self;
+}
+
+pub trait Integer: Numeric {}
+
+macro_rules! integer {
+    ($($t:ty),*) => {$(
+        impl Numeric for $t {
+            const NAME: &'static str = stringify!($t);
+            const KIND: Kind = Kind::Integer { bits: <$t>::BITS, signed: <$t>::MIN != 0 };
+            const MIN: Self = <$t>::MIN;
+            const MAX: Self = <$t>::MAX;
+
+            fn to_value(self) -> Value {
+                if <$t>::MIN == 0 { Value::from_u128(self as u128) } else { Value::Int(self as i128) }
+            }
+
+            fn from_value(value: Value) -> Self {
+                match value {
+                    Value::Int(i) => i as $t,
+                    Value::UInt(u) => u as $t,
+                    Value::Float(f) => f as $t,
+                }
+            }
+        }
+
+        impl Integer for $t {}
+    )*};
+}
+
+integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+macro_rules! float {
+    ($($t:ty),*) => {$(
+        impl Numeric for $t {
+            const NAME: &'static str = stringify!($t);
+            const KIND: Kind = Kind::Float { bits: std::mem::size_of::<$t>() as u32 * 8 };
+            const MIN: Self = <$t>::MIN;
+            const MAX: Self = <$t>::MAX;
+
+            fn to_value(self) -> Value {
+                Value::Float(self as f64)
+            }
+
+            fn from_value(value: Value) -> Self {
+                match value {
+                    Value::Int(i) => i as $t,
+                    Value::UInt(u) => u as $t,
+                    Value::Float(f) => f as $t,
+                }
+            }
+        }
+    )*};
+}
+
+float!(f32, f64);
+
+// The value must be exactly the same in the target type.
+pub fn checked<T: Numeric, U: Numeric>(value: T) -> Result<U, ConversionError> {
+    match round::<U>(value.to_value(), Rounding::TowardZero) {
+        Outcome::Exact(v) => Ok(U::from_value(v)),
+        Outcome::Inexact(_) => Err(match U::KIND {
+            Kind::Integer { .. } => ConversionError::FractionLost { value: value.to_string(), target: U::NAME },
+            // rounding toward zero stops at the largest float instead of infinity
+            Kind::Float { .. } if beyond_float_max::<U>(value.to_value()) => overflow::<T, U>(value),
+            Kind::Float { .. } => ConversionError::PrecisionLost { value: value.to_string(), target: U::NAME },
+        }),
+        Outcome::TooLow | Outcome::TooHigh => Err(overflow::<T, U>(value)),
+        Outcome::NotANumber => Err(ConversionError::NotANumber { target: U::NAME }),
+    }
+}
+
+pub fn rounded<T: Numeric, U: Numeric>(value: T, rounding: Rounding) -> Result<U, ConversionError> {
+    match round::<U>(value.to_value(), rounding) {
+        Outcome::Exact(v) | Outcome::Inexact(v) => Ok(U::from_value(v)),
+        Outcome::TooLow | Outcome::TooHigh => Err(overflow::<T, U>(value)),
+        Outcome::NotANumber => Err(ConversionError::NotANumber { target: U::NAME }),
+    }
+}
+
+// Infinity stays infinity when the target is a float.
+pub fn saturating<T: Numeric, U: Numeric>(value: T, rounding: Rounding) -> Result<U, ConversionError> {
+    match round::<U>(value.to_value(), rounding) {
+        Outcome::Exact(v) | Outcome::Inexact(v) => Ok(U::from_value(v)),
+        Outcome::TooLow => Ok(U::MIN),
+        Outcome::TooHigh => Ok(U::MAX),
+        Outcome::NotANumber => Err(ConversionError::NotANumber { target: U::NAME }),
+    }
+}
+
+// Infinity can't be wrapped, it is an overflow.
+pub fn wrapping<T: Numeric, U: Integer>(value: T, rounding: Rounding) -> Result<U, ConversionError> {
+    match (round::<U>(value.to_value(), rounding), value.to_value()) {
+        (Outcome::Exact(v), _) | (Outcome::Inexact(v), _) => Ok(U::from_value(v)),
+        (Outcome::NotANumber, _) => Err(ConversionError::NotANumber { target: U::NAME }),
+        (_, Value::Float(f)) if f.is_infinite() => Err(overflow::<T, U>(value)),
+        // `as` between integers keeps the low bits
+        (_, Value::Float(f)) => Ok(U::from_value(Value::UInt(low_bits(round_float(f, rounding))))),
+        (_, v) => Ok(U::from_value(v)),
+    }
+}
+
+fn overflow<T: Numeric, U: Numeric>(value: T) -> ConversionError {
+    ConversionError::Overflow {
+        value: value.to_string(),
+        target: U::NAME,
+        range: format!("{} to {}", U::MIN, U::MAX),
+    }
+}
+
+// The magnitude of the value is larger than the largest finite value of the float type U.
+fn beyond_float_max<U: Numeric>(value: Value) -> bool {
+    let max = match U::MAX.to_value() {
+        Value::Float(max) => max,
+        _ => unreachable!("U is a float type"),
+    };
+    match value {
+        Value::Float(f) => f.abs() > max,
+        // an f32 is an exact u128, every integer is smaller than the largest f64
+        _ => max < 2f64.powi(128) && value.sign_magnitude().1 > max as u128,
+    }
+}
+
+enum Outcome {
+    // value for U::from_value
+    Exact(Value),
+    Inexact(Value),
+    TooLow,
+    TooHigh,
+    NotANumber,
+}
+
+fn round<U: Numeric>(value: Value, rounding: Rounding) -> Outcome {
+    match (value, U::KIND) {
+        (Value::Float(f), _) if f.is_nan() => Outcome::NotANumber,
+        (Value::Float(f), Kind::Integer { bits, signed }) => float_to_integer(f, rounding, bits, signed),
+        (_, Kind::Integer { bits, signed }) => integer_to_integer(value, bits, signed),
+        (_, Kind::Float { bits }) => to_float(value, rounding, bits),
+    }
+}
+
+fn integer_to_integer(value: Value, bits: u32, signed: bool) -> Outcome {
+    let (min, max) = if signed {
+        (i128::MIN >> (128 - bits), (i128::MAX >> (128 - bits)) as u128)
+    } else {
+        (0, u128::MAX >> (128 - bits))
+    };
+    match value {
+        Value::Int(i) if i < min => Outcome::TooLow,
+        Value::Int(i) if i >= 0 && i as u128 > max => Outcome::TooHigh,
+        Value::UInt(u) if u > max => Outcome::TooHigh,
+        _ => Outcome::Exact(value),
+    }
+}
+
+fn float_to_integer(f: f64, rounding: Rounding, bits: u32, signed: bool) -> Outcome {
+    let rounded = round_float(f, rounding);
+    // powers of two are exact floats, unlike the maximum of the type
+    let (low, high) = if signed {
+        (-(2f64.powi(bits as i32 - 1)), 2f64.powi(bits as i32 - 1))
+    } else {
+        (0.0, 2f64.powi(bits as i32))
+    };
+    if rounded < low {
+        return Outcome::TooLow;
+    }
+    if rounded >= high {
+        return Outcome::TooHigh;
+    }
+    let value = if rounded < 0.0 { Value::Int(rounded as i128) } else { Value::from_u128(rounded as u128) };
+    if rounded == f { Outcome::Exact(value) } else { Outcome::Inexact(value) }
+}
+
+fn round_float(f: f64, rounding: Rounding) -> f64 {
+    match rounding {
+        Rounding::TowardZero => f.trunc(),
+        Rounding::Down => f.floor(),
+        Rounding::Up => f.ceil(),
+        Rounding::NearestEven => f.round_ties_even(),
+        Rounding::NearestAway => f.round(),
+    }
+}
+
+// Integral float modulo 2^128, in two's complement for negative values.
+fn low_bits(f: f64) -> u128 {
+    // f = mantissa * 2^exponent with 53 bits of mantissa
+    let bits = f.abs().to_bits();
+    let exponent = ((bits >> 52) & 0x7ff) as i32;
+    if exponent == 0 {
+        // zero or subnormal, a rounded subnormal is zero
+        return 0;
+    }
+    let mantissa = (bits & ((1 << 52) - 1)) | (1 << 52);
+    let shift = exponent - 1075;
+    let magnitude = if shift >= 0 {
+        (mantissa as u128).checked_shl(shift as u32).unwrap_or(0)
+    } else {
+        (mantissa >> -shift) as u128
+    };
+    if f < 0.0 { magnitude.wrapping_neg() } else { magnitude }
+}
+
+fn to_float(value: Value, rounding: Rounding, bits: u32) -> Outcome {
+    // the nearest float of the target type, ties to even, and its neighbours
+    let nearest = |v: Value| -> f64 {
+        match (v, bits) {
+            (Value::Int(i), 32) => i as f32 as f64,
+            (Value::UInt(u), 32) => u as f32 as f64,
+            (Value::Float(f), 32) => f as f32 as f64,
+            (Value::Int(i), _) => i as f64,
+            (Value::UInt(u), _) => u as f64,
+            (Value::Float(f), _) => f,
+        }
+    };
+    let next_up = |f: f64| if bits == 32 { (f as f32).next_up() as f64 } else { f.next_up() };
+    let next_down = |f: f64| if bits == 32 { (f as f32).next_down() as f64 } else { f.next_down() };
+
+    let near = nearest(value);
+    let order = compare(value, near);
+    if order == Ordering::Equal {
+        return Outcome::Exact(Value::Float(near));
+    }
+    let (low, high) = if order == Ordering::Greater { (near, next_up(near)) } else { (next_down(near), near) };
+    let positive = high > 0.0;
+    let chosen = match rounding {
+        Rounding::TowardZero => if positive { low } else { high },
+        Rounding::Down => low,
+        Rounding::Up => high,
+        Rounding::NearestEven => near,
+        Rounding::NearestAway => match distance(value, low).cmp(&distance(value, high)) {
+            Ordering::Equal => if positive { high } else { low },
+            _ => near,
+        },
+    };
+    match chosen {
+        f if f == f64::INFINITY => Outcome::TooHigh,
+        f if f == f64::NEG_INFINITY => Outcome::TooLow,
+        f => Outcome::Inexact(Value::Float(f)),
+    }
+}
+
+// Order of the value and a float which is the nearest float to it.
+fn compare(value: Value, f: f64) -> Ordering {
+    match value {
+        Value::Float(v) => v.partial_cmp(&f).unwrap(),
+        _ => {
+            // the float has the same sign and it is an integer, if the value isn't exact it is large
+            let (negative, magnitude) = value.sign_magnitude();
+            let order = if f.abs() >= 2f64.powi(128) {
+                Ordering::Less
+            } else {
+                magnitude.cmp(&(f.abs() as u128))
+            };
+            if negative { order.reverse() } else { order }
+        }
+    }
+}
+
+// Distance of the value and one of the two floats around it, only to find out whether it is
+// exactly in the middle of them.
+fn distance(value: Value, f: f64) -> u128 {
+    if f.is_infinite() {
+        return u128::MAX;
+    }
+    match value {
+        Value::Float(v) => {
+            // v is between two neighbouring f32 floats, their f64 difference is exact and the bits of
+            // positive floats are ordered the same way as the numbers
+            (v - f).abs().to_bits() as u128
+        }
+        _ => {
+            let (_, magnitude) = value.sign_magnitude();
+            let f = f.abs();
+            if f >= 2f64.powi(128) {
+                u128::MAX - magnitude + 1
+            } else {
+                (f as u128).abs_diff(magnitude)
+            }
+        }
+    }
+}
@@ -0,0 +1,145 @@
+// crash_report.rs
+// Panic hook which writes a crash report to a local file and prints a short message instead of
+// the raw panic output. Install it with one call at the start of main:
+//
+//     mod crash_report;
+//
+//     fn main() {
+//         crash_report::install();
+//         ...
+//     }
+#![allow(dead_code)]
+
+use std::backtrace::Backtrace;
+use std::env;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::{ErrorKind, Write};
+use std::panic;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Everything we know about a panic at the moment it happened.
+#[derive(Debug)]
+pub struct CrashReport {
+    pub program: String,
+    pub pid: u32,
+    pub message: String,
+    pub location: Option<String>,
+    pub args: Vec<String>,
+    pub timestamp: u64,
+    pub backtrace: String,
+}
+
+impl CrashReport {
+    fn from_panic(info: &PanicHookInfo) -> CrashReport {
+        let payload = info.payload();
+        let message = match payload.downcast_ref::<&str>() {
+            Some(s) => s.to_string(),
+            None => match payload.downcast_ref::<String>() {
+                Some(s) => s.clone(),
+                None => String::from("Box<dyn Any>"),
+            },
+        };
+
+        CrashReport {
+            program: program_name(),
+            pid: process::id(),
+            message,
+            location: info.location().map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+            args: env::args().collect(),
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            backtrace: Backtrace::force_capture().to_string(),
+        }
+    }
+
+    // file name of the report, unique per process and second
+    pub fn file_name(&self) -> String {
+        format!("{}-crash-{}-{}.txt", self.program, self.timestamp, self.pid)
+    }
+
+    // An existing report is never overwritten, e.g. the one of another thread of the same
+    // process, the name gets a number instead.
+    pub fn write_to(&self, dir: &Path) -> io::Result<PathBuf> {
+        let name = self.file_name();
+        let stem = name.trim_end_matches(".txt");
+        for n in 1.. {
+            let path = if n == 1 { dir.join(&name) } else { dir.join(format!("{}-{}.txt", stem, n)) };
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    file.write_all(self.to_string().as_bytes())?;
+                    return Ok(path);
+                }
+                Err(ref e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        unreachable!()
+    }
+}
+
+impl fmt::Display for CrashReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "program:   {}", self.program)?;
+        writeln!(f, "pid:       {}", self.pid)?;
+        writeln!(f, "timestamp: {}", format_timestamp(self.timestamp))?;
+        writeln!(f, "message:   {}", self.message)?;
+        writeln!(f, "location:  {}", self.location.as_deref().unwrap_or("unknown"))?;
+        writeln!(f, "args:      {:?}", self.args)?;
+        writeln!(f, "backtrace:")?;
+        write!(f, "{}", self.backtrace)
+    }
+}
+
+// Install the hook, reports are written to the current directory.
+pub fn install() {
+    install_in(".");
+}
+
+// Install the hook, reports are written to the given directory.
+pub fn install_in<P: Into<PathBuf>>(dir: P) {
+    let dir = dir.into();
+    panic::set_hook(Box::new(move |info| {
+        let report = CrashReport::from_panic(info);
+        eprintln!("{} crashed: {}", report.program, report.message);
+        match report.write_to(&dir) {
+            Ok(path) => eprintln!("A crash report was written to {}", path.display()),
+            // we can't lose the report, so print it all
+            Err(e) => eprintln!("Unable to write crash report ({}):\n{}", e, report),
+        }
+    }));
+}
+
+fn program_name() -> String {
+    env::args()
+        .next()
+        .as_ref()
+        .and_then(|arg| Path::new(arg).file_stem())
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| String::from("program"))
+}
+
+// Seconds since the unix epoch as UTC date and time, e.g. 2020-12-09T18:30:00Z.
+pub fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64;
+    let seconds = timestamp % 86_400;
+
+    // civil from days, http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, seconds / 3_600, seconds % 3_600 / 60, seconds % 60
+    )
+}
@@ -1,17 +1,63 @@
 // data-types.rs
+//
+// rustc data-types.rs
+// ./data-types              every numeric type, then the other scalar and compound types
+// ./data-types 300          the types which can hold 300, also -1, 0xffu8, 2.5 or 1e39
+
+use std::mem;
+use std::process;
+
+mod args;
+mod convert;
+mod literal;
+
+use literal::Literal;
 
 fn main() {
+    let matches = args::Command::new("data-types")
+        .about("Show the primitive data types, or find the types which can hold a value.")
+        .optional("VALUE", "Literal like 300, -1, 0xffu8 or 2.5, see literal.rs")
+        .parse();
+
+    match matches.value("VALUE") {
+        Some(text) => fits(text),
+        None => tour(),
+    }
+}
+
+fn tour() {
     // Scalar types - (single value)
 
-    // Integer
-    println!("integer 8-bit   [signed i8    min {}, max {}], [unsigned u8    min 0, max {}]", -128i8, 127i8, 255u8);
-    println!("integer 16-bit  [signed i16   min {}, max {}], [unsigned u16   min 0, max {}]", -32_768i16, 32_767i16, 65_535u16);
-    println!("integer 32-bit  [signed i32   min {}, max {}], [unsigned u32   min 0, max {}]", -2_147_483_648i32, 0b0111_1111_1111_1111_1111_1111_1111_1111i32, 0xffffffffu32);
-    println!("integer 64-bit  [signed i64   min {}, max {}], [unsigned u64   min 0, max {}]", std::i64::MIN, std::i64::MAX, std::u64::MAX);
-    println!("integer 128-bit [signed i128  min {}, max {}], [unsigned u128  min 0, max {}]", std::i128::MIN, std::i128::MAX, std::u128::MAX);
-    println!("integer arch    [signed isize min {}, max {}], [unsigned usize min 0, max {}]", std::isize::MIN, std::usize::MAX, std::usize::MAX);
+    // Integer, the limits are the MIN and MAX constants of each type
+    println!("{:<6} {:>4} {:>5} {:>40} {:>40}", "type", "size", "align", "min", "max");
+    macro_rules! integers {
+        ($($t:ty),*) => {$(
+            println!("{:<6} {:>4} {:>5} {:>40} {:>40}",
+                     stringify!($t), mem::size_of::<$t>(), mem::align_of::<$t>(), <$t>::MIN, <$t>::MAX);
+        )*};
+    }
+    // isize and usize have the size of a pointer of the architecture
+    integers!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+    // the same values written as literals
+    println!("i32 min {}, max {}, u32 max {}", -2_147_483_648i32, 0b0111_1111_1111_1111_1111_1111_1111_1111i32, 0xffffffffu32);
 
     // Floating-Point IEEE-754 standard
+    macro_rules! floats {
+        ($($t:ident),*) => {$(
+            println!();
+            println!("{} size {}, align {}", stringify!($t), mem::size_of::<$t>(), mem::align_of::<$t>());
+            println!("  max        {:e} (min is -max)", <$t>::MAX);
+            // the distance from 1.0 to the next float
+            println!("  epsilon    {:e}", <$t>::EPSILON);
+            println!("  precision  {} bits of mantissa, {} significant decimal digits", <$t>::MANTISSA_DIGITS, <$t>::DIGITS);
+            println!("  normal     {:e} to {:e}", <$t>::MIN_POSITIVE, <$t>::MAX);
+            // smaller numbers lose precision until the smallest one has a single bit
+            println!("  subnormal  {:e} to {:e}", $t::from_bits(1), <$t>::MIN_POSITIVE.next_down());
+            println!("  exponent   2^{} to 2^{}", <$t>::MIN_EXP - 1, <$t>::MAX_EXP - 1);
+        )*};
+    }
+    floats!(f32, f64);
+    println!();
     let float: f32 = 1.0; // 32 bit float must be defined
     println!("float 32-bit {:.1}", float);
     let float = 2.0; // 64 bit float is default
@@ -64,3 +110,41 @@ fn main() {
     let a: NanoSecond = 10;
     println!("NanoSecond {}", a);
 }
+
+// Every type which holds exactly the value, and the smallest signed, unsigned and float ones.
+fn fits(text: &str) {
+    let value = literal::parse_widest(text).unwrap_or_else(|e| {
+        eprintln!("data-types: {}: {}", text, e);
+        process::exit(2);
+    });
+    println!("{}", value);
+    let mut smallest: [Option<&str>; 3] = [None; 3];
+    macro_rules! types {
+        ($($t:ty),*) => {$(
+            match value.to::<$t>() {
+                Ok(_) => {
+                    println!("  {:<6} yes", stringify!($t));
+                    let kind = match <$t as convert::Numeric>::KIND {
+                        convert::Kind::Integer { signed: true, .. } => 0,
+                        convert::Kind::Integer { signed: false, .. } => 1,
+                        convert::Kind::Float { .. } => 2,
+                    };
+                    // isize and usize aren't the smallest, they depend on the architecture
+                    if !stringify!($t).ends_with("size") {
+                        smallest[kind] = smallest[kind].or(Some(stringify!($t)));
+                    }
+                }
+                Err(e) => println!("  {:<6} no, {}", stringify!($t), e),
+            }
+        )*};
+    }
+    types!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+    let names: Vec<&str> = smallest.iter().map(|name| name.unwrap_or("none")).collect();
+    println!("smallest signed {}, unsigned {}, float {}", names[0], names[1], names[2]);
+    // a float literal is already rounded to f64, f32 may round it further
+    if let (Literal::F64(x), Err(_)) = (value, value.to::<f32>()) {
+        if let Ok(nearest) = convert::rounded::<f64, f32>(x, convert::Rounding::NearestEven) {
+            println!("nearest f32 is {:?}, nearest f64 is {:?}", nearest as f64, x);
+        }
+    }
+}
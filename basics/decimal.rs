@@ -0,0 +1,337 @@
+// decimal.rs
+// Decimal numbers with a fixed number of decimals for money, where 0.1 + 0.2 must be 0.30 and
+// not 0.30000000000000004. A Decimal<SCALE> is an integer count of units of 10^-SCALE, Money counts
+// cents. Adding, subtracting and multiplying by integers is exact, everything that may lose
+// decimals takes a Rounding from convert.rs:
+//
+// let price: Money = "19.99".parse()?;
+// let total = price * 3 + "4.50".parse()?;                               59.97 + 4.50 = 64.47
+// let tax = total.mul(Decimal::<2>::from_units(19), Rounding::NearestAway)?;   19% is 12.25
+// let share = total.div_int(4, Rounding::NearestEven)?;                       16.12 each
+//
+// The operators panic on overflow like the integer operators do, the checked_ methods and the
+// methods with rounding return an error instead.
+//
+// Binaries using this module must also declare `mod convert;`.
+#![allow(dead_code)]
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+use crate::convert::Rounding;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Decimal<const SCALE: u32> {
+    units: i128,
+}
+
+pub type Money = Decimal<2>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecimalError {
+    Overflow,
+    DivisionByZero,
+    // text that isn't a decimal number, with the byte offset of the problem
+    Invalid { offset: usize, message: &'static str },
+    // more decimals than the scale, they would need rounding
+    TooManyDecimals { scale: u32 },
+}
+
+impl fmt::Display for DecimalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecimalError::Overflow => f.write_str("decimal overflow"),
+            DecimalError::DivisionByZero => f.write_str("division by zero"),
+            DecimalError::Invalid { offset, message } => write!(f, "{}: {}", offset + 1, message),
+            DecimalError::TooManyDecimals { scale } => write!(f, "more than {} decimals", scale),
+        }
+    }
+}
+
+impl<const SCALE: u32> Decimal<SCALE> {
+    // 10^SCALE, the number of units in 1
+    const ONE_UNITS: i128 = {
+        assert!(SCALE <= 38, "10^SCALE must fit in i128");
+        10i128.pow(SCALE)
+    };
+    pub const ZERO: Self = Decimal { units: 0 };
+    pub const ONE: Self = Decimal { units: Self::ONE_UNITS };
+    pub const MIN: Self = Decimal { units: i128::MIN };
+    pub const MAX: Self = Decimal { units: i128::MAX };
+
+    pub const fn from_units(units: i128) -> Self {
+        Decimal { units }
+    }
+
+    // Number of 10^-SCALE, 12.34 is 1234 for Money.
+    pub const fn units(self) -> i128 {
+        self.units
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.units < 0
+    }
+
+    pub fn abs(self) -> Self {
+        Decimal { units: self.units.checked_abs().expect("decimal overflow") }
+    }
+
+    // The integer part, rounded by the rule.
+    pub fn round(self, rounding: Rounding) -> i128 {
+        divide(self.units, 1, Self::ONE_UNITS, rounding).unwrap()
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Result<Self, DecimalError> {
+        self.units.checked_add(rhs.units).map(Decimal::from_units).ok_or(DecimalError::Overflow)
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Result<Self, DecimalError> {
+        self.units.checked_sub(rhs.units).map(Decimal::from_units).ok_or(DecimalError::Overflow)
+    }
+
+    // Quantity times price, exact.
+    pub fn checked_mul(self, rhs: i128) -> Result<Self, DecimalError> {
+        self.units.checked_mul(rhs).map(Decimal::from_units).ok_or(DecimalError::Overflow)
+    }
+
+    // Times a decimal of any scale, 19.99 times the rate 0.075 of Decimal<3>. The product is
+    // rounded to SCALE decimals.
+    pub fn mul<const S: u32>(self, rhs: Decimal<S>, rounding: Rounding) -> Result<Self, DecimalError> {
+        divide(self.units, rhs.units, Decimal::<S>::ONE_UNITS, rounding)
+            .map(Decimal::from_units)
+            .ok_or(DecimalError::Overflow)
+    }
+
+    pub fn div(self, rhs: Self, rounding: Rounding) -> Result<Self, DecimalError> {
+        if rhs.units == 0 {
+            return Err(DecimalError::DivisionByZero);
+        }
+        divide(self.units, Self::ONE_UNITS, rhs.units, rounding)
+            .map(Decimal::from_units)
+            .ok_or(DecimalError::Overflow)
+    }
+
+    pub fn div_int(self, rhs: i128, rounding: Rounding) -> Result<Self, DecimalError> {
+        if rhs == 0 {
+            return Err(DecimalError::DivisionByZero);
+        }
+        divide(self.units, 1, rhs, rounding).map(Decimal::from_units).ok_or(DecimalError::Overflow)
+    }
+
+    // The same value with another number of decimals, rounded when there are fewer.
+    pub fn rescale<const TO: u32>(self, rounding: Rounding) -> Result<Decimal<TO>, DecimalError> {
+        let units = if TO >= SCALE {
+            self.units.checked_mul(10i128.pow(TO - SCALE))
+        } else {
+            divide(self.units, 1, 10i128.pow(SCALE - TO), rounding)
+        };
+        units.map(Decimal::from_units).ok_or(DecimalError::Overflow)
+    }
+
+    // Split into parts which differ by at most one unit and add up to exactly self, the larger
+    // parts first: 100.00 in 3 parts is 33.34, 33.33 and 33.33.
+    pub fn allocate(self, parts: usize) -> Vec<Self> {
+        if parts == 0 {
+            return Vec::new();
+        }
+        let n = parts as i128;
+        let (share, rest) = (self.units.div_euclid(n), self.units.rem_euclid(n));
+        (0..n).map(|i| Decimal::from_units(share + (i < rest) as i128)).collect()
+    }
+}
+
+impl<const SCALE: u32> From<i64> for Decimal<SCALE> {
+    // Panics when SCALE is so large that the value doesn't fit, never for Money.
+    fn from(n: i64) -> Self {
+        Decimal::from_units(n as i128).checked_mul(Self::ONE_UNITS).expect("decimal overflow")
+    }
+}
+
+// a * b / d rounded, with a 256 bit product so nothing overflows in between. None when the result
+// doesn't fit in i128.
+fn divide(a: i128, b: i128, d: i128, rounding: Rounding) -> Option<i128> {
+    let negative = (a < 0) ^ (b < 0) ^ (d < 0);
+    let (high, low) = widening_mul(a.unsigned_abs(), b.unsigned_abs());
+    let d_abs = d.unsigned_abs();
+    if high >= d_abs {
+        return None;
+    }
+    // long division of the 256 bits, the remainder stays below d
+    let (mut q, mut r) = (0u128, high);
+    for i in (0..128).rev() {
+        let carry = r >> 127;
+        r = (r << 1) | ((low >> i) & 1);
+        q <<= 1;
+        if carry == 1 || r >= d_abs {
+            r = r.wrapping_sub(d_abs);
+            q |= 1;
+        }
+    }
+    let away = r > 0 && match rounding {
+        Rounding::TowardZero => false,
+        Rounding::Down => negative,
+        Rounding::Up => !negative,
+        Rounding::NearestEven => match r.cmp(&(d_abs - r)) {
+            Ordering::Less => false,
+            Ordering::Greater => true,
+            Ordering::Equal => q % 2 == 1,
+        },
+        Rounding::NearestAway => r >= d_abs - r,
+    };
+    let q = q.checked_add(away as u128)?;
+    if negative {
+        0i128.checked_sub_unsigned(q)
+    } else {
+        i128::try_from(q).ok()
+    }
+}
+
+// Full 256 bit product as high and low 128 bits.
+fn widening_mul(a: u128, b: u128) -> (u128, u128) {
+    let mask = u64::MAX as u128;
+    let (a1, a0) = (a >> 64, a & mask);
+    let (b1, b0) = (b >> 64, b & mask);
+    let low = a0 * b0;
+    let middle1 = a1 * b0;
+    let middle2 = a0 * b1;
+    let high = a1 * b1;
+    // the carries of adding the middle products at bit 64
+    let middle = (low >> 64) + (middle1 & mask) + (middle2 & mask);
+    let low = (low & mask) | (middle << 64);
+    let high = high + (middle1 >> 64) + (middle2 >> 64) + (middle >> 64);
+    (high, low)
+}
+
+impl<const SCALE: u32> fmt::Display for Decimal<SCALE> {
+    // SCALE decimals, 12.30 and -0.05. A precision gives that many decimals instead, fewer are
+    // rounded to the nearest even like {:.2} of floats. Width, alignment, + and 0 apply.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let decimals = f.precision().unwrap_or(SCALE as usize);
+        let (units, scale) = if decimals < SCALE as usize {
+            let dropped = 10i128.pow(SCALE - decimals as u32);
+            (divide(self.units, 1, dropped, Rounding::NearestEven).unwrap(), decimals)
+        } else {
+            (self.units, SCALE as usize)
+        };
+        let one = 10u128.pow(scale as u32);
+        let magnitude = units.unsigned_abs();
+        let mut digits = (magnitude / one).to_string();
+        if decimals > 0 {
+            digits.push('.');
+        }
+        if scale > 0 {
+            digits += &format!("{:0width$}", magnitude % one, width = scale);
+        }
+        // more decimals than SCALE
+        digits.extend(std::iter::repeat_n('0', decimals - scale));
+        f.pad_integral(units >= 0, "", &digits)
+    }
+}
+
+// -12.5 or 3, at most SCALE decimals.
+impl<const SCALE: u32> FromStr for Decimal<SCALE> {
+    type Err = DecimalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |offset, message| Err(DecimalError::Invalid { offset, message });
+        let digits_start = if s.starts_with('-') || s.starts_with('+') { 1 } else { 0 };
+        let (integer, decimals) = match s[digits_start..].find('.') {
+            Some(dot) => (&s[digits_start..digits_start + dot], &s[digits_start + dot + 1..]),
+            None => (&s[digits_start..], ""),
+        };
+        if integer.is_empty() {
+            return invalid(digits_start, "expected a digit");
+        }
+        if s.ends_with('.') {
+            return invalid(s.len(), "expected a digit after '.'");
+        }
+        let decimals_start = digits_start + integer.len() + 1;
+        for (start, part) in [(digits_start, integer), (decimals_start, decimals)] {
+            if let Some(i) = part.find(|c: char| !c.is_ascii_digit()) {
+                return invalid(start + i, "expected a digit");
+            }
+        }
+        // extra zeros don't need rounding
+        let decimals = decimals.trim_end_matches('0');
+        if decimals.len() > SCALE as usize {
+            return Err(DecimalError::TooManyDecimals { scale: SCALE });
+        }
+
+        let mut units: i128 = 0;
+        let padded = decimals.bytes().chain(std::iter::repeat(b'0')).take(SCALE as usize);
+        for b in integer.bytes().chain(padded) {
+            units = units.checked_mul(10)
+                .and_then(|u| if s.starts_with('-') { u.checked_sub((b - b'0') as i128) } else { u.checked_add((b - b'0') as i128) })
+                .ok_or(DecimalError::Overflow)?;
+        }
+        Ok(Decimal::from_units(units))
+    }
+}
+
+impl<const SCALE: u32> Add for Decimal<SCALE> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        self.checked_add(rhs).expect("decimal overflow")
+    }
+}
+
+impl<const SCALE: u32> Sub for Decimal<SCALE> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self.checked_sub(rhs).expect("decimal overflow")
+    }
+}
+
+impl<const SCALE: u32> Neg for Decimal<SCALE> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Decimal::from_units(self.units.checked_neg().expect("decimal overflow"))
+    }
+}
+
+impl<const SCALE: u32> Mul<i128> for Decimal<SCALE> {
+    type Output = Self;
+
+    fn mul(self, rhs: i128) -> Self {
+        self.checked_mul(rhs).expect("decimal overflow")
+    }
+}
+
+impl<const SCALE: u32> Mul<Decimal<SCALE>> for i128 {
+    type Output = Decimal<SCALE>;
+
+    fn mul(self, rhs: Decimal<SCALE>) -> Decimal<SCALE> {
+        rhs * self
+    }
+}
+
+impl<const SCALE: u32> AddAssign for Decimal<SCALE> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl<const SCALE: u32> SubAssign for Decimal<SCALE> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl<const SCALE: u32> Sum for Decimal<SCALE> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Decimal::ZERO, Add::add)
+    }
+}
+
+impl<'a, const SCALE: u32> Sum<&'a Decimal<SCALE>> for Decimal<SCALE> {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
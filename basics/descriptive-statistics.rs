@@ -0,0 +1,157 @@
+// descriptive-statistics.rs
+// Mean, median, modes, variance, percentiles and histogram of integers read from files or stdin.
+//
+// rustc descriptive-statistics.rs
+// seq 1 100 | ./descriptive-statistics --percentile 90 --buckets 5
+// ./descriptive-statistics --check
+
+use std::process;
+
+mod args;
+mod crash_report;
+mod input;
+mod statistics;
+
+use statistics::Variance;
+
+// Width of the longest histogram bar.
+const BAR_WIDTH: usize = 40;
+
+fn main() {
+    crash_report::install();
+
+    let matches = args::Command::new("descriptive-statistics")
+        .about("Print descriptive statistics of integers separated by whitespace or commas.")
+        .option("percentile", Some('p'), "P", "Percentile to print, may be repeated [default: 25, 50, 75]")
+        .option("buckets", Some('b'), "N", "Number of histogram buckets [default: 10]")
+        .flag("sample", Some('s'), "Values are a sample, use n - 1 for variance")
+        .flag("check", None, "Check the statistics with examples and exit")
+        .multiple("FILE", "Files to read, standard input if none or -")
+        .parse();
+    if matches.flag("check") {
+        check();
+        println!("ok");
+        return;
+    }
+
+    let mut percentiles: Vec<f64> = matches.get_all("percentile").unwrap_or_else(|e| e.exit());
+    if percentiles.is_empty() {
+        percentiles = vec![25.0, 50.0, 75.0];
+    }
+    let buckets: usize = matches.get("buckets").unwrap_or_else(|e| e.exit()).unwrap_or(10);
+    let kind = if matches.flag("sample") { Variance::Sample } else { Variance::Population };
+
+    let mut files = matches.values("FILE");
+    if files.is_empty() {
+        files.push("-");
+    }
+    let mut values = Vec::new();
+    for path in files {
+        let parsed = input::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| parse_values(&text));
+        match parsed {
+            Ok(parsed) => values.extend(parsed),
+            Err(e) => {
+                eprintln!("descriptive-statistics: {}: {}", path, e);
+                process::exit(1);
+            }
+        }
+    }
+
+    println!("count     {}", values.len());
+    let summary = match statistics::summarize(&values, kind) {
+        Ok(summary) => summary,
+        // empty input has only the count
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    println!("min       {}", summary.min);
+    println!("max       {}", summary.max);
+    println!("mean      {:.2}", summary.mean);
+    println!("median    {:.2}", summary.median);
+    let modes: Vec<String> = summary.modes.iter().map(|m| m.to_string()).collect();
+    println!("modes     {}", modes.join(", "));
+    match (summary.variance, summary.std_dev) {
+        (Ok(variance), Ok(std_dev)) => {
+            println!("variance  {:.2}", variance);
+            println!("std dev   {:.2}", std_dev);
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            println!("variance  {}", e);
+            println!("std dev   {}", e);
+        }
+    }
+    for p in percentiles {
+        match statistics::percentile(&values, p) {
+            Ok(value) => println!("p{:<8} {:.2}", p, value),
+            Err(e) => println!("p{:<8} {}", p, e),
+        }
+    }
+
+    match statistics::histogram(&values, buckets) {
+        Ok(histogram) => {
+            println!("histogram");
+            let labels: Vec<String> = histogram.iter().map(|b| format!("{}..={}", b.start, b.end)).collect();
+            let width = labels.iter().map(|l| l.len()).max().unwrap_or(0);
+            let max = histogram.iter().map(|b| b.count).max().unwrap_or(1);
+            for (label, bucket) in labels.iter().zip(histogram.iter()) {
+                let bar = "#".repeat((bucket.count * BAR_WIDTH).div_ceil(max));
+                println!("  {:>width$}  {:>8}  {}", label, bucket.count, bar, width = width);
+            }
+        }
+        Err(e) => println!("histogram {}", e),
+    }
+}
+
+fn parse_values(text: &str) -> Result<Vec<i32>, String> {
+    let mut values = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        for token in line.split(|c: char| c.is_whitespace() || c == ',').filter(|t| !t.is_empty()) {
+            let value = token.parse::<i32>()
+                .map_err(|e| format!("line {}: invalid number '{}': {}", i + 1, token, e))?;
+            values.push(value);
+        }
+    }
+    Ok(values)
+}
+
+fn check() {
+    use statistics::StatsError;
+
+    let values = [3, 1, 4, 1, 5, 9, 2, 6, 5];
+    let summary = statistics::summarize(&values, Variance::Population).unwrap();
+    assert_eq!((summary.count, summary.min, summary.max), (9, 1, 9));
+    assert!((summary.mean - 4.0).abs() < 1e-9);
+    assert_eq!(summary.median, 4.0);
+    // ties are all modes, in ascending order
+    assert_eq!(summary.modes, vec![1, 5]);
+    assert_eq!(summary.variance, Ok(6.0));
+    assert_eq!(statistics::modes(&[7, 2, 9]), Ok(vec![2, 7, 9]));
+    assert_eq!(statistics::median(&[1, 2, 3, 4]), Ok(2.5));
+    assert_eq!(statistics::variance(&values, Variance::Sample), Ok(6.75));
+
+    // empty input has no statistics
+    assert_eq!(statistics::summarize(&[], Variance::Population), Err(StatsError::Empty));
+    assert_eq!(statistics::percentile(&[], 50.0), Err(StatsError::Empty));
+    assert_eq!(statistics::histogram(&[], 3), Err(StatsError::Empty));
+    assert_eq!(parse_values(" \n,\n"), Ok(Vec::new()));
+
+    // one value is enough for everything except the sample variance
+    let summary = statistics::summarize(&[42], Variance::Sample).unwrap();
+    assert_eq!((summary.count, summary.min, summary.max, summary.mean, summary.median), (1, 42, 42, 42.0, 42.0));
+    assert_eq!(summary.modes, vec![42]);
+    assert_eq!(summary.variance, Err(StatsError::NotEnoughValues));
+    assert_eq!(summary.std_dev, Err(StatsError::NotEnoughValues));
+    assert_eq!(statistics::summarize(&[42], Variance::Population).unwrap().variance, Ok(0.0));
+
+    assert_eq!(statistics::percentile(&[10, 20, 30, 40], 50.0), Ok(25.0));
+    assert_eq!(statistics::percentile(&[10, 20], 101.0), Err(StatsError::InvalidPercentile(101.0)));
+    let histogram = statistics::histogram(&[1, 2, 3, 10], 2).unwrap();
+    let counts: Vec<(i64, i64, usize)> = histogram.iter().map(|b| (b.start, b.end, b.count)).collect();
+    assert_eq!(counts, vec![(1, 5, 3), (6, 10, 1)]);
+    assert_eq!(parse_values("1, -2\n3"), Ok(vec![1, -2, 3]));
+    assert!(parse_values("1 x").is_err());
+}
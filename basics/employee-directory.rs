@@ -0,0 +1,336 @@
+// employee-directory.rs
+// Text interface to add employee names to a department in a company and list them.
+// Commands are read interactively or from a script file and the directory is kept in a plain
+// text file between runs.
+//
+// rustc employee-directory.rs
+// ./employee-directory
+// > Add Sally to Engineering
+// > Add Amir to Sales
+// > List all
+// ./employee-directory --check
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::io::{BufRead, ErrorKind, Write};
+use std::path::Path;
+use std::process;
+use std::str::FromStr;
+
+mod args;
+mod crash_report;
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Add { name: String, department: String },
+    Remove { name: String, department: String },
+    List(Option<String>),
+    Help,
+    Quit,
+}
+
+const HELP: &str = "Commands:
+  Add <name> to <department>
+  Remove <name> from <department>
+  List <department>
+  List all
+  Help
+  Quit";
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let line = line.trim();
+        let (verb, rest) = match line.find(char::is_whitespace) {
+            Some(pos) => (&line[..pos], line[pos..].trim()),
+            None => (line, ""),
+        };
+        match verb.to_lowercase().as_str() {
+            "add" => {
+                let (name, department) = split_name(rest, " to ")?;
+                Ok(Command::Add { name, department })
+            }
+            "remove" => {
+                let (name, department) = split_name(rest, " from ")?;
+                Ok(Command::Remove { name, department })
+            }
+            "list" => match rest {
+                "" => Err(String::from("List what? Use List <department> or List all")),
+                _ if rest.eq_ignore_ascii_case("all") => Ok(Command::List(None)),
+                _ => Ok(Command::List(Some(String::from(rest)))),
+            },
+            "help" if rest.is_empty() => Ok(Command::Help),
+            "quit" | "exit" if rest.is_empty() => Ok(Command::Quit),
+            _ => Err(format!("Unknown command '{}', type Help", line)),
+        }
+    }
+}
+
+// "Sally Smith to Research and Development" -> (Sally Smith, Research and Development)
+fn split_name(text: &str, separator: &str) -> Result<(String, String), String> {
+    let pos = text.to_ascii_lowercase().find(separator)
+        .ok_or_else(|| format!("Expected <name>{}<department>", separator))?;
+    let name = text[..pos].trim();
+    let department = text[pos + separator.len()..].trim();
+    if name.is_empty() || department.is_empty() {
+        return Err(format!("Expected <name>{}<department>", separator));
+    }
+    Ok((String::from(name), String::from(department)))
+}
+
+// Case doesn't matter, "amir" comes before "Bob". Letters are still compared by their code point,
+// so accented letters like "É" come after "Z", sorting them like a dictionary needs locale data.
+fn sort_names(names: &mut [&str]) {
+    names.sort_unstable_by_key(|name| (name.to_lowercase(), *name));
+}
+
+#[derive(Debug, Default)]
+struct Directory {
+    departments: HashMap<String, Vec<String>>,
+}
+
+impl Directory {
+    fn add(&mut self, name: &str, department: &str) -> Result<(), String> {
+        // the file separates fields by tab and entries by line
+        if let Some(text) = [name, department].iter().find(|text| text.contains(char::is_control)) {
+            return Err(format!("{:?} contains a tab, line break or other control character", text));
+        }
+        let employees = self.departments.entry(String::from(department)).or_default();
+        if employees.iter().any(|e| e == name) {
+            return Err(format!("{} is already in {}", name, department));
+        }
+        employees.push(String::from(name));
+        Ok(())
+    }
+
+    fn remove(&mut self, name: &str, department: &str) -> Result<(), String> {
+        let employees = self.departments.get_mut(department)
+            .ok_or_else(|| format!("There is no department {}", department))?;
+        let pos = employees.iter().position(|e| e == name)
+            .ok_or_else(|| format!("{} is not in {}", name, department))?;
+        employees.remove(pos);
+        if employees.is_empty() {
+            self.departments.remove(department);
+        }
+        Ok(())
+    }
+
+    // Employees of the department sorted alphabetically, see sort_names.
+    fn list(&self, department: &str) -> Vec<&str> {
+        let mut employees: Vec<&str> = match self.departments.get(department) {
+            Some(employees) => employees.iter().map(|e| e.as_str()).collect(),
+            None => Vec::new(),
+        };
+        sort_names(&mut employees);
+        employees
+    }
+
+    // Departments sorted alphabetically, each with its sorted employees.
+    fn list_all(&self) -> Vec<(&str, Vec<&str>)> {
+        let mut departments: Vec<&str> = self.departments.keys().map(|d| d.as_str()).collect();
+        sort_names(&mut departments);
+        departments.into_iter().map(|d| (d, self.list(d))).collect()
+    }
+
+    // One `department<TAB>name` per line, missing file is an empty directory.
+    fn load(path: &Path) -> io::Result<Directory> {
+        let mut directory = Directory::default();
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(directory),
+            Err(e) => return Err(e),
+        };
+        for (i, line) in text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let invalid = |message: String| io::Error::new(ErrorKind::InvalidData, format!("line {}: {}", i + 1, message));
+            let (department, name) = line.split_once('\t')
+                .ok_or_else(|| invalid(String::from("expected department and name separated by tab")))?;
+            directory.add(name, department).map_err(invalid)?;
+        }
+        Ok(directory)
+    }
+
+    // Written to a temporary file first so an interrupted save doesn't destroy the data.
+    fn save(&self, path: &Path) -> io::Result<()> {
+        let mut text = String::new();
+        for (department, employees) in self.list_all() {
+            for name in employees {
+                text.push_str(&format!("{}\t{}\n", department, name));
+            }
+        }
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, text)?;
+        fs::rename(&tmp, path)
+    }
+
+    // Output of the command, Ok(None) for Quit.
+    fn execute(&mut self, command: Command) -> Result<Option<String>, String> {
+        match command {
+            Command::Add { name, department } => {
+                self.add(&name, &department)?;
+                Ok(Some(format!("Added {} to {}", name, department)))
+            }
+            Command::Remove { name, department } => {
+                self.remove(&name, &department)?;
+                Ok(Some(format!("Removed {} from {}", name, department)))
+            }
+            Command::List(Some(department)) => {
+                let employees = self.list(&department);
+                if employees.is_empty() {
+                    return Err(format!("There is no department {}", department));
+                }
+                Ok(Some(format!("{}: {}", department, employees.join(", "))))
+            }
+            Command::List(None) => {
+                let lines: Vec<String> = self.list_all().iter()
+                    .map(|(department, employees)| format!("{}: {}", department, employees.join(", ")))
+                    .collect();
+                if lines.is_empty() {
+                    Ok(Some(String::from("The directory is empty")))
+                } else {
+                    Ok(Some(lines.join("\n")))
+                }
+            }
+            Command::Help => Ok(Some(String::from(HELP))),
+            Command::Quit => Ok(None),
+        }
+    }
+}
+
+fn main() {
+    crash_report::install();
+
+    let matches = args::Command::new("employee-directory")
+        .about("Add employees to departments and list them, type Help for commands.")
+        .option("data", Some('d'), "FILE", "File with the directory [default: employees.txt]")
+        .flag("check", None, "Check commands, names and saving with examples and exit")
+        .optional("SCRIPT", "File with commands, one per line, interactive if not set")
+        .parse();
+    if matches.flag("check") {
+        check();
+        println!("ok");
+        return;
+    }
+    let data = Path::new(matches.value("data").unwrap_or("employees.txt"));
+
+    let mut directory = Directory::load(data).unwrap_or_else(|e| {
+        eprintln!("employee-directory: {}: {}", data.display(), e);
+        process::exit(1);
+    });
+
+    let result = match matches.value("SCRIPT") {
+        Some(script) => run_script(&mut directory, script, data),
+        None => run_interactive(&mut directory, data),
+    };
+    match result {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("employee-directory: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+// Every command is executed even if some of them fail, returns false if any failed.
+fn run_script(directory: &mut Directory, script: &str, data: &Path) -> io::Result<bool> {
+    let text = fs::read_to_string(script)?;
+    let mut success = true;
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        match line.parse().and_then(|command| directory.execute(command)) {
+            Ok(Some(output)) => println!("{}", output),
+            Ok(None) => break,
+            Err(e) => {
+                eprintln!("{}:{}: {}", script, i + 1, e);
+                success = false;
+            }
+        }
+    }
+    directory.save(data)?;
+    Ok(success)
+}
+
+fn run_interactive(directory: &mut Directory, data: &Path) -> io::Result<bool> {
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("> ");
+        io::stdout().flush()?;
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let command = match line.parse::<Command>() {
+            Ok(command) => command,
+            Err(e) => {
+                println!("{}", e);
+                continue;
+            }
+        };
+        let modifies = matches!(command, Command::Add { .. } | Command::Remove { .. });
+        match directory.execute(command) {
+            Ok(Some(output)) => println!("{}", output),
+            Ok(None) => break,
+            Err(e) => println!("{}", e),
+        }
+        // saved after every change, so nothing is lost when the program is killed
+        if modifies {
+            directory.save(data)?;
+        }
+    }
+    Ok(true)
+}
+
+fn check() {
+    let add = |name: &str, department: &str| Command::Add { name: String::from(name), department: String::from(department) };
+    let commands = [
+        ("Add Sally to Engineering", Ok(add("Sally", "Engineering"))),
+        ("  add Sally Smith TO Research and Development ", Ok(add("Sally Smith", "Research and Development"))),
+        ("Remove Amir from Sales", Ok(Command::Remove { name: String::from("Amir"), department: String::from("Sales") })),
+        ("List Sales", Ok(Command::List(Some(String::from("Sales"))))),
+        ("list ALL", Ok(Command::List(None))),
+        ("Help", Ok(Command::Help)),
+        ("exit", Ok(Command::Quit)),
+        ("List", Err(String::from("List what? Use List <department> or List all"))),
+        ("Add Sally", Err(String::from("Expected <name> to <department>"))),
+        ("Add  to Sales", Err(String::from("Expected <name> to <department>"))),
+        ("Help me", Err(String::from("Unknown command 'Help me', type Help"))),
+    ];
+    for (line, expected) in commands.iter() {
+        assert_eq!(line.parse::<Command>(), *expected, "{:?}", line);
+    }
+
+    let mut directory = Directory::default();
+    for (name, department) in [("Zoe", "Sales"), ("Émile", "Sales"), ("amir", "Sales"), ("Bob", "Sales"), ("Sally", "engineering")] {
+        directory.add(name, department).unwrap();
+    }
+    assert_eq!(directory.list("Sales"), ["amir", "Bob", "Zoe", "Émile"]);
+    assert_eq!(directory.list_all(), [("engineering", vec!["Sally"]), ("Sales", vec!["amir", "Bob", "Zoe", "Émile"])]);
+    assert_eq!(directory.add("Bob", "Sales"), Err(String::from("Bob is already in Sales")));
+    assert!(directory.add("Bob\tSmith", "Sales").is_err());
+    assert!(directory.add("Bob", "Sales\n").is_err());
+    assert!(directory.add("Bob\r", "Sales").is_err());
+    assert_eq!(directory.remove("Bob", "Marketing"), Err(String::from("There is no department Marketing")));
+    assert_eq!(directory.execute(Command::List(Some(String::from("Sales")))), Ok(Some(String::from("Sales: amir, Bob, Zoe, Émile"))));
+    assert_eq!(directory.execute(Command::Quit), Ok(None));
+
+    // saved and loaded again, a missing file is empty and a damaged file an error
+    let dir = env::temp_dir().join(format!("employee-directory-check-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let data = dir.join("employees.txt");
+    assert!(Directory::load(&data).unwrap().list_all().is_empty());
+    directory.save(&data).unwrap();
+    assert_eq!(Directory::load(&data).unwrap().list_all(), directory.list_all());
+    fs::write(&data, "Sales\tBob\nSales Bob\n").unwrap();
+    let error = Directory::load(&data).unwrap_err();
+    assert_eq!((error.kind(), error.to_string()), (ErrorKind::InvalidData, String::from("line 2: expected department and name separated by tab")));
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -2,29 +2,61 @@
 
 // Enums allow you to define a type by enumerating its possible variants.
 
-enum Message {
-    Quit,
-    Move { x: i32, y: i32 },
-    Write(String),
-    ChangeColor(i32, i32, i32),
-}
+// Message is defined in message.rs, so other programs can use it too:
+//
+// enum Message {
+//     Quit,
+//     Move { x: i32, y: i32 },
+//     Write(String),
+//     ChangeColor(i32, i32, i32),
+// }
+mod message;
+
+use message::{Message, State};
 
+// The match in Display for Message has an arm for every variant, without a `_` arm the compiler
+// tells us about every match we have to update when a variant is added.
 fn print_message(message: Message) {
-    println!("{}",
-             match message {
-                 Message::Quit => String::from("quit"),
-                 Message::Write(message) => format!("{}", message),
-                 _ => String::from("default"),
-             },
-    );
+    println!("{}", message);
 }
 
 fn main() {
     {
         print_message(Message::Write(String::from("hello, world!")));
+        print_message(Message::Move { x: 3, y: -4 });
+        print_message(Message::ChangeColor(255, 0, 0));
         print_message(Message::Quit);
     }
 
+    // Messages as commands changing the state of an application
+    {
+        let messages = vec![
+            Message::Move { x: 3, y: -4 },
+            Message::Write(String::from("hello")),
+            Message::ChangeColor(255, 128, 0),
+            Message::ChangeColor(256, 0, 0),
+            Message::Move { x: i32::MAX, y: 0 },
+            Message::Write(String::from(", world")),
+            Message::Quit,
+            Message::Write(String::from("!")),
+        ];
+        let mut state = State::new();
+        let results = state.dispatch(&messages);
+        for (message, result) in messages.iter().zip(&results) {
+            match result {
+                Ok(()) => println!("{:<36} ok", message.to_string()),
+                Err(e) => println!("{:<36} error: {}", message.to_string(), e),
+            }
+        }
+        println!("{:?}", state);
+
+        assert_eq!(state.position, (3, -4));
+        assert_eq!(state.color.to_string(), "#ff8000");
+        assert_eq!(state.text, "hello, world");
+        assert!(!state.running);
+        assert_eq!(results.iter().filter(|r| r.is_err()).count(), 3);
+    }
+
     // The Option<T> Enum and Its Advantages Over Null Values
     {
         let v = vec![1, 2, 3];
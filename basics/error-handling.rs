@@ -16,20 +16,25 @@
 // You can switch from unwinding to aborting upon a panic by adding panic = 'abort'
 // to the appropriate [profile] sections in your Cargo.toml file.
 
-use std::env;
 use std::fs::File;
 use std::io;
 use std::io::ErrorKind;
 use std::io::Read;
 
+mod args;
+// Panics are reported by our own hook, see crash_report.rs
+mod crash_report;
+
 fn main() {
+    crash_report::install();
+
     {
-        let args: Vec<String> = env::args().collect();
-        let arg1 = args.get(1);
-        match arg1 {
-            None => panic!("Define least one argument!"),
-            Some(v) => println!("arg1 = {}", v),
-        }
+        // Missing argument is a usage error, reported by the parser instead of panic!
+        let matches = args::Command::new("error-handling")
+            .about("Examples of recoverable and unrecoverable errors.")
+            .positional("ARG", "Any value, it is printed back")
+            .parse();
+        println!("arg1 = {}", matches.value("ARG").unwrap());
     }
 
     {
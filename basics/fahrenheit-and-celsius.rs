@@ -1,6 +1,9 @@
 // fahrenheit-and-celsius.rs
 // Simple program to convert fahrenheit to celsius and vice versa.
-use std::env;
+use std::str::FromStr;
+
+mod args;
+mod crash_report;
 
 enum InputValue {
     Fahrenheit(f64),
@@ -8,23 +11,30 @@ enum InputValue {
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let arg1 = args.get(1);
-    match arg1 {
-        None => panic!("Please input value!"),
-        Some(val) => match parse(val) {
-            InputValue::Fahrenheit(f) => println!("{:.2}C", fahrenheit_to_celsius(f)),
-            InputValue::Celsius(c) => println!("{:.2}F", celsius_to_fahrenheit(c)),
-        }
+    crash_report::install();
+
+    let matches = args::Command::new("fahrenheit-and-celsius")
+        .about("Convert temperatures between Fahrenheit and Celsius.")
+        .positional("VALUE", "Temperature ending with F or C character, e.g. 100F or -40C")
+        .parse();
+    match matches.required::<InputValue>("VALUE").unwrap_or_else(|e| e.exit()) {
+        InputValue::Fahrenheit(f) => println!("{:.2}C", fahrenheit_to_celsius(f)),
+        InputValue::Celsius(c) => println!("{:.2}F", celsius_to_fahrenheit(c)),
     };
 }
 
-fn parse(value: &str) -> InputValue {
-    let mut value = String::from(value);
-    match value.pop().unwrap() {
-        'F' => InputValue::Fahrenheit(value.parse::<f64>().unwrap()),
-        'C' => InputValue::Celsius(value.parse::<f64>().unwrap()),
-        _ => panic!("Unsupported value format! Value must ends with F or C character!"),
+impl FromStr for InputValue {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut value = String::from(value);
+        let unit = value.pop();
+        let number = value.parse::<f64>().map_err(|e| e.to_string());
+        match unit {
+            Some('F') => number.map(InputValue::Fahrenheit),
+            Some('C') => number.map(InputValue::Celsius),
+            _ => Err(String::from("Unsupported value format! Value must ends with F or C character!")),
+        }
     }
 }
 
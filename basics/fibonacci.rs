@@ -1,18 +1,18 @@
 // fibonacci.rs
 // Generate the nth Fibonacci number.
 
-use std::env;
+mod args;
+mod crash_report;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let arg1 = args.get(1);
-    match arg1 {
-        None => panic!("Please input value!"),
-        Some(value) => {
-            let n: u32 = value.parse::<u32>().unwrap();
-            println!("{}", fibonacci(n));
-        },
-    };
+    crash_report::install();
+
+    let matches = args::Command::new("fibonacci")
+        .about("Generate the nth Fibonacci number.")
+        .positional("N", "Position in the Fibonacci sequence")
+        .parse();
+    let n: u32 = matches.required("N").unwrap_or_else(|e| e.exit());
+    println!("{}", fibonacci(n));
 }
 
 fn fibonacci(n: u32) -> u32 {
@@ -0,0 +1,17 @@
+// input.rs
+// Input of the command-line tools, a file or `-` for standard input.
+
+use std::fs;
+use std::io;
+use std::io::Read;
+
+// Read whole file, `-` means standard input.
+pub fn read_to_string(path: &str) -> io::Result<String> {
+    if path == "-" {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text)?;
+        Ok(text)
+    } else {
+        fs::read_to_string(path)
+    }
+}
@@ -0,0 +1,406 @@
+// inverted_index.rs
+// Full-text search over text files. For every word the index keeps the documents containing it
+// together with the positions of the word, which is enough for boolean queries, phrases and
+// TF-IDF ranking without reading the documents again.
+//
+// Query syntax: `rust AND (cargo OR rustc) NOT java "string slice"`, words next to each other
+// are joined by AND, NOT binds to the following word, phrase or group.
+//
+// Binaries using this module must also declare `mod word_count;`.
+#![allow(dead_code)]
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::word_count;
+
+const HEADER: &str = "inverted-index 1";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    pub path: PathBuf,
+    // number of words
+    pub len: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Posting {
+    doc: u32,
+    positions: Vec<u32>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Index {
+    documents: Vec<Document>,
+    // postings of every word ordered by document
+    terms: HashMap<String, Vec<Posting>>,
+}
+
+impl Index {
+    pub fn new() -> Index {
+        Index::default()
+    }
+
+    // Index every UTF-8 text file in the directory and its subdirectories, hidden ones are skipped
+    // and so is exclude, a canonical path like the one of the index file itself. Symbolic links
+    // are not followed, a link to a parent directory would never end.
+    // Returns paths of files which are not text.
+    pub fn add_dir(&mut self, dir: &Path, exclude: Option<&Path>) -> io::Result<Vec<PathBuf>> {
+        let mut skipped = Vec::new();
+        let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+        // same index for the same files, whatever order the file system uses
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            let path = entry.path();
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            // the type of the entry itself, not of what a link points to
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                skipped.extend(self.add_dir(&path, exclude)?);
+                continue;
+            }
+            if !file_type.is_file() {
+                continue;
+            }
+            if exclude.is_some() && fs::canonicalize(&path).ok().as_deref() == exclude {
+                continue;
+            }
+            match fs::read_to_string(&path) {
+                Ok(text) => self.add_document(path, &text),
+                Err(ref e) if e.kind() == ErrorKind::InvalidData => skipped.push(path),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(skipped)
+    }
+
+    pub fn add_document(&mut self, path: PathBuf, text: &str) {
+        let doc = self.documents.len() as u32;
+        let mut len = 0;
+        for (position, word) in word_count::words(text).enumerate() {
+            let postings = self.terms.entry(word).or_default();
+            match postings.last_mut() {
+                Some(posting) if posting.doc == doc => posting.positions.push(position as u32),
+                _ => postings.push(Posting { doc, positions: vec![position as u32] }),
+            }
+            len += 1;
+        }
+        self.documents.push(Document { path, len });
+    }
+
+    pub fn documents(&self) -> &[Document] {
+        &self.documents
+    }
+
+    pub fn term_count(&self) -> usize {
+        self.terms.len()
+    }
+
+    // Matching documents with their score, the best first.
+    pub fn search(&self, query: &str) -> Result<Vec<(&Document, f64)>, QueryError> {
+        let query = Query::parse(query)?;
+        let mut results: Vec<(&Document, f64)> = self.evaluate(&query)
+            .into_iter()
+            .map(|doc| (&self.documents[doc as usize], self.score(doc, &query)))
+            .collect();
+        results.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.path.cmp(&b.0.path)));
+        Ok(results)
+    }
+
+    fn evaluate(&self, query: &Query) -> BTreeSet<u32> {
+        match query {
+            Query::Term(term) => self.postings(term).iter().map(|p| p.doc).collect(),
+            Query::Phrase(terms) => self.phrase(terms),
+            Query::And(left, right) => self.evaluate(left).intersection(&self.evaluate(right)).copied().collect(),
+            Query::Or(left, right) => self.evaluate(left).union(&self.evaluate(right)).copied().collect(),
+            Query::Not(inner) => {
+                let excluded = self.evaluate(inner);
+                (0..self.documents.len() as u32).filter(|doc| !excluded.contains(doc)).collect()
+            }
+        }
+    }
+
+    fn postings(&self, term: &str) -> &[Posting] {
+        self.terms.get(term).map(|p| p.as_slice()).unwrap_or(&[])
+    }
+
+    // Documents where the words follow each other in the given order.
+    fn phrase(&self, terms: &[String]) -> BTreeSet<u32> {
+        let first = match terms.first() {
+            Some(first) => first,
+            None => return BTreeSet::new(),
+        };
+        self.postings(first).iter()
+            .filter(|posting| {
+                posting.positions.iter().any(|&start| {
+                    terms.iter().enumerate().skip(1).all(|(offset, term)| {
+                        self.positions(term, posting.doc)
+                            .is_some_and(|positions| positions.binary_search(&(start + offset as u32)).is_ok())
+                    })
+                })
+            })
+            .map(|posting| posting.doc)
+            .collect()
+    }
+
+    fn positions(&self, term: &str, doc: u32) -> Option<&[u32]> {
+        let postings = self.postings(term);
+        postings.binary_search_by_key(&doc, |p| p.doc)
+            .ok()
+            .map(|i| postings[i].positions.as_slice())
+    }
+
+    // Sum of TF-IDF of the query words in the document, words under NOT don't count.
+    fn score(&self, doc: u32, query: &Query) -> f64 {
+        let n = self.documents.len() as f64;
+        query.positive_terms().iter()
+            .map(|term| {
+                let tf = self.positions(term, doc).map_or(0, |p| p.len()) as f64;
+                if tf == 0.0 {
+                    return 0.0;
+                }
+                let df = self.postings(term).len() as f64;
+                // smoothed, a word in every document still counts a little
+                let idf = ((n + 1.0) / (df + 1.0)).ln() + 1.0;
+                (1.0 + tf.ln()) * idf
+            })
+            .fold(0.0, |sum, score| sum + score)
+    }
+
+    // Text format, one document per line followed by one word with its postings per line:
+    // `word<TAB>doc:pos,pos;doc:pos`.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut out = format!("{}\n{}\n", HEADER, self.documents.len());
+        for document in &self.documents {
+            out.push_str(&format!("{}\t{}\n", document.len, escape(&document.path.to_string_lossy())));
+        }
+        let mut terms: Vec<&String> = self.terms.keys().collect();
+        terms.sort();
+        for term in terms {
+            let postings: Vec<String> = self.terms[term].iter()
+                .map(|p| {
+                    let positions: Vec<String> = p.positions.iter().map(|pos| pos.to_string()).collect();
+                    format!("{}:{}", p.doc, positions.join(","))
+                })
+                .collect();
+            out.push_str(&format!("{}\t{}\n", term, postings.join(";")));
+        }
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, out)?;
+        fs::rename(&tmp, path)
+    }
+
+    pub fn open(path: &Path) -> io::Result<Index> {
+        let text = fs::read_to_string(path)?;
+        let mut lines = text.lines().enumerate();
+        let invalid = |line: usize, message: &str| {
+            io::Error::new(ErrorKind::InvalidData, format!("line {}: {}", line + 1, message))
+        };
+
+        match lines.next() {
+            Some((_, HEADER)) => {}
+            _ => return Err(invalid(0, "not an index file")),
+        }
+        let (i, count) = lines.next().ok_or_else(|| invalid(1, "missing document count"))?;
+        let count: usize = count.parse().map_err(|_| invalid(i, "invalid document count"))?;
+
+        let mut index = Index::new();
+        for _ in 0..count {
+            let (i, line) = lines.next().ok_or_else(|| invalid(i, "missing document"))?;
+            let (len, path) = line.split_once('\t').ok_or_else(|| invalid(i, "invalid document"))?;
+            let len = len.parse().map_err(|_| invalid(i, "invalid document length"))?;
+            index.documents.push(Document { path: PathBuf::from(unescape(path)), len });
+        }
+        for (i, line) in lines {
+            let (term, postings) = line.split_once('\t').ok_or_else(|| invalid(i, "invalid term"))?;
+            let postings = postings.split(';')
+                .map(|posting| {
+                    let (doc, positions) = posting.split_once(':')?;
+                    let doc: u32 = doc.parse().ok().filter(|&doc| (doc as usize) < count)?;
+                    let positions = positions.split(',').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+                    Some(Posting { doc, positions })
+                })
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| invalid(i, "invalid postings"))?;
+            index.terms.insert(String::from(term), postings);
+        }
+        Ok(index)
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n")
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match (c, c == '\\') {
+            (_, true) => match chars.next() {
+                Some('t') => out.push('\t'),
+                Some('n') => out.push('\n'),
+                Some(other) => out.push(other),
+                None => out.push('\\'),
+            },
+            (c, false) => out.push(c),
+        }
+    }
+    out
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    Term(String),
+    Phrase(Vec<String>),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryError {
+    // byte offset in the query
+    pub position: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Result<Query, QueryError> {
+        let tokens = tokenize(query)?;
+        let mut parser = Parser { tokens: &tokens, pos: 0, end: query.len() };
+        let result = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(result),
+            Some(&(offset, token)) => Err(QueryError { position: offset, message: format!("unexpected '{}'", token) }),
+        }
+    }
+
+    fn positive_terms(&self) -> Vec<&String> {
+        match self {
+            Query::Term(term) => vec![term],
+            Query::Phrase(terms) => terms.iter().collect(),
+            Query::And(left, right) | Query::Or(left, right) => {
+                let mut terms = left.positive_terms();
+                terms.extend(right.positive_terms());
+                terms
+            }
+            Query::Not(_) => Vec::new(),
+        }
+    }
+}
+
+// Split the query into slices: parentheses, phrases with their quotes and words.
+fn tokenize(query: &str) -> Result<Vec<(usize, &str)>, QueryError> {
+    let bytes = query.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        match bytes[i] {
+            b' ' | b'\t' | b'\n' | b'\r' => {
+                i += 1;
+                continue;
+            }
+            b'(' | b')' => i += 1,
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += 1;
+                }
+                if i == bytes.len() {
+                    return Err(QueryError { position: start, message: String::from("unterminated phrase") });
+                }
+                i += 1;
+            }
+            _ => {
+                while i < bytes.len() && !b" \t\n\r()\"".contains(&bytes[i]) {
+                    i += 1;
+                }
+            }
+        }
+        tokens.push((start, &query[start..i]));
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: &'a [(usize, &'a str)],
+    pos: usize,
+    // offset reported for an unexpected end of the query
+    end: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).map(|&(_, token)| token)
+    }
+
+    fn or(&mut self) -> Result<Query, QueryError> {
+        let mut left = self.and()?;
+        while self.peek() == Some("OR") {
+            self.pos += 1;
+            left = Query::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Query, QueryError> {
+        let mut left = self.not()?;
+        loop {
+            match self.peek() {
+                Some("AND") => self.pos += 1,
+                Some("OR") | Some(")") | None => return Ok(left),
+                Some(_) => {}
+            }
+            left = Query::And(Box::new(left), Box::new(self.not()?));
+        }
+    }
+
+    fn not(&mut self) -> Result<Query, QueryError> {
+        if self.peek() == Some("NOT") {
+            self.pos += 1;
+            return Ok(Query::Not(Box::new(self.not()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Query, QueryError> {
+        let (offset, token) = match self.tokens.get(self.pos) {
+            Some(&token) => token,
+            None => return Err(QueryError { position: self.end, message: String::from("unexpected end of query") }),
+        };
+        self.pos += 1;
+        match token {
+            "(" => {
+                let inner = self.or()?;
+                if self.peek() != Some(")") {
+                    return Err(QueryError { position: offset, message: String::from("unclosed '('") });
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            ")" | "AND" | "OR" => Err(QueryError { position: offset, message: format!("unexpected '{}'", token) }),
+            _ => {
+                // words are normalized like the documents, "Don't," is don't and "a.b" is a phrase
+                let words: Vec<String> = word_count::words(token.trim_matches('"')).collect();
+                match words.len() {
+                    0 => Err(QueryError { position: offset, message: format!("no words in '{}'", token) }),
+                    1 if !token.starts_with('"') => Ok(Query::Term(words.into_iter().next().unwrap())),
+                    _ => Ok(Query::Phrase(words)),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,218 @@
+// kv-store.rs
+// Command-line access to a persistent key-value store, see kv_store.rs.
+//
+// rustc kv-store.rs
+// ./kv-store put Blue 10
+// ./kv-store get Blue
+// ./kv-store verify
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::process;
+
+mod args;
+mod crash_report;
+mod kv_store;
+mod random;
+
+use kv_store::KvStore;
+use random::Random;
+
+fn main() {
+    crash_report::install();
+
+    let matches = args::Command::new("kv-store")
+        .about("Persistent key-value store backed by a write-ahead log.")
+        .option("file", Some('f'), "FILE", "Log file of the store [default: store.log]")
+        .flag("sync", Some('s'), "Wait until every write is on the disk")
+        .subcommand(args::Command::new("get").about("Print value of the key").positional("KEY", "Key"))
+        .subcommand(args::Command::new("put").about("Set value of the key")
+            .positional("KEY", "Key")
+            .positional("VALUE", "Value"))
+        .subcommand(args::Command::new("delete").about("Remove the key").positional("KEY", "Key"))
+        .subcommand(args::Command::new("list").about("Print all keys and values sorted by key"))
+        .subcommand(args::Command::new("compact").about("Rewrite the log with live values only"))
+        .subcommand(args::Command::new("verify").about("Check recovery from a log cut at every byte"))
+        .parse();
+
+    let (command, sub) = match matches.subcommand() {
+        Some(subcommand) => subcommand,
+        None => matches.error(args::ErrorKind::Other(String::from("a command is required"))).exit(),
+    };
+    if command == "verify" {
+        match verify() {
+            Ok(points) => println!("recovered correctly at {} truncation points", points),
+            Err(e) => {
+                eprintln!("kv-store: verify: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let file = matches.value("file").unwrap_or("store.log");
+    let result = KvStore::<String>::open(file).and_then(|mut store| {
+        store.set_sync(matches.flag("sync"));
+        let found = run(&mut store, command, sub);
+        if let Some(e) = store.take_compaction_error() {
+            // the change itself is saved
+            eprintln!("kv-store: {}: compaction failed: {}", file, e);
+        }
+        found
+    });
+    match result {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("kv-store: {}: {}", file, e);
+            process::exit(1);
+        }
+    }
+}
+
+// Returns false when the key doesn't exist.
+fn run(store: &mut KvStore<String>, command: &str, sub: &args::Matches) -> io::Result<bool> {
+    let key = sub.value("KEY").unwrap_or("");
+    match command {
+        "get" => match store.get(key) {
+            Some(value) => println!("{}", value),
+            None => return Ok(false),
+        },
+        "put" => {
+            store.put(String::from(key), String::from(sub.value("VALUE").unwrap()))?;
+        }
+        "delete" => {
+            if store.delete(key)?.is_none() {
+                return Ok(false);
+            }
+        }
+        "list" => {
+            let mut entries: Vec<(&String, &String)> = store.iter().collect();
+            entries.sort();
+            for (key, value) in entries {
+                println!("{}\t{}", key, value);
+            }
+        }
+        "compact" => {
+            let before = store.log_len();
+            store.compact()?;
+            println!("{} -> {} bytes", before, store.log_len());
+        }
+        _ => unreachable!(),
+    }
+    Ok(true)
+}
+
+// Write a log of scores, then open every prefix of it as if the program crashed after writing
+// that many bytes. The store must contain exactly the changes of complete records.
+fn verify() -> io::Result<usize> {
+    let dir = env::temp_dir().join(format!("kv-store-verify-{}", process::id()));
+    fs::create_dir_all(&dir)?;
+    let log = dir.join("scores.log");
+    let cut = dir.join("cut.log");
+
+    // state after every record, by the log length
+    let mut states: Vec<(u64, HashMap<String, i32>)> = vec![(0, HashMap::new())];
+    {
+        let mut scores = KvStore::<i32>::open(&log)?;
+        let teams = ["Blue", "Yellow", "Red", "Green", "Здравствуйте"];
+        let mut random = Random::new(42);
+        for _ in 0..60 {
+            let team = String::from(teams[random.below(teams.len())]);
+            match random.below(4) {
+                0 => {
+                    scores.delete(&team)?;
+                }
+                1 => {
+                    scores.entry(team).and_modify(|score| *score += 10)?.or_insert(50)?;
+                }
+                _ => {
+                    scores.put(team, random.below(4096) as i32 - 2048)?;
+                }
+            }
+            if scores.log_len() != states.last().unwrap().0 {
+                states.push((scores.log_len(), snapshot(&scores)));
+            }
+        }
+    }
+
+    let bytes = fs::read(&log)?;
+    for len in 0..=bytes.len() {
+        fs::write(&cut, &bytes[..len])?;
+        let (complete, expected) = states.iter().rev().find(|(end, _)| *end <= len as u64).unwrap();
+        let mut store = KvStore::<i32>::open(&cut)?;
+        check(snapshot(&store) == *expected, format!("wrong state after cut at {}", len))?;
+        check(fs::metadata(&cut)?.len() == *complete, format!("damaged tail not removed at {}", len))?;
+
+        // writes after recovery must not be lost behind the damaged tail
+        store.put(String::from("after crash"), 1)?;
+        drop(store);
+        let store = KvStore::<i32>::open(&cut)?;
+        check(store.get("after crash") == Some(&1), format!("write after recovery lost at {}", len))?;
+    }
+
+    // flipped byte in the middle of a record, the checksum must catch it
+    let (start, _) = &states[states.len() / 2];
+    let mut damaged = bytes.clone();
+    damaged[*start as usize + 10] ^= 0xFF;
+    fs::write(&cut, &damaged)?;
+    let store = KvStore::<i32>::open(&cut)?;
+    check(snapshot(&store) == states[states.len() / 2].1, String::from("damaged record was applied"))?;
+
+    // a whole record which doesn't decode is an error, not a damaged tail to drop
+    let names = dir.join("names.log");
+    {
+        let mut store = KvStore::<String>::open(&names)?;
+        store.put(String::from("Blue"), String::from("Здравствуйте"))?;
+        store.put(String::from("Yellow"), String::from("50"))?;
+    }
+    let len = fs::metadata(&names)?.len();
+    let wrong_type = KvStore::<i32>::open(&names).err().map(|e| e.kind());
+    check(wrong_type == Some(io::ErrorKind::InvalidData), String::from("record of another type was accepted"))?;
+    check(fs::metadata(&names)?.len() == len, String::from("log of another type was truncated"))?;
+
+    // compaction keeps the values
+    let mut store = KvStore::<i32>::open(&log)?;
+    let expected = snapshot(&store);
+    store.compact()?;
+    drop(store);
+    let store = KvStore::<i32>::open(&log)?;
+    check(snapshot(&store) == expected, String::from("compaction changed values"))?;
+
+    // a failed periodic compaction doesn't fail the write which started it
+    let big = dir.join("big.log");
+    let blocked = big.with_extension("compact");
+    fs::create_dir_all(&blocked)?;
+    let mut store = KvStore::<Vec<u8>>::open(&big)?;
+    let mut compaction_error = None;
+    for i in 0..400 {
+        store.put(String::from("key"), vec![i as u8; 4096])?;
+        compaction_error = compaction_error.or_else(|| store.take_compaction_error());
+    }
+    check(compaction_error.is_some(), String::from("compaction did not fail"))?;
+    let before = store.log_len();
+    check(before > 400 * 4096, String::from("log was compacted"))?;
+    drop(store);
+    let mut store = KvStore::<Vec<u8>>::open(&big)?;
+    check(store.get("key") == Some(&vec![143; 4096]), String::from("write before failed compaction lost"))?;
+    fs::remove_dir(&blocked)?;
+    store.put(String::from("key"), Vec::new())?;
+    check(store.take_compaction_error().is_none() && store.log_len() < 4096, String::from("compaction not retried"))?;
+
+    fs::remove_dir_all(&dir)?;
+    Ok(bytes.len() + 1)
+}
+
+fn snapshot(store: &KvStore<i32>) -> HashMap<String, i32> {
+    store.iter().map(|(key, value)| (key.clone(), *value)).collect()
+}
+
+fn check(condition: bool, message: String) -> io::Result<()> {
+    if condition {
+        Ok(())
+    } else {
+        Err(io::Error::other(message))
+    }
+}
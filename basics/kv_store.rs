@@ -0,0 +1,330 @@
+// kv_store.rs
+// The scores HashMap<String, i32> from common-collections.rs made persistent. Every change is
+// appended to a log file before it is applied to the map, the log is replayed when the store is
+// opened again and rewritten (compacted) when most of it are overwritten or deleted values.
+//
+// Record in the log: [length u32][checksum u32][operation u8][key length u32][key][value],
+// numbers are little endian, length and checksum cover everything after the checksum.
+// A record cut by a crash or with a wrong checksum ends the replay and is truncated away. A record
+// with a right checksum which doesn't decode, e.g. a log opened with another value type, is an
+// InvalidData error and the log is left as it is.
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+const PUT: u8 = 1;
+const DELETE: u8 = 2;
+const HEADER_LEN: usize = 8;
+// Log is not compacted before it has at least this many bytes.
+const COMPACT_MIN_LEN: u64 = 1024 * 1024;
+
+// Conversion of stored values from and to bytes.
+pub trait Value: Sized + Clone {
+    fn encode(&self) -> Vec<u8>;
+    fn decode(bytes: &[u8]) -> Option<Self>;
+}
+
+impl Value for i32 {
+    fn encode(&self) -> Vec<u8> {
+        self.to_le_bytes().to_vec()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut le = [0; 4];
+        if bytes.len() != le.len() {
+            return None;
+        }
+        le.copy_from_slice(bytes);
+        Some(i32::from_le_bytes(le))
+    }
+}
+
+impl Value for String {
+    fn encode(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
+
+impl Value for Vec<u8> {
+    fn encode(&self) -> Vec<u8> {
+        self.clone()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        Some(bytes.to_vec())
+    }
+}
+
+pub struct KvStore<V: Value> {
+    path: PathBuf,
+    log: File,
+    map: HashMap<String, V>,
+    // bytes in the log, bytes of records which still hold a live value and their sum
+    log_len: u64,
+    live_len: HashMap<String, u64>,
+    live_total: u64,
+    sync: bool,
+    // failure of the last periodic compaction, the write itself succeeded
+    compaction_error: Option<io::Error>,
+}
+
+impl<V: Value> KvStore<V> {
+    // Open or create the store, replays the log and drops a damaged tail.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<KvStore<V>> {
+        let path = path.as_ref().to_path_buf();
+        let mut log = OpenOptions::new().read(true).append(true).create(true).open(&path)?;
+        let mut bytes = Vec::new();
+        log.read_to_end(&mut bytes)?;
+
+        let mut store = KvStore {
+            path,
+            log,
+            map: HashMap::new(),
+            log_len: 0,
+            live_len: HashMap::new(),
+            live_total: 0,
+            sync: false,
+            compaction_error: None,
+        };
+        let mut offset = 0;
+        while let Some((record, len)) = Record::read(&bytes[offset..])? {
+            store.apply(record, len as u64);
+            offset += len;
+        }
+        if offset < bytes.len() {
+            // crash in the middle of a write, the rest is garbage
+            store.log.set_len(offset as u64)?;
+            store.log.sync_data()?;
+        }
+        Ok(store)
+    }
+
+    // With sync every write waits until the data is on the disk, without it the data may be lost
+    // when the system crashes but not when only the program crashes.
+    pub fn set_sync(&mut self, sync: bool) {
+        self.sync = sync;
+    }
+
+    pub fn get(&self, key: &str) -> Option<&V> {
+        self.map.get(key)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.map.contains_key(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=(&String, &V)> {
+        self.map.iter()
+    }
+
+    // Returns the previous value.
+    pub fn put(&mut self, key: String, value: V) -> io::Result<Option<V>> {
+        let previous = self.map.get(&key).cloned();
+        self.write(Record::Put(key, value))?;
+        Ok(previous)
+    }
+
+    // Returns the deleted value, nothing is written when the key doesn't exist.
+    pub fn delete(&mut self, key: &str) -> io::Result<Option<V>> {
+        let previous = self.map.get(key).cloned();
+        if previous.is_some() {
+            self.write(Record::Delete(String::from(key)))?;
+        }
+        Ok(previous)
+    }
+
+    // Like HashMap::entry, e.g. store.entry(key).and_modify(|v| *v += 1)?.or_insert(1)?
+    pub fn entry(&mut self, key: String) -> Entry<'_, V> {
+        Entry { store: self, key }
+    }
+
+    // Bytes of the log file.
+    pub fn log_len(&self) -> u64 {
+        self.log_len
+    }
+
+    // Error of a periodic compaction after a write, the write is in the log and the store works on
+    // with the longer log. The next write tries to compact again.
+    pub fn take_compaction_error(&mut self) -> Option<io::Error> {
+        self.compaction_error.take()
+    }
+
+    // Rewrite the log with only live values. New log is written next to the old one and renamed
+    // over it, so a crash leaves either the old or the new log, never a mix.
+    pub fn compact(&mut self) -> io::Result<()> {
+        let tmp = self.path.with_extension("compact");
+        let mut bytes = Vec::new();
+        let mut live_len = HashMap::new();
+        for (key, value) in &self.map {
+            let record = Record::Put(key.clone(), value.clone()).encode();
+            live_len.insert(key.clone(), record.len() as u64);
+            bytes.extend(record);
+        }
+        {
+            let mut file = File::create(&tmp)?;
+            file.write_all(&bytes)?;
+            file.sync_all()?;
+        }
+        // opened before the rename, so a failure leaves the store on the old log it can still use
+        let log = OpenOptions::new().read(true).append(true).open(&tmp)?;
+        fs::rename(&tmp, &self.path)?;
+        self.log = log;
+        self.log_len = bytes.len() as u64;
+        self.live_total = self.log_len;
+        self.live_len = live_len;
+        Ok(())
+    }
+
+    fn write(&mut self, record: Record<V>) -> io::Result<()> {
+        let bytes = record.encode();
+        if let Err(e) = self.log.write_all(&bytes) {
+            // don't leave a part of the record, following records would be lost on replay
+            let _ = self.log.set_len(self.log_len);
+            return Err(e);
+        }
+        if self.sync {
+            self.log.sync_data()?;
+        }
+        self.apply(record, bytes.len() as u64);
+
+        // periodic compaction, when more than half of the log is garbage
+        if self.log_len >= COMPACT_MIN_LEN && self.live_total * 2 < self.log_len {
+            self.compaction_error = self.compact().err();
+        }
+        Ok(())
+    }
+
+    fn apply(&mut self, record: Record<V>, len: u64) {
+        self.log_len += len;
+        match record {
+            Record::Put(key, value) => {
+                self.live_total += len;
+                if let Some(previous) = self.live_len.insert(key.clone(), len) {
+                    self.live_total -= previous;
+                }
+                self.map.insert(key, value);
+            }
+            Record::Delete(key) => {
+                if let Some(previous) = self.live_len.remove(&key) {
+                    self.live_total -= previous;
+                }
+                self.map.remove(&key);
+            }
+        }
+    }
+}
+
+pub struct Entry<'a, V: Value> {
+    store: &'a mut KvStore<V>,
+    key: String,
+}
+
+impl<'a, V: Value> Entry<'a, V> {
+    // Change an existing value, the change is written to the log.
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> io::Result<Entry<'a, V>> {
+        if let Some(value) = self.store.get(&self.key) {
+            let mut value = value.clone();
+            f(&mut value);
+            self.store.put(self.key.clone(), value)?;
+        }
+        Ok(self)
+    }
+
+    // Insert the default when the key doesn't exist, returns the value.
+    pub fn or_insert(self, default: V) -> io::Result<&'a V> {
+        let Entry { store, key } = self;
+        if !store.contains_key(&key) {
+            store.put(key.clone(), default)?;
+        }
+        let store: &'a KvStore<V> = store;
+        Ok(&store.map[&key])
+    }
+}
+
+enum Record<V> {
+    Put(String, V),
+    Delete(String),
+}
+
+impl<V: Value> Record<V> {
+    fn encode(&self) -> Vec<u8> {
+        let (operation, key, value) = match self {
+            Record::Put(key, value) => (PUT, key, value.encode()),
+            Record::Delete(key) => (DELETE, key, Vec::new()),
+        };
+        let mut body = vec![operation];
+        body.extend_from_slice(&(key.len() as u32).to_le_bytes());
+        body.extend_from_slice(key.as_bytes());
+        body.extend_from_slice(&value);
+
+        let mut bytes = Vec::with_capacity(HEADER_LEN + body.len());
+        bytes.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&crc32(&body).to_le_bytes());
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+
+    // Record at the start of bytes and its length, None for incomplete or damaged record.
+    fn read(bytes: &[u8]) -> io::Result<Option<(Record<V>, usize)>> {
+        let (len, checksum) = match (read_u32(bytes), bytes.get(4..).and_then(read_u32)) {
+            (Some(len), Some(checksum)) => (len as usize, checksum),
+            _ => return Ok(None),
+        };
+        let body = match HEADER_LEN.checked_add(len).and_then(|end| bytes.get(HEADER_LEN..end)) {
+            Some(body) if crc32(body) == checksum => body,
+            _ => return Ok(None),
+        };
+        match Record::decode(body) {
+            Some(record) => Ok(Some((record, HEADER_LEN + len))),
+            None => Err(io::Error::new(io::ErrorKind::InvalidData, "record with a right checksum doesn't decode")),
+        }
+    }
+
+    fn decode(body: &[u8]) -> Option<Record<V>> {
+        let (&operation, rest) = body.split_first()?;
+        let key_len = read_u32(rest)? as usize;
+        let key = String::from_utf8(rest.get(4..4usize.checked_add(key_len)?)?.to_vec()).ok()?;
+        let value = &rest[4 + key_len..];
+        match operation {
+            PUT => Some(Record::Put(key, V::decode(value)?)),
+            DELETE if value.is_empty() => Some(Record::Delete(key)),
+            _ => None,
+        }
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> Option<u32> {
+    let mut le = [0; 4];
+    le.copy_from_slice(bytes.get(..4)?);
+    Some(u32::from_le_bytes(le))
+}
+
+// CRC-32 (IEEE), bit by bit, records are small.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
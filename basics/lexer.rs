@@ -0,0 +1,322 @@
+// lexer.rs
+// Tokens of Rust source code, enough of the language for the examples of this repository:
+// identifiers and keywords, lifetimes, char, string and raw string literals, numeric literals
+// checked by literal.rs, line and nested block comments, and punctuation. Whitespace is skipped,
+// everything else is a token with its span, so the tokens and the whitespace between them give
+// back the source.
+//
+// Not handled: shebang lines, C string literals c"..." and the reserved prefixes of edition 2021.
+//
+// Binaries using this module must also declare `mod convert;` and `mod literal;`.
+#![allow(dead_code)]
+
+use std::fmt;
+
+use crate::literal::{self, ErrorKind, LiteralError};
+
+pub const KEYWORDS: [&str; 49] = [
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe",
+    "use", "where", "while",
+    // reserved for the future
+    "abstract", "become", "box", "do", "final", "macro", "override", "priv", "try", "typeof",
+    "virtual",
+];
+
+// Longest first, a >>= b is one token and not > > =.
+const PUNCTUATION: [&str; 51] = [
+    "<<=", ">>=", "...", "..=",
+    "::", "->", "=>", "==", "!=", "<=", ">=", "&&", "||", "+=", "-=", "*=", "/=", "%=", "^=", "&=", "|=",
+    "<<", ">>", "..",
+    "+", "-", "*", "/", "%", "^", "!", "&", "|", "=", "<", ">", "@", ".", ",", ";", ":", "#", "$", "?",
+    "~", "(", ")", "[", "]", "{", "}",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenKind {
+    // also raw identifiers like r#type, and _
+    Ident,
+    Keyword,
+    // 'a, also labels like 'outer
+    Lifetime,
+    // 'c' and b'c'
+    Char,
+    // "..." and b"..."
+    Str,
+    // r"...", r#"..."# and br"..."
+    RawStr,
+    Number,
+    // doc comments /// and //! too
+    LineComment,
+    BlockComment,
+    Punct,
+}
+
+// Bytes start..end of the source, line and column (in chars) of start both begin at 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+// All tokens, stops at the first error.
+pub fn tokenize(source: &str) -> Result<Vec<Token<'_>>, LexError> {
+    lexer(source).collect()
+}
+
+// Tokens one by one, nothing more after an error.
+pub fn lexer(source: &str) -> Lexer<'_> {
+    Lexer { source, pos: 0, line: 1, line_start: 0 }
+}
+
+pub struct Lexer<'a> {
+    source: &'a str,
+    pos: usize,
+    // line of pos and the offset where it begins
+    line: usize,
+    line_start: usize,
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.source[self.pos..];
+        let start = self.pos + rest.find(|c: char| !c.is_whitespace()).unwrap_or(rest.len());
+        self.move_to(start);
+        if start == self.source.len() {
+            return None;
+        }
+        let span = Span { start, end: start, line: self.line, column: self.source[self.line_start..start].chars().count() + 1 };
+        match self.token(start) {
+            Ok((kind, end)) => {
+                self.move_to(end);
+                Some(Ok(Token { kind, text: &self.source[start..end], span: Span { end, ..span } }))
+            }
+            Err(e) => {
+                self.move_to(self.source.len());
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl<'a> Lexer<'a> {
+    fn peek(&self, at: usize) -> Option<char> {
+        self.source.get(at..)?.chars().next()
+    }
+
+    fn move_to(&mut self, pos: usize) {
+        let passed = &self.source[self.pos..pos];
+        if let Some(i) = passed.rfind('\n') {
+            self.line += passed.matches('\n').count();
+            self.line_start = self.pos + i + 1;
+        }
+        self.pos = pos;
+    }
+
+    fn error_at(&self, at: usize, message: String) -> LexError {
+        let line_start = self.source[..at].rfind('\n').map_or(0, |i| i + 1);
+        LexError {
+            line: self.source[..at].matches('\n').count() + 1,
+            column: self.source[line_start..at].chars().count() + 1,
+            message,
+        }
+    }
+
+    // Kind and end of the token at start.
+    fn token(&self, start: usize) -> Result<(TokenKind, usize), LexError> {
+        let rest = &self.source[start..];
+        let c = self.peek(start).unwrap();
+        let next = self.peek(start + c.len_utf8());
+        let token = match (c, next) {
+            ('/', Some('/')) => (TokenKind::LineComment, start + rest.find('\n').unwrap_or(rest.len())),
+            ('/', Some('*')) => (TokenKind::BlockComment, self.block_comment(start)?),
+            ('r', Some('#')) if self.peek(start + 2).is_some_and(is_ident_start) => {
+                (TokenKind::Ident, self.ident(start + 2))
+            }
+            ('r', Some('"')) | ('r', Some('#')) => (TokenKind::RawStr, self.raw_string(start, start + 1)?),
+            ('b', Some('r')) if matches!(self.peek(start + 2), Some('"') | Some('#')) => {
+                (TokenKind::RawStr, self.raw_string(start, start + 2)?)
+            }
+            ('b', Some('\'')) => (TokenKind::Char, self.char(start + 1, true)?),
+            ('b', Some('"')) => (TokenKind::Str, self.string(start + 1, true)?),
+            (c, _) if is_ident_start(c) => {
+                let end = self.ident(start);
+                let kind = if KEYWORDS.contains(&&self.source[start..end]) { TokenKind::Keyword } else { TokenKind::Ident };
+                (kind, end)
+            }
+            (c, _) if c.is_ascii_digit() => (TokenKind::Number, self.number(start)?),
+            ('\'', _) => self.char_or_lifetime(start)?,
+            ('"', _) => (TokenKind::Str, self.string(start, false)?),
+            _ => match PUNCTUATION.iter().find(|p| rest.starts_with(*p)) {
+                Some(p) => (TokenKind::Punct, start + p.len()),
+                None => return Err(self.error_at(start, format!("unexpected character {:?}", c))),
+            },
+        };
+        Ok(token)
+    }
+
+    fn ident(&self, start: usize) -> usize {
+        let rest = &self.source[start..];
+        start + rest.find(|c: char| !is_ident_continue(c)).unwrap_or(rest.len())
+    }
+
+    // Block comments nest, /* /* */ */ is one comment.
+    fn block_comment(&self, start: usize) -> Result<usize, LexError> {
+        let mut depth = 0;
+        let mut at = start;
+        while at < self.source.len() {
+            let rest = &self.source[at..];
+            if rest.starts_with("/*") {
+                depth += 1;
+                at += 2;
+            } else if rest.starts_with("*/") {
+                depth -= 1;
+                at += 2;
+                if depth == 0 {
+                    return Ok(at);
+                }
+            } else {
+                at += rest.chars().next().unwrap().len_utf8();
+            }
+        }
+        Err(self.error_at(start, String::from("unterminated block comment")))
+    }
+
+    fn number(&self, start: usize) -> Result<usize, LexError> {
+        let end = start + literal::scan(&self.source[start..]);
+        let text = &self.source[start..end];
+        match literal::parse(text) {
+            // the range is checked later by the compiler: without a suffix the type comes from the
+            // code around, 3_000_000_000 may be a u32, and 128i8 is fine after a minus
+            Ok(_) | Err(LiteralError { kind: ErrorKind::OutOfRange(_), .. }) => Ok(end),
+            Err(e) => Err(self.error_at(start + e.start, e.kind.to_string())),
+        }
+    }
+
+    // 'a' is a char, 'a a lifetime.
+    fn char_or_lifetime(&self, start: usize) -> Result<(TokenKind, usize), LexError> {
+        match self.peek(start + 1) {
+            Some(c) if c != '\\' && is_ident_start(c) && self.peek(start + 1 + c.len_utf8()) != Some('\'') => {
+                Ok((TokenKind::Lifetime, self.ident(start + 1)))
+            }
+            _ => Ok((TokenKind::Char, self.char(start, false)?)),
+        }
+    }
+
+    // One char or escape between the quotes, the quote is at start.
+    fn char(&self, start: usize, byte: bool) -> Result<usize, LexError> {
+        let at = start + 1;
+        let after = match self.peek(at) {
+            Some('\\') => self.escape(at, byte)?,
+            Some('\'') => return Err(self.error_at(start, String::from("empty character literal"))),
+            Some('\n') | None => return Err(self.error_at(start, String::from("unterminated character literal"))),
+            Some(c) if byte && !c.is_ascii() => {
+                return Err(self.error_at(at, format!("non-ASCII character {:?} in byte literal", c)));
+            }
+            Some(c) => at + c.len_utf8(),
+        };
+        match self.peek(after) {
+            Some('\'') => Ok(after + 1),
+            _ => Err(self.error_at(start, String::from("character literal may only contain one character"))),
+        }
+    }
+
+    // The quote is at start, a string may span lines.
+    fn string(&self, start: usize, byte: bool) -> Result<usize, LexError> {
+        let mut at = start + 1;
+        loop {
+            match self.peek(at) {
+                Some('"') => return Ok(at + 1),
+                Some('\\') => at = self.escape(at, byte)?,
+                Some(c) if byte && !c.is_ascii() => {
+                    return Err(self.error_at(at, format!("non-ASCII character {:?} in byte string", c)));
+                }
+                Some(c) => at += c.len_utf8(),
+                None => return Err(self.error_at(start, String::from("unterminated string"))),
+            }
+        }
+    }
+
+    // r"..." or r#"..."# with any number of #, prefix_end is after the r.
+    fn raw_string(&self, start: usize, prefix_end: usize) -> Result<usize, LexError> {
+        let hashes = self.source[prefix_end..].bytes().take_while(|&b| b == b'#').count();
+        let quote = prefix_end + hashes;
+        if self.peek(quote) != Some('"') {
+            return Err(self.error_at(quote, String::from("expected '\"' in raw string")));
+        }
+        let closing = format!("\"{}", "#".repeat(hashes));
+        match self.source[quote + 1..].find(&closing) {
+            Some(i) => Ok(quote + 1 + i + closing.len()),
+            None => Err(self.error_at(start, String::from("unterminated raw string"))),
+        }
+    }
+
+    // The backslash is at start, returns the end of the escape.
+    fn escape(&self, start: usize, byte: bool) -> Result<usize, LexError> {
+        let error = |message: &str| Err(self.error_at(start, String::from(message)));
+        match self.peek(start + 1) {
+            Some('n') | Some('r') | Some('t') | Some('\\') | Some('0') | Some('\'') | Some('"') => Ok(start + 2),
+            // a line continuation skips the line break and the indentation after it
+            Some('\n') => Ok(start + 2),
+            Some('\r') if self.peek(start + 2) == Some('\n') => Ok(start + 3),
+            Some('x') => {
+                let digits = self.source.get(start + 2..start + 4).unwrap_or("");
+                match u8::from_str_radix(digits, 16) {
+                    Ok(b) if b > 0x7f && !byte => error("\\x escape must be at most \\x7f, use \\u{..}"),
+                    Ok(_) if digits.bytes().all(|b| b.is_ascii_hexdigit()) => Ok(start + 4),
+                    _ => error("\\x escape must have two hexadecimal digits"),
+                }
+            }
+            Some('u') if byte => error("unicode escape in byte literal"),
+            Some('u') => {
+                let rest = &self.source[start + 2..];
+                let close = match (rest.starts_with('{'), rest.find('}')) {
+                    (true, Some(close)) => close,
+                    _ => return error("unicode escape must be \\u{...}"),
+                };
+                let digits: String = rest[1..close].chars().filter(|&c| c != '_').collect();
+                let valid = (1..=6).contains(&digits.len())
+                    && u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32).is_some();
+                if !valid {
+                    return error("invalid unicode escape");
+                }
+                Ok(start + 2 + close + 1)
+            }
+            Some(c) => Err(self.error_at(start, format!("unknown character escape '\\{}'", c.escape_default()))),
+            None => error("unterminated escape"),
+        }
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c == '_' || c.is_alphabetic()
+}
+
+fn is_ident_continue(c: char) -> bool {
+    c == '_' || c.is_alphanumeric()
+}
@@ -0,0 +1,348 @@
+// literal.rs
+// Numeric literals written the way Rust source writes them, see data-types.rs:
+//
+// 1_000  5i32  -2_147_483_648i32  0xffffffffu32  0o777  0b0111_1111  2.5f32  1e-3_f64
+//
+// Underscores may appear anywhere after the first digit, the suffix chooses the type and without
+// one integers are i32 and floats are f64 like in Rust. Hexadecimal digits include a to f, so
+// 0x1f32 is the integer 7986 and not a float. A leading - makes the value negative, a value out of
+// range of its type is an error instead of wrapping around.
+//
+// Binaries using this module must also declare `mod convert;`.
+#![allow(dead_code)]
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::convert::{self, ConversionError, Numeric};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Literal {
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
+    Isize(isize),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    Usize(usize),
+    F32(f32),
+    F64(f64),
+}
+
+pub const INTEGER_SUFFIXES: [&str; 12] =
+    ["i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize"];
+pub const FLOAT_SUFFIXES: [&str; 2] = ["f32", "f64"];
+
+impl Literal {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Literal::I8(_) => "i8",
+            Literal::I16(_) => "i16",
+            Literal::I32(_) => "i32",
+            Literal::I64(_) => "i64",
+            Literal::I128(_) => "i128",
+            Literal::Isize(_) => "isize",
+            Literal::U8(_) => "u8",
+            Literal::U16(_) => "u16",
+            Literal::U32(_) => "u32",
+            Literal::U64(_) => "u64",
+            Literal::U128(_) => "u128",
+            Literal::Usize(_) => "usize",
+            Literal::F32(_) => "f32",
+            Literal::F64(_) => "f64",
+        }
+    }
+
+    // The same value as another type, an error unless it is exactly the same.
+    pub fn to<T: Numeric>(&self) -> Result<T, ConversionError> {
+        match *self {
+            Literal::I8(n) => convert::checked(n),
+            Literal::I16(n) => convert::checked(n),
+            Literal::I32(n) => convert::checked(n),
+            Literal::I64(n) => convert::checked(n),
+            Literal::I128(n) => convert::checked(n),
+            Literal::Isize(n) => convert::checked(n),
+            Literal::U8(n) => convert::checked(n),
+            Literal::U16(n) => convert::checked(n),
+            Literal::U32(n) => convert::checked(n),
+            Literal::U64(n) => convert::checked(n),
+            Literal::U128(n) => convert::checked(n),
+            Literal::Usize(n) => convert::checked(n),
+            Literal::F32(x) => convert::checked(x),
+            Literal::F64(x) => convert::checked(x),
+        }
+    }
+}
+
+// Decimal value with its suffix, parsed back to the same literal.
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Literal::I8(n) => write!(f, "{}", n)?,
+            Literal::I16(n) => write!(f, "{}", n)?,
+            Literal::I32(n) => write!(f, "{}", n)?,
+            Literal::I64(n) => write!(f, "{}", n)?,
+            Literal::I128(n) => write!(f, "{}", n)?,
+            Literal::Isize(n) => write!(f, "{}", n)?,
+            Literal::U8(n) => write!(f, "{}", n)?,
+            Literal::U16(n) => write!(f, "{}", n)?,
+            Literal::U32(n) => write!(f, "{}", n)?,
+            Literal::U64(n) => write!(f, "{}", n)?,
+            Literal::U128(n) => write!(f, "{}", n)?,
+            Literal::Usize(n) => write!(f, "{}", n)?,
+            // Debug keeps the shortest digits that give the same float, and the .0 of whole numbers
+            Literal::F32(x) => write!(f, "{:?}", x)?,
+            Literal::F64(x) => write!(f, "{:?}", x)?,
+        }
+        f.write_str(self.type_name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+    // nothing where a digit must be
+    MissingDigits,
+    InvalidDigit { digit: char, radix: u32 },
+    // e without digits after it
+    MissingExponent,
+    UnknownSuffix(String),
+    // integer suffix on a float, or float suffix on a binary, octal or hexadecimal number
+    IncompatibleSuffix(&'static str),
+    UnexpectedChar(char),
+    // more than the 128 bits of any integer type
+    TooLarge,
+    OutOfRange(ConversionError),
+}
+
+// Bytes start..end of the parsed text are the cause, start == end when something is missing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiteralError {
+    pub start: usize,
+    pub end: usize,
+    pub kind: ErrorKind,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorKind::MissingDigits => f.write_str("expected a digit"),
+            ErrorKind::InvalidDigit { digit, radix } => {
+                let name = match radix {
+                    2 => "binary",
+                    8 => "octal",
+                    _ => "decimal",
+                };
+                write!(f, "invalid digit '{}' in {} literal", digit, name)
+            }
+            ErrorKind::MissingExponent => f.write_str("expected at least one digit in exponent"),
+            ErrorKind::UnknownSuffix(suffix) => {
+                write!(f, "invalid suffix '{}', expected one of {} or {}",
+                       suffix, INTEGER_SUFFIXES.join(", "), FLOAT_SUFFIXES.join(", "))
+            }
+            ErrorKind::IncompatibleSuffix(reason) => f.write_str(reason),
+            ErrorKind::UnexpectedChar(c) => write!(f, "unexpected character {:?}", c),
+            ErrorKind::TooLarge => f.write_str("integer literal is too large for any type"),
+            ErrorKind::OutOfRange(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl fmt::Display for LiteralError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.start + 1, self.kind)
+    }
+}
+
+impl FromStr for Literal {
+    type Err = LiteralError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s)
+    }
+}
+
+// The whole text must be one literal, spaces included.
+pub fn parse(s: &str) -> Result<Literal, LiteralError> {
+    parse_as(s, "i32")
+}
+
+// Like parse, but an integer without a suffix is i128, or u128 when it is too large for i128, so
+// only the literals larger than any type are out of range.
+pub fn parse_widest(s: &str) -> Result<Literal, LiteralError> {
+    match parse_as(s, "i128") {
+        Err(LiteralError { kind: ErrorKind::OutOfRange(_), .. }) if !s.starts_with('-') => parse_as(s, "u128"),
+        result => result,
+    }
+}
+
+// An integer without a suffix has the type of default_suffix.
+fn parse_as(s: &str, default_suffix: &str) -> Result<Literal, LiteralError> {
+    let bytes = s.as_bytes();
+    let error = |start: usize, end: usize, kind: ErrorKind| Err(LiteralError { start, end, kind });
+    let negative = s.starts_with('-');
+    let start = negative as usize;
+    let radix = match s.get(start..start + 2) {
+        Some("0x") => 16,
+        Some("0o") => 8,
+        Some("0b") => 2,
+        _ => 10,
+    };
+    let digits_start = if radix == 10 { start } else { start + 2 };
+
+    // binary and octal literals take all decimal digits to report the invalid ones
+    let scan_radix = if radix == 16 { 16 } else { 10 };
+    let scan = |from: usize| {
+        from + bytes[from..].iter().take_while(|&&b| b == b'_' || (b as char).is_digit(scan_radix)).count()
+    };
+    let mut end = scan(digits_start);
+    let digits = &s[digits_start..end];
+    // a decimal literal starts with a digit, _1 would be a name
+    let first_digit = if radix == 10 { digits.chars().next() } else { digits.chars().find(|&c| c != '_') };
+    if !first_digit.is_some_and(|c| c.is_digit(scan_radix)) {
+        return error(digits_start, digits_start, ErrorKind::MissingDigits);
+    }
+    if let Some(i) = digits.find(|c: char| !c.is_digit(radix) && c != '_') {
+        let digit = digits[i..].chars().next().unwrap();
+        return error(digits_start + i, digits_start + i + 1, ErrorKind::InvalidDigit { digit, radix });
+    }
+
+    let mut is_float = false;
+    if radix == 10 {
+        if is_fraction(bytes, end) {
+            is_float = true;
+            end = scan(end + 1);
+        }
+        if matches!(bytes.get(end), Some(b'e') | Some(b'E')) {
+            let e = end;
+            end += 1;
+            if matches!(bytes.get(end), Some(b'+') | Some(b'-')) {
+                end += 1;
+            }
+            let exponent_end = scan(end);
+            if !s[end..exponent_end].bytes().any(|b| b.is_ascii_digit()) {
+                return error(e, exponent_end, ErrorKind::MissingExponent);
+            }
+            is_float = true;
+            end = exponent_end;
+        }
+    }
+
+    let suffix = &s[end..];
+    let suffix_len = suffix.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(suffix.len());
+    if let Some(c) = suffix[suffix_len..].chars().next() {
+        return error(end + suffix_len, end + suffix_len + c.len_utf8(), ErrorKind::UnexpectedChar(c));
+    }
+    let suffix_span = (end, s.len());
+    let number = &s[..end];
+    let is_float_suffix = FLOAT_SUFFIXES.contains(&suffix);
+    if !suffix.is_empty() && !is_float_suffix && !INTEGER_SUFFIXES.contains(&suffix) {
+        return error(suffix_span.0, suffix_span.1, ErrorKind::UnknownSuffix(String::from(suffix)));
+    }
+    if is_float && !suffix.is_empty() && !is_float_suffix {
+        return error(suffix_span.0, suffix_span.1, ErrorKind::IncompatibleSuffix("integer suffix on a float literal"));
+    }
+    if radix != 10 && is_float_suffix {
+        let reason = match radix {
+            2 => "binary float literal is not supported",
+            8 => "octal float literal is not supported",
+            _ => unreachable!("f is a hexadecimal digit"),
+        };
+        return error(suffix_span.0, suffix_span.1, ErrorKind::IncompatibleSuffix(reason));
+    }
+
+    if is_float || is_float_suffix {
+        let text: String = number.chars().filter(|&c| c != '_').collect();
+        let out_of_range = |e| LiteralError { start: 0, end, kind: ErrorKind::OutOfRange(e) };
+        return match suffix {
+            "f32" => float::<f32>(&text).map(Literal::F32).map_err(out_of_range),
+            _ => float::<f64>(&text).map(Literal::F64).map_err(out_of_range),
+        };
+    }
+
+    let mut magnitude: u128 = 0;
+    for c in digits.chars().filter(|&c| c != '_') {
+        magnitude = match magnitude.checked_mul(radix as u128).and_then(|m| m.checked_add(c.to_digit(radix).unwrap() as u128)) {
+            Some(m) => m,
+            None => return error(0, end, ErrorKind::TooLarge),
+        };
+    }
+    let value = |e| LiteralError { start: 0, end, kind: ErrorKind::OutOfRange(e) };
+    let literal = match if suffix.is_empty() { default_suffix } else { suffix } {
+        "i8" => Literal::I8(integer(negative, magnitude).map_err(value)?),
+        "i16" => Literal::I16(integer(negative, magnitude).map_err(value)?),
+        "i32" => Literal::I32(integer(negative, magnitude).map_err(value)?),
+        "i64" => Literal::I64(integer(negative, magnitude).map_err(value)?),
+        "i128" => Literal::I128(integer(negative, magnitude).map_err(value)?),
+        "isize" => Literal::Isize(integer(negative, magnitude).map_err(value)?),
+        "u8" => Literal::U8(integer(negative, magnitude).map_err(value)?),
+        "u16" => Literal::U16(integer(negative, magnitude).map_err(value)?),
+        "u32" => Literal::U32(integer(negative, magnitude).map_err(value)?),
+        "u64" => Literal::U64(integer(negative, magnitude).map_err(value)?),
+        "u128" => Literal::U128(integer(negative, magnitude).map_err(value)?),
+        "usize" => Literal::Usize(integer(negative, magnitude).map_err(value)?),
+        _ => unreachable!("suffix is checked above"),
+    };
+    Ok(literal)
+}
+
+// Length in bytes of the literal at the start of source code, the text after it may be anything.
+// The literal isn't checked, a digit followed by letters is taken as one literal and parse tells
+// what is wrong with it.
+pub fn scan(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let digits = |from: usize| from + bytes[from..].iter().take_while(|&&b| b == b'_' || b.is_ascii_digit()).count();
+    let word = |from: usize| from + bytes[from..].iter().take_while(|&&b| b == b'_' || b.is_ascii_alphanumeric()).count();
+    if matches!(s.get(..2), Some("0x") | Some("0o") | Some("0b")) {
+        return word(2);
+    }
+    let mut end = digits(0);
+    if is_fraction(bytes, end) {
+        end = digits(end + 1);
+    }
+    // the sign of an exponent, 1e-3 but not 1usize-3
+    if matches!(bytes.get(end), Some(b'e') | Some(b'E')) && matches!(bytes.get(end + 1), Some(b'+') | Some(b'-')) {
+        end += 2;
+    }
+    word(end)
+}
+
+// 1. is a float but not 1..2, 1.max(2) or 1._5
+fn is_fraction(bytes: &[u8], dot: usize) -> bool {
+    let after_dot = bytes.get(dot + 1).copied();
+    bytes.get(dot) == Some(&b'.') && !after_dot.is_some_and(|b| b == b'.' || b == b'_' || b.is_ascii_alphabetic())
+}
+
+fn integer<T: Numeric>(negative: bool, magnitude: u128) -> Result<T, ConversionError> {
+    if !negative {
+        return convert::checked(magnitude);
+    }
+    match 0i128.checked_sub_unsigned(magnitude) {
+        Some(value) => convert::checked(value),
+        None => Err(ConversionError::Overflow {
+            value: format!("-{}", magnitude),
+            target: T::NAME,
+            range: format!("{} to {}", T::MIN, T::MAX),
+        }),
+    }
+}
+
+// The text is already checked, the standard parser rounds it to the nearest float.
+fn float<T: Numeric + FromStr>(text: &str) -> Result<T, ConversionError>
+    where
+        T::Err: fmt::Debug,
+{
+    let value: T = text.parse().unwrap();
+    match value.to_value() {
+        convert::Value::Float(f) if f.is_infinite() => Err(ConversionError::Overflow {
+            value: String::from(text),
+            target: T::NAME,
+            range: format!("{} to {}", T::MIN, T::MAX),
+        }),
+        _ => Ok(value),
+    }
+}
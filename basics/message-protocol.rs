@@ -0,0 +1,263 @@
+// message-protocol.rs
+// Formats of Message, see message_text.rs and message_wire.rs.
+//
+// rustc message-protocol.rs
+// ./message-protocol check
+// ./message-protocol fmt script.txt
+// ./message-protocol encode script.txt > script.bin
+// ./message-protocol decode script.bin
+
+use std::io;
+use std::io::{Read, Write};
+use std::process;
+
+mod args;
+mod crash_report;
+mod input;
+mod message;
+mod message_text;
+mod message_wire;
+mod random;
+
+use message::Message;
+use message_wire::{DecodeError, Decoder};
+use random::Random;
+
+fn main() {
+    crash_report::install();
+
+    let matches = args::Command::new("message-protocol")
+        .about("Read, write and check messages.")
+        .subcommand(args::Command::new("check")
+            .about("Check that messages are read back as they were written")
+            .option("rounds", Some('r'), "N", "Number of random messages [default: 10000]"))
+        .subcommand(args::Command::new("fmt")
+            .about("Print the messages of a text script in the canonical form")
+            .positional("FILE", "Script, - for standard input"))
+        .subcommand(args::Command::new("encode")
+            .about("Write the messages of a text script in the binary format to standard output")
+            .positional("FILE", "Script, - for standard input"))
+        .subcommand(args::Command::new("decode")
+            .about("Print messages in the binary format as a text script")
+            .positional("FILE", "Binary messages, - for standard input"))
+        .parse();
+
+    match matches.subcommand() {
+        Some(("check", sub)) => {
+            let rounds: usize = sub.get("rounds").unwrap_or_else(|e| e.exit()).unwrap_or(10_000);
+            check_text(rounds);
+            println!("text format: {} random messages read back unchanged", rounds);
+            check_wire(rounds);
+            println!("binary format: {} random messages read back unchanged, random bytes rejected", rounds);
+        }
+        Some(("fmt", sub)) => {
+            let messages = read_script(sub.value("FILE").unwrap());
+            print!("{}", message_text::to_script(&messages));
+        }
+        Some(("encode", sub)) => {
+            let messages = read_script(sub.value("FILE").unwrap());
+            if let Err(e) = io::stdout().write_all(&message_wire::to_bytes(&messages)) {
+                exit_with(&e.to_string());
+            }
+        }
+        Some(("decode", sub)) => {
+            let path = sub.value("FILE").unwrap();
+            let reader: Box<dyn Read> = if path == "-" {
+                Box::new(io::stdin())
+            } else {
+                Box::new(std::fs::File::open(path).unwrap_or_else(|e| exit_with(&format!("{}: {}", path, e))))
+            };
+            for message in Decoder::new(reader) {
+                match message {
+                    Ok(message) => println!("{}", message_text::to_text(&message)),
+                    Err(e) => exit_with(&format!("{}: {}", path, e)),
+                }
+            }
+        }
+        _ => matches.error(args::ErrorKind::Other(String::from("a command is required"))).exit(),
+    }
+}
+
+fn read_script(path: &str) -> Vec<Message> {
+    let text = input::read_to_string(path)
+        .unwrap_or_else(|e| exit_with(&format!("{}: {}", path, e)));
+    message_text::parse(&text).unwrap_or_else(|e| exit_with(&format!("{}:{}", path, e)))
+}
+
+fn check_text(rounds: usize) {
+    let examples = [
+        ("QUIT", Message::Quit),
+        ("MOVE 3 -4", Message::Move { x: 3, y: -4 }),
+        ("WRITE \"hello\"", Message::Write(String::from("hello"))),
+        ("COLOR 255 0 0", Message::ChangeColor(255, 0, 0)),
+        ("WRITE \"say \\\"hi\\\"\\n\\\\\"", Message::Write(String::from("say \"hi\"\n\\"))),
+        ("WRITE \"\\u{7f}\\0\\t\"", Message::Write(String::from("\u{7f}\0\t"))),
+        ("WRITE \"Здравствуйте 👋\"", Message::Write(String::from("Здравствуйте 👋"))),
+        ("MOVE -2147483648 2147483647", Message::Move { x: i32::MIN, y: i32::MAX }),
+    ];
+    for (text, message) in examples.iter() {
+        assert_eq!(text.parse::<Message>().as_ref(), Ok(message));
+        assert_eq!(message_text::to_text(message), *text);
+    }
+
+    // extra spaces, comments and escapes which are not written by to_text
+    assert_eq!("  MOVE\t1   2  # right and down".parse(), Ok(Message::Move { x: 1, y: 2 }));
+    assert_eq!("WRITE \"a#b\\u{41}\" #".parse(), Ok(Message::Write(String::from("a#bA"))));
+    let script = "# a script\r\nMOVE 1 1\r\n\r\n  QUIT\n";
+    assert_eq!(message_text::parse(script), Ok(vec![Message::Move { x: 1, y: 1 }, Message::Quit]));
+
+    // errors point at the line and column of the problem
+    let errors = [
+        ("", 1, "missing message"),
+        ("JUMP 1 2", 1, "unknown command 'JUMP', expected QUIT, MOVE, WRITE or COLOR"),
+        ("move 1 2", 1, "unknown command 'move', expected QUIT, MOVE, WRITE or COLOR"),
+        ("MOVE 1", 7, "missing integer"),
+        ("MOVE 1 two", 8, "expected an integer, found 'two'"),
+        ("MOVE 1 +2", 8, "expected an integer, found '+2'"),
+        ("COLOR 1 2 3000000000", 11, "integer 3000000000 is out of range of i32"),
+        ("QUIT now", 6, "unexpected 'now' after the message"),
+        ("WRITE hello", 7, "expected a string in double quotes, found 'hello'"),
+        ("WRITE", 6, "missing string"),
+        ("WRITE \"hello", 7, "unterminated string"),
+        ("WRITE \"ж\\q\"", 9, "unknown escape '\\q'"),
+        ("WRITE \"\\u{110000}\"", 8, "invalid unicode escape, expected \\u{hex} of a char"),
+        ("WRITE \"\\u{d800}\"", 8, "invalid unicode escape, expected \\u{hex} of a char"),
+        ("WRITE \"a\" \"b\"", 11, "unexpected '\"b\"' after the message"),
+    ];
+    for &(text, column, message) in errors.iter() {
+        let error = text.parse::<Message>().unwrap_err();
+        assert_eq!((error.line, error.column, error.message.as_str()), (1, column, message), "{:?}", text);
+    }
+    let error = message_text::parse("QUIT\nMOVE 1 2\nCOLOR 1 2").unwrap_err();
+    assert_eq!((error.line, error.column), (3, 10));
+    assert_eq!(error.to_string(), "3:10: missing integer");
+
+    // random messages, alone and as a script
+    let mut random = Random::new(0x9e37_79b9_7f4a_7c15);
+    let messages: Vec<Message> = (0..rounds).map(|_| random_message(&mut random)).collect();
+    for message in &messages {
+        let text = message_text::to_text(message);
+        assert!(!text.contains('\n'), "{:?}", text);
+        assert_eq!(text.parse::<Message>().as_ref(), Ok(message), "{:?}", text);
+    }
+    let script = message_text::to_script(&messages);
+    assert_eq!(message_text::parse(&script), Ok(messages));
+}
+
+fn check_wire(rounds: usize) {
+    let examples: &[(Message, &[u8])] = &[
+        (Message::Quit, &[0]),
+        (Message::Move { x: 3, y: -4 }, &[1, 6, 7]),
+        (Message::Move { x: -64, y: 64 }, &[1, 127, 128, 1]),
+        (Message::Move { x: i32::MAX, y: i32::MIN }, &[1, 254, 255, 255, 255, 15, 255, 255, 255, 255, 15]),
+        (Message::Write(String::from("hi")), &[2, 2, b'h', b'i']),
+        (Message::Write(String::from("ж")), &[2, 2, 0xd0, 0xb6]),
+        (Message::ChangeColor(255, 0, 0), &[3, 254, 3, 0, 0]),
+    ];
+    for (message, bytes) in examples {
+        assert_eq!(message_wire::to_bytes(std::slice::from_ref(message)), *bytes);
+        let (decoded, len) = message_wire::decode(bytes, 16).unwrap().unwrap();
+        assert_eq!((&decoded, len), (message, bytes.len()));
+        // every shorter prefix is an incomplete frame
+        for end in 0..bytes.len() {
+            assert!(matches!(message_wire::decode(&bytes[..end], 16), Ok(None)), "{:?}", &bytes[..end]);
+        }
+    }
+
+    let invalid: &[(&[u8], &str)] = &[
+        (&[4], "unknown message tag 4"),
+        (&[1, 0x80, 0x00, 0], "invalid varint"),
+        (&[1, 0xff, 0xff, 0xff, 0xff, 0x10, 0], "invalid varint"),
+        (&[1, 0xff, 0xff, 0xff, 0xff, 0x80, 0], "invalid varint"),
+        (&[2, 17], "string of 17 bytes is longer than 16"),
+        (&[2, 2, 0xd0, 0x41], "string is not valid UTF-8"),
+    ];
+    for &(bytes, error) in invalid {
+        assert_eq!(message_wire::decode(bytes, 16).unwrap_err().to_string(), error);
+    }
+    // the length is checked before the string is read
+    let e = Decoder::new(&[2, 0xff, 0xff, 0xff, 0xff, 0x0f][..]).read_message().unwrap_err();
+    assert!(matches!(e, DecodeError::StringTooLong { len, .. } if len == u32::MAX as usize));
+    assert!(matches!(Decoder::new(&[1, 2][..]).read_message(), Err(DecodeError::Truncated)));
+    assert!(matches!(Decoder::new(&[][..]).read_message(), Ok(None)));
+    // the iterator ends after the first error, the same frame isn't decoded again
+    let results: Vec<_> = Decoder::new(&[0, 4, 0][..]).take(5).collect();
+    assert!(matches!(results[..], [Ok(Message::Quit), Err(DecodeError::UnknownTag(4))]), "{:?}", results);
+
+    // random messages read back in chunks of random size
+    let mut random = Random::new(0x2545_f491_4f6c_dd1d);
+    let messages: Vec<Message> = (0..rounds).map(|_| random_message(&mut random)).collect();
+    let bytes = message_wire::to_bytes(&messages);
+    let reader = Trickle { bytes: &bytes, random: Random::new(7) };
+    let decoded: Vec<Message> = Decoder::new(reader).map(Result::unwrap).collect();
+    assert_eq!(decoded, messages);
+
+    // random bytes and damaged frames never panic, every error ends the reading
+    for round in 0..rounds {
+        let mut bytes: Vec<u8> = if round % 2 == 0 {
+            (0..random.below(32)).map(|_| random.next() as u8).collect()
+        } else {
+            message_wire::to_bytes(&[random_message(&mut random), random_message(&mut random)])
+        };
+        if !bytes.is_empty() {
+            let i = random.below(bytes.len());
+            bytes[i] ^= 1 << random.below(8);
+        }
+        let reader = Trickle { bytes: &bytes, random: Random::new(round as u64 + 1) };
+        let mut decoder = Decoder::new(reader).with_max_string_len(64);
+        while let Ok(Some(message)) = decoder.read_message() {
+            // whatever is decoded is encoded to the same bytes
+            let encoded = message_wire::to_bytes(&[message]);
+            assert!(bytes.windows(encoded.len()).any(|w| w == &encoded[..]));
+        }
+    }
+}
+
+// Reader returning a few bytes at a time and sometimes an interruption.
+struct Trickle<'a> {
+    bytes: &'a [u8],
+    random: Random,
+}
+
+impl<'a> Read for Trickle<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.random.below(8) == 0 {
+            return Err(io::Error::from(io::ErrorKind::Interrupted));
+        }
+        let n = (1 + self.random.below(5)).min(buf.len()).min(self.bytes.len());
+        buf[..n].copy_from_slice(&self.bytes[..n]);
+        self.bytes = &self.bytes[n..];
+        Ok(n)
+    }
+}
+
+// Mostly small numbers, sometimes the extremes.
+fn random_integer(random: &mut Random) -> i32 {
+    match random.below(4) {
+        0 => [i32::MIN, i32::MAX, 0, -1][random.below(4)],
+        1 => random.next() as i32,
+        _ => random.below(512) as i32 - 256,
+    }
+}
+
+fn random_text(random: &mut Random) -> String {
+    const CHARS: &[char] = &[
+        'a', 'Z', '0', ' ', '#', '"', '\\', '\n', '\r', '\t', '\0', '\u{1b}', '\u{7f}', '\u{85}',
+        '{', '}', 'u', 'é', 'ж', 'न', '\u{301}', '\u{200d}', '世', '👋', '\u{feff}', '\u{10ffff}',
+    ];
+    (0..random.below(12)).map(|_| CHARS[random.below(CHARS.len())]).collect()
+}
+
+fn random_message(random: &mut Random) -> Message {
+    match random.below(4) {
+        0 => Message::Quit,
+        1 => Message::Move { x: random_integer(random), y: random_integer(random) },
+        2 => Message::Write(random_text(random)),
+        _ => Message::ChangeColor(random_integer(random), random_integer(random), random_integer(random)),
+    }
+}
+
+fn exit_with(message: &str) -> ! {
+    eprintln!("message-protocol: {}", message);
+    process::exit(2);
+}
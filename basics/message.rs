@@ -0,0 +1,117 @@
+// message.rs
+// The Message enum of enums-and-pattern-matching.rs as a command type: every variant is displayed
+// and applied to the state of a small application.
+#![allow(dead_code)]
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Quit,
+    // relative to the current position
+    Move { x: i32, y: i32 },
+    Write(String),
+    // red, green and blue in 0..=255
+    ChangeColor(i32, i32, i32),
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Message::Quit => write!(f, "quit"),
+            Message::Move { x, y } => write!(f, "move by ({}, {})", x, y),
+            Message::Write(text) => write!(f, "write {:?}", text),
+            Message::ChangeColor(r, g, b) => write!(f, "change color to rgb({}, {}, {})", r, g, b),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DispatchError {
+    // message after Quit
+    NotRunning,
+    // color component is not in 0..=255
+    InvalidColor(i32),
+    // position would leave the range of i32
+    PositionOverflow { x: i32, y: i32 },
+}
+
+impl fmt::Display for DispatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DispatchError::NotRunning => write!(f, "application is not running"),
+            DispatchError::InvalidColor(value) => write!(f, "color component {} is not in range 0 to 255", value),
+            DispatchError::PositionOverflow { x, y } => write!(f, "moving by ({}, {}) overflows the position", x, y),
+        }
+    }
+}
+
+// What the messages are applied to. A message which fails leaves the state unchanged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct State {
+    pub position: (i32, i32),
+    pub color: Color,
+    pub text: String,
+    pub running: bool,
+}
+
+impl Default for State {
+    fn default() -> State {
+        State { position: (0, 0), color: Color::default(), text: String::new(), running: true }
+    }
+}
+
+impl State {
+    pub fn new() -> State {
+        State::default()
+    }
+
+    pub fn apply(&mut self, message: &Message) -> Result<(), DispatchError> {
+        if !self.running {
+            return Err(DispatchError::NotRunning);
+        }
+        match *message {
+            Message::Quit => self.running = false,
+            Message::Move { x, y } => {
+                let overflow = DispatchError::PositionOverflow { x, y };
+                let new_x = self.position.0.checked_add(x).ok_or_else(|| overflow.clone())?;
+                let new_y = self.position.1.checked_add(y).ok_or(overflow)?;
+                self.position = (new_x, new_y);
+            }
+            Message::Write(ref text) => self.text.push_str(text),
+            Message::ChangeColor(r, g, b) => {
+                self.color = Color { r: component(r)?, g: component(g)?, b: component(b)? };
+            }
+        }
+        Ok(())
+    }
+
+    // Apply all messages in order, one result for each.
+    pub fn dispatch<'a, I>(&mut self, messages: I) -> Vec<Result<(), DispatchError>>
+        where
+            I: IntoIterator<Item=&'a Message>,
+    {
+        messages.into_iter().map(|message| self.apply(message)).collect()
+    }
+}
+
+fn component(value: i32) -> Result<u8, DispatchError> {
+    if (0..=255).contains(&value) {
+        Ok(value as u8)
+    } else {
+        Err(DispatchError::InvalidColor(value))
+    }
+}
@@ -0,0 +1,229 @@
+// message_text.rs
+// Line-oriented text format of Message from message.rs, one message per line:
+//
+// MOVE 3 -4
+// WRITE "hello, \"world\"\n"
+// COLOR 255 0 0
+// QUIT
+//
+// Tokens are separated by spaces or tabs, empty lines and everything after # outside of a string
+// are ignored. Strings escape \" \\ \n \r \t \0 and \u{7f} for other control chars. Output of
+// to_text parses back to the same message. A parsed line isn't always written back the same way,
+// e.g. -0, leading zeros and \u{..} escapes of printable chars are normalized.
+//
+// Binaries using this module must also declare `mod message;`.
+#![allow(dead_code)]
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::message::Message;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    // both start at 1, column is in chars
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl FromStr for Message {
+    type Err = ParseError;
+
+    // Exactly one message.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.contains('\n') {
+            let column = s.chars().position(|c| c == '\n').unwrap() + 1;
+            return Err(ParseError { line: 1, column, message: String::from("message must be on a single line") });
+        }
+        parse_line(s, 1)?.ok_or(ParseError { line: 1, column: 1, message: String::from("missing message") })
+    }
+}
+
+// All messages of a script, stops at the first error.
+pub fn parse(text: &str) -> Result<Vec<Message>, ParseError> {
+    let mut messages = Vec::new();
+    for (i, line) in text.split('\n').enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if let Some(message) = parse_line(line, i + 1)? {
+            messages.push(message);
+        }
+    }
+    Ok(messages)
+}
+
+// Message of a single line, None for an empty line or a comment.
+pub fn parse_line(line: &str, line_number: usize) -> Result<Option<Message>, ParseError> {
+    let mut cursor = Cursor { line, pos: 0, line_number };
+    let (start, command) = cursor.word();
+    let message = match command {
+        "" => return Ok(None),
+        "QUIT" => Message::Quit,
+        "MOVE" => Message::Move { x: cursor.integer()?, y: cursor.integer()? },
+        "WRITE" => Message::Write(cursor.string()?),
+        "COLOR" => Message::ChangeColor(cursor.integer()?, cursor.integer()?, cursor.integer()?),
+        _ => {
+            let message = format!("unknown command '{}', expected QUIT, MOVE, WRITE or COLOR", command);
+            return Err(cursor.error_at(start, message));
+        }
+    };
+    cursor.end()?;
+    Ok(Some(message))
+}
+
+pub fn to_text(message: &Message) -> String {
+    match message {
+        Message::Quit => String::from("QUIT"),
+        Message::Move { x, y } => format!("MOVE {} {}", x, y),
+        Message::Write(text) => format!("WRITE {}", quote(text)),
+        Message::ChangeColor(r, g, b) => format!("COLOR {} {} {}", r, g, b),
+    }
+}
+
+// Script with a line for every message.
+pub fn to_script(messages: &[Message]) -> String {
+    messages.iter().map(|m| to_text(m) + "\n").collect()
+}
+
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            '\0' => quoted.push_str("\\0"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+struct Cursor<'a> {
+    line: &'a str,
+    // byte offset of the next char
+    pos: usize,
+    line_number: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn error_at(&self, pos: usize, message: String) -> ParseError {
+        let column = self.line[..pos].chars().count() + 1;
+        ParseError { line: self.line_number, column, message }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.line[self.pos..].chars().next()
+    }
+
+    fn skip_spaces(&mut self) {
+        while let Some(c @ (' ' | '\t')) = self.peek() {
+            self.pos += c.len_utf8();
+        }
+        if self.peek() == Some('#') {
+            self.pos = self.line.len();
+        }
+    }
+
+    // Next run of chars up to a space, empty at the end of the line.
+    fn word(&mut self) -> (usize, &'a str) {
+        self.skip_spaces();
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c == ' ' || c == '\t' {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+        (start, &self.line[start..self.pos])
+    }
+
+    fn integer(&mut self) -> Result<i32, ParseError> {
+        let (start, word) = self.word();
+        let digits = word.strip_prefix('-').unwrap_or(word);
+        if word.is_empty() {
+            return Err(self.error_at(start, String::from("missing integer")));
+        }
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(self.error_at(start, format!("expected an integer, found '{}'", word)));
+        }
+        word.parse()
+            .map_err(|_| self.error_at(start, format!("integer {} is out of range of i32", word)))
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        self.skip_spaces();
+        let start = self.pos;
+        if self.peek() != Some('"') {
+            let (_, word) = self.word();
+            let message = if word.is_empty() {
+                String::from("missing string")
+            } else {
+                format!("expected a string in double quotes, found '{}'", word)
+            };
+            return Err(self.error_at(start, message));
+        }
+        self.pos += 1;
+        let mut text = String::new();
+        loop {
+            let at = self.pos;
+            let c = match self.peek() {
+                Some(c) => c,
+                None => return Err(self.error_at(start, String::from("unterminated string"))),
+            };
+            self.pos += c.len_utf8();
+            match c {
+                '"' => return Ok(text),
+                '\\' => text.push(self.escape(at)?),
+                c => text.push(c),
+            }
+        }
+    }
+
+    // Char of the escape sequence starting with the backslash at `at`.
+    fn escape(&mut self, at: usize) -> Result<char, ParseError> {
+        let c = self.peek().ok_or_else(|| self.error_at(at, String::from("unterminated string")))?;
+        self.pos += c.len_utf8();
+        match c {
+            '"' => Ok('"'),
+            '\\' => Ok('\\'),
+            'n' => Ok('\n'),
+            'r' => Ok('\r'),
+            't' => Ok('\t'),
+            '0' => Ok('\0'),
+            'u' => {
+                let hex = self.line[self.pos..].strip_prefix('{')
+                    .and_then(|rest| rest.find('}').map(|end| &rest[..end]))
+                    .filter(|hex| (1..=6).contains(&hex.len()) && hex.bytes().all(|b| b.is_ascii_hexdigit()));
+                match hex.and_then(|hex| char::from_u32(u32::from_str_radix(hex, 16).ok()?)) {
+                    Some(c) => {
+                        self.pos += hex.map_or(0, str::len) + 2;
+                        Ok(c)
+                    }
+                    None => Err(self.error_at(at, String::from("invalid unicode escape, expected \\u{hex} of a char"))),
+                }
+            }
+            c => Err(self.error_at(at, format!("unknown escape '\\{}'", c))),
+        }
+    }
+
+    fn end(&mut self) -> Result<(), ParseError> {
+        let (start, word) = self.word();
+        if word.is_empty() {
+            Ok(())
+        } else {
+            Err(self.error_at(start, format!("unexpected '{}' after the message", word)))
+        }
+    }
+}
@@ -0,0 +1,237 @@
+// message_wire.rs
+// Compact binary encoding of Message from message.rs. Every message is a frame:
+//
+// tag      u8, 0 Quit, 1 Move, 2 Write, 3 ChangeColor
+// Move     x, y as zigzag varints
+// Write    length as varint, then that many bytes of UTF-8
+// Color    r, g, b as zigzag varints
+//
+// A varint stores 7 bits in each byte, least significant first, the high bit is set on all bytes
+// but the last. Zigzag maps 0, -1, 1, -2 ... to 0, 1, 2, 3 ... so small negative numbers are short.
+// Only the shortest encoding of a number is accepted, so each message has exactly one frame.
+//
+// Binaries using this module must also declare `mod message;`.
+#![allow(dead_code)]
+
+use std::fmt;
+use std::io;
+use std::io::Read;
+
+use crate::message::Message;
+
+const QUIT: u8 = 0;
+const MOVE: u8 = 1;
+const WRITE: u8 = 2;
+const CHANGE_COLOR: u8 = 3;
+// Longer strings are rejected before they are read, a broken length can't exhaust the memory.
+pub const MAX_STRING_LEN: usize = 1024 * 1024;
+
+#[derive(Debug)]
+pub enum DecodeError {
+    UnknownTag(u8),
+    // more than 32 bits, or not the shortest encoding
+    InvalidVarint,
+    StringTooLong { len: usize, max: usize },
+    InvalidUtf8,
+    // input ended in the middle of a frame
+    Truncated,
+    Io(io::Error),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::UnknownTag(tag) => write!(f, "unknown message tag {}", tag),
+            DecodeError::InvalidVarint => write!(f, "invalid varint"),
+            DecodeError::StringTooLong { len, max } => write!(f, "string of {} bytes is longer than {}", len, max),
+            DecodeError::InvalidUtf8 => write!(f, "string is not valid UTF-8"),
+            DecodeError::Truncated => write!(f, "input ends in the middle of a message"),
+            DecodeError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<io::Error> for DecodeError {
+    fn from(e: io::Error) -> Self {
+        DecodeError::Io(e)
+    }
+}
+
+pub fn encode(message: &Message, out: &mut Vec<u8>) {
+    match message {
+        Message::Quit => out.push(QUIT),
+        Message::Move { x, y } => {
+            out.push(MOVE);
+            write_varint(out, zigzag(*x));
+            write_varint(out, zigzag(*y));
+        }
+        Message::Write(text) => {
+            out.push(WRITE);
+            write_varint(out, text.len() as u64);
+            out.extend_from_slice(text.as_bytes());
+        }
+        Message::ChangeColor(r, g, b) => {
+            out.push(CHANGE_COLOR);
+            for &c in &[r, g, b] {
+                write_varint(out, zigzag(*c));
+            }
+        }
+    }
+}
+
+pub fn to_bytes(messages: &[Message]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for message in messages {
+        encode(message, &mut bytes);
+    }
+    bytes
+}
+
+// Message at the start of bytes and the length of its frame, None when the frame is incomplete.
+pub fn decode(bytes: &[u8], max_string_len: usize) -> Result<Option<(Message, usize)>, DecodeError> {
+    let mut input = Input { bytes, pos: 0 };
+    let decoded = match input.byte() {
+        None => return Ok(None),
+        Some(QUIT) => Some(Message::Quit),
+        Some(MOVE) => match (input.integer()?, input.integer()?) {
+            (Some(x), Some(y)) => Some(Message::Move { x, y }),
+            _ => None,
+        },
+        Some(WRITE) => input.string(max_string_len)?.map(Message::Write),
+        Some(CHANGE_COLOR) => match (input.integer()?, input.integer()?, input.integer()?) {
+            (Some(r), Some(g), Some(b)) => Some(Message::ChangeColor(r, g, b)),
+            _ => None,
+        },
+        Some(tag) => return Err(DecodeError::UnknownTag(tag)),
+    };
+    Ok(decoded.map(|message| (message, input.pos)))
+}
+
+// Messages read from any reader, frames may be split between reads in any way.
+pub struct Decoder<R: Read> {
+    reader: R,
+    buffer: Vec<u8>,
+    // start of the undecoded bytes in buffer
+    start: usize,
+    max_string_len: usize,
+    // after an error, the rest of the input can't be split into frames
+    failed: bool,
+}
+
+impl<R: Read> Decoder<R> {
+    pub fn new(reader: R) -> Decoder<R> {
+        Decoder { reader, buffer: Vec::new(), start: 0, max_string_len: MAX_STRING_LEN, failed: false }
+    }
+
+    pub fn with_max_string_len(mut self, max: usize) -> Decoder<R> {
+        self.max_string_len = max;
+        self
+    }
+
+    // Next message, None at the end of the input after a complete frame.
+    pub fn read_message(&mut self) -> Result<Option<Message>, DecodeError> {
+        loop {
+            if let Some((message, len)) = decode(&self.buffer[self.start..], self.max_string_len)? {
+                self.start += len;
+                return Ok(Some(message));
+            }
+            // move the incomplete frame to the front, the buffer doesn't grow with the input
+            self.buffer.drain(..self.start);
+            self.start = 0;
+            let mut chunk = [0; 4096];
+            let n = match self.reader.read(&mut chunk) {
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(DecodeError::Io(e)),
+            };
+            if n == 0 {
+                return if self.buffer.is_empty() { Ok(None) } else { Err(DecodeError::Truncated) };
+            }
+            self.buffer.extend_from_slice(&chunk[..n]);
+        }
+    }
+}
+
+impl<R: Read> Iterator for Decoder<R> {
+    type Item = Result<Message, DecodeError>;
+
+    // None after the first error.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let message = self.read_message().transpose();
+        self.failed = matches!(message, Some(Err(_)));
+        message
+    }
+}
+
+fn zigzag(n: i32) -> u64 {
+    ((n << 1) ^ (n >> 31)) as u32 as u64
+}
+
+fn unzigzag(n: u32) -> i32 {
+    ((n >> 1) as i32) ^ -((n & 1) as i32)
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push((n as u8 & 0x7f) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+struct Input<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Input<'a> {
+    fn byte(&mut self) -> Option<u8> {
+        let byte = *self.bytes.get(self.pos)?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    // None when the input ends before the last byte.
+    fn varint(&mut self) -> Result<Option<u32>, DecodeError> {
+        let mut n: u64 = 0;
+        for shift in (0..35).step_by(7) {
+            let byte = match self.byte() {
+                Some(byte) => byte,
+                None => return Ok(None),
+            };
+            n |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                // a zero last byte after the first one means a shorter encoding exists
+                if (byte == 0 && shift > 0) || n > u32::MAX as u64 {
+                    return Err(DecodeError::InvalidVarint);
+                }
+                return Ok(Some(n as u32));
+            }
+        }
+        Err(DecodeError::InvalidVarint)
+    }
+
+    fn integer(&mut self) -> Result<Option<i32>, DecodeError> {
+        Ok(self.varint()?.map(unzigzag))
+    }
+
+    fn string(&mut self, max_len: usize) -> Result<Option<String>, DecodeError> {
+        let len = match self.varint()? {
+            Some(len) => len as usize,
+            None => return Ok(None),
+        };
+        if len > max_len {
+            return Err(DecodeError::StringTooLong { len, max: max_len });
+        }
+        let bytes = match self.bytes.get(self.pos..self.pos + len) {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+        self.pos += len;
+        let text = std::str::from_utf8(bytes).map_err(|_| DecodeError::InvalidUtf8)?;
+        Ok(Some(String::from(text)))
+    }
+}
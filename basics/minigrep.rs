@@ -0,0 +1,304 @@
+// minigrep.rs
+// Print lines of files that contain a query, a small grep. Matching lines are slices of the file
+// contents like the words returned by first_word in slice.rs, errors are propagated with ? like in
+// read_username_from_file2 in error-handling.rs.
+//
+// rustc minigrep.rs
+// ./minigrep -n -C 1 first_word slice.rs
+// IGNORE_CASE=1 ./minigrep -r STRING .
+// ./minigrep --check
+//
+// Exit status is 0 when a line was printed, 1 when none was and 2 after an error, like grep.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+mod args;
+mod crash_report;
+
+struct Config {
+    query: String,
+    ignore_case: bool,
+    invert: bool,
+    line_numbers: bool,
+    before: usize,
+    after: usize,
+    recursive: bool,
+    with_names: bool,
+}
+
+fn main() {
+    crash_report::install();
+
+    let matches = command().parse();
+    if matches.flag("check") {
+        check();
+        println!("ok");
+        return;
+    }
+    if matches.value("QUERY").is_none() {
+        matches.error(args::ErrorKind::MissingArgument(String::from("QUERY"))).exit();
+    }
+    let (config, files) = config(&matches);
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let (found, failed) = run(&files, &config, &mut out);
+    if let Err(e) = out.flush() {
+        exit_with(&e);
+    }
+    process::exit(if failed { 2 } else if found { 0 } else { 1 });
+}
+
+fn command() -> args::Command {
+    args::Command::new("minigrep")
+        .about("Print lines of the files which contain the query.")
+        .flag("ignore-case", Some('i'), "Ignore case, the same as setting IGNORE_CASE")
+        .flag("invert-match", Some('v'), "Print lines which don't contain the query")
+        .flag("line-number", Some('n'), "Print line numbers")
+        .flag("recursive", Some('r'), "Search all files in directories")
+        .option("context", Some('C'), "NUM", "Print NUM lines around each match")
+        .option("before-context", Some('B'), "NUM", "Print NUM lines before each match")
+        .option("after-context", Some('A'), "NUM", "Print NUM lines after each match")
+        .flag("check", None, "Check the search and the output with examples and exit")
+        .optional("QUERY", "Text to search for, required without --check")
+        .multiple("FILE", "Files to search, - or nothing for standard input")
+}
+
+// Configuration and the files to search.
+fn config(matches: &args::Matches) -> (Config, Vec<&str>) {
+    let context: usize = matches.get("context").unwrap_or_else(|e| e.exit()).unwrap_or(0);
+    let before = matches.get("before-context").unwrap_or_else(|e| e.exit()).unwrap_or(context);
+    let after = matches.get("after-context").unwrap_or_else(|e| e.exit()).unwrap_or(context);
+    let recursive = matches.flag("recursive");
+    let mut files = matches.values("FILE");
+    if files.is_empty() {
+        files.push(if recursive { "." } else { "-" });
+    }
+    let config = Config {
+        query: String::from(matches.value("QUERY").unwrap_or("")),
+        ignore_case: matches.flag("ignore-case") || env::var_os("IGNORE_CASE").is_some(),
+        invert: matches.flag("invert-match"),
+        line_numbers: matches.flag("line-number"),
+        before,
+        after,
+        recursive,
+        with_names: recursive || files.len() > 1,
+    };
+    (config, files)
+}
+
+// Search all files, returns whether a line was printed and whether a file failed.
+fn run<W: Write>(files: &[&str], config: &Config, out: &mut W) -> (bool, bool) {
+    let mut found = false;
+    let mut failed = false;
+    for file in files {
+        for (path, result) in search_path(Path::new(file), config, out) {
+            match result {
+                Ok(matched) => found |= matched,
+                Err(e) => {
+                    eprintln!("minigrep: {}: {}", path.display(), e);
+                    failed = true;
+                }
+            }
+        }
+    }
+    (found, failed)
+}
+
+// Search a file or all files of a directory, returns for each file whether a line was printed.
+fn search_path<W: Write>(path: &Path, config: &Config, out: &mut W) -> Vec<(PathBuf, io::Result<bool>)> {
+    let mut files = Vec::new();
+    let mut results = Vec::new();
+    if path.to_str() == Some("-") || !path.is_dir() {
+        files.push(path.to_path_buf());
+    } else if config.recursive {
+        if let Err(e) = walk(path, &mut files, &mut results) {
+            results.push((path.to_path_buf(), Err(e)));
+        }
+    } else {
+        let e = io::Error::other("Is a directory");
+        results.push((path.to_path_buf(), Err(e)));
+    }
+    for file in files {
+        let result = search_file(&file, config, out);
+        results.push((file, result));
+    }
+    results
+}
+
+// Collect files of the directory and its subdirectories in sorted order, symbolic links inside
+// the directory are not followed.
+fn walk(dir: &Path, files: &mut Vec<PathBuf>, errors: &mut Vec<(PathBuf, io::Result<bool>)>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            if let Err(e) = walk(&path, files, errors) {
+                errors.push((path, Err(e)));
+            }
+        } else if file_type.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn search_file<W: Write>(path: &Path, config: &Config, out: &mut W) -> io::Result<bool> {
+    let contents = read(path)?;
+    let name = if path.to_str() == Some("-") { "(standard input)" } else { path.to_str().unwrap_or("?") };
+    let contents = match String::from_utf8(contents) {
+        Ok(contents) => contents,
+        Err(e) => {
+            // like grep, only tell whether a binary file matches
+            let contents = String::from_utf8_lossy(e.as_bytes());
+            let matched = !search(&config.query, &contents, config.ignore_case, config.invert).is_empty();
+            if matched {
+                write_or_exit(out, format_args!("Binary file {} matches\n", name));
+            }
+            return Ok(matched);
+        }
+    };
+    let matches = search(&config.query, &contents, config.ignore_case, config.invert);
+    print_matches(&contents, &matches, name, config, out);
+    Ok(!matches.is_empty())
+}
+
+fn read(path: &Path) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    if path.to_str() == Some("-") {
+        io::stdin().read_to_end(&mut bytes)?;
+    } else {
+        fs::File::open(path)?.read_to_end(&mut bytes)?;
+    }
+    Ok(bytes)
+}
+
+// Lines which contain the query, or with invert which don't, and their indices.
+fn search<'a>(query: &str, contents: &'a str, ignore_case: bool, invert: bool) -> Vec<(usize, &'a str)> {
+    let query = if ignore_case { query.to_lowercase() } else { String::from(query) };
+    contents.lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let found = if ignore_case { line.to_lowercase().contains(&query) } else { line.contains(&query) };
+            found != invert
+        })
+        .collect()
+}
+
+// Matching lines are printed as name:number:line, context lines as name-number-line and groups of
+// lines which are not next to each other are separated by --.
+fn print_matches<W: Write>(contents: &str, matches: &[(usize, &str)], name: &str, config: &Config, out: &mut W) {
+    let lines: Vec<&str> = contents.lines().collect();
+    let context = config.before > 0 || config.after > 0;
+    // next line which wasn't printed yet
+    let mut next = 0;
+    for (i, &(index, line)) in matches.iter().enumerate() {
+        let start = index.saturating_sub(config.before).max(next);
+        if context && next > 0 && start > next {
+            write_or_exit(out, format_args!("--\n"));
+        }
+        for (number, line) in lines.iter().enumerate().take(index).skip(start) {
+            print_line(number, line, '-', name, config, out);
+        }
+        print_line(index, line, ':', name, config, out);
+        // context after stops at the next match, it is printed by the next iteration
+        let end = matches.get(i + 1).map_or(lines.len(), |&(next_index, _)| next_index);
+        let end = end.min(index + 1 + config.after);
+        for (number, line) in lines.iter().enumerate().take(end).skip(index + 1) {
+            print_line(number, line, '-', name, config, out);
+        }
+        next = end;
+    }
+}
+
+fn print_line<W: Write>(index: usize, line: &str, separator: char, name: &str, config: &Config, out: &mut W) {
+    let mut prefix = String::new();
+    if config.with_names {
+        prefix.push_str(name);
+        prefix.push(separator);
+    }
+    if config.line_numbers {
+        prefix.push_str(&(index + 1).to_string());
+        prefix.push(separator);
+    }
+    write_or_exit(out, format_args!("{}{}\n", prefix, line));
+}
+
+// Failed output is not an error of the searched file, there is no point to continue.
+fn write_or_exit<W: Write>(out: &mut W, args: std::fmt::Arguments) {
+    if let Err(e) = out.write_fmt(args) {
+        exit_with(&e);
+    }
+}
+
+fn exit_with(e: &io::Error) -> ! {
+    // output closed early, e.g. by head
+    if e.kind() == io::ErrorKind::BrokenPipe {
+        process::exit(0);
+    }
+    eprintln!("minigrep: {}", e);
+    process::exit(2);
+}
+
+// Files of the examples, written to a temporary directory.
+const FILES: [(&str, &str); 3] = [
+    ("poem.txt", "I'm nobody! Who are you?\nAre you nobody, too?\nThen there's a pair of us - don't tell!\n\
+                  They'd banish us, you know.\n\nHow dreary to be somebody!\nHow public, like a frog\n\
+                  To tell your name the livelong day\nTo an admiring bog!\n"),
+    ("dir/a.txt", "alpha\nBeta\n"),
+    ("dir/sub/b.txt", "beta\ngamma\n"),
+];
+
+fn check() {
+    let dir = env::temp_dir().join(format!("minigrep-check-{}", process::id()));
+    for (name, contents) in FILES.iter() {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+    // output with the names relative to the directory, whether a line was printed and a file failed
+    let grep = |arguments: &[&str], files: &[&str]| {
+        let files = files.iter().map(|file| dir.join(file).display().to_string());
+        let matches = command().parse_from(arguments.iter().map(|arg| arg.to_string()).chain(files)).unwrap();
+        let (config, files) = config(&matches);
+        let mut out = Vec::new();
+        let (found, failed) = run(&files, &config, &mut out);
+        let prefix = format!("{}/", dir.display());
+        (String::from_utf8(out).unwrap().replace(&prefix, ""), found, failed)
+    };
+    let found = |output: &str| (String::from(output), true, false);
+
+    assert_eq!(grep(&["-n", "frog"], &["poem.txt"]), found("7:How public, like a frog\n"));
+    assert_eq!(grep(&["Frog"], &["poem.txt"]), (String::new(), false, false));
+    // context, overlapping groups are joined and separate groups are divided by --
+    assert_eq!(grep(&["-n", "-C", "1", "frog"], &["poem.txt"]),
+               found("6-How dreary to be somebody!\n7:How public, like a frog\n8-To tell your name the livelong day\n"));
+    assert_eq!(grep(&["-C1", "nobody"], &["poem.txt"]),
+               found("I'm nobody! Who are you?\nAre you nobody, too?\nThen there's a pair of us - don't tell!\n"));
+    assert_eq!(grep(&["-n", "-C", "1", "tell"], &["poem.txt"]),
+               found("2-Are you nobody, too?\n3:Then there's a pair of us - don't tell!\n4-They'd banish us, you know.\n--\n\
+                      7-How public, like a frog\n8:To tell your name the livelong day\n9-To an admiring bog!\n"));
+    assert_eq!(grep(&["-n", "-B", "2", "-A", "0", "bog"], &["poem.txt"]),
+               found("7-How public, like a frog\n8-To tell your name the livelong day\n9:To an admiring bog!\n"));
+    // lines without the query, the empty line too
+    assert_eq!(grep(&["-vn", "u"], &["poem.txt"]), found("5:\n6:How dreary to be somebody!\n9:To an admiring bog!\n"));
+
+    // recursive search in sorted order, with names
+    assert_eq!(grep(&["-r", "beta"], &["dir"]), found("dir/sub/b.txt:beta\n"));
+    assert_eq!(grep(&["-rn", "-i", "BETA"], &["dir"]), found("dir/a.txt:2:Beta\ndir/sub/b.txt:1:beta\n"));
+    // a directory without -r and a missing file are errors, reported on stderr
+    assert_eq!(grep(&["beta"], &["dir"]), (String::new(), false, true));
+    env::set_var("IGNORE_CASE", "1");
+    let ignored = grep(&["-r", "beta"], &["dir"]);
+    env::remove_var("IGNORE_CASE");
+    assert_eq!(ignored, found("dir/a.txt:Beta\ndir/sub/b.txt:beta\n"));
+    assert_eq!(grep(&["alpha"], &["dir/a.txt", "dir/missing.txt"]), ("dir/a.txt:alpha\n".to_string(), true, true));
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,216 @@
+// money.rs
+// An invoice computed with Money from decimal.rs, then checks of its arithmetic.
+//
+// rustc money.rs
+// ./money
+
+mod convert;
+mod crash_report;
+mod decimal;
+mod random;
+
+use convert::Rounding;
+use decimal::{Decimal, DecimalError, Money};
+use random::Random;
+
+fn main() {
+    crash_report::install();
+
+    invoice();
+    check();
+    println!("ok");
+}
+
+fn money(s: &str) -> Money {
+    s.parse().unwrap()
+}
+
+fn invoice() {
+    // data-types.rs prints 95.5 - 4.3 with {:.2}, which hides errors like this one
+    let (a, b) = (0.1, 0.2);
+    println!("f64:   0.1 + 0.2 = {}", a + b);
+    println!("Money: 0.1 + 0.2 = {}", money("0.1") + money("0.2"));
+    println!();
+
+    let items = [(3, "Coffee", "3.50"), (1, "Cheesecake", "4.95"), (2, "Orange juice", "3.10")];
+    let mut subtotal = Money::ZERO;
+    for &(quantity, name, price) in &items {
+        let line = money(price) * quantity;
+        println!("{:>2} x {:<14} {:>8} {:>8}", quantity, name, price, line);
+        subtotal += line;
+    }
+    // 19% as a rate with 3 decimals, rounded to cents half up like on a receipt
+    let rate: Decimal<3> = "0.19".parse().unwrap();
+    let tax = subtotal.mul(rate, Rounding::NearestAway).unwrap();
+    let total = subtotal + tax;
+    println!("{:<28} {:>8}", "Subtotal", subtotal);
+    println!("{:<28} {:>8}", "Tax 19%", tax);
+    println!("{:<28} {:>8}", "Total", total);
+    let shares: Vec<String> = total.allocate(3).iter().map(|share| share.to_string()).collect();
+    println!("{:<28} {}", "Split in 3", shares.join(" + "));
+    println!();
+}
+
+// a * b / d with i128 arithmetic, for values small enough that a * b doesn't overflow.
+fn reference(a: i128, b: i128, d: i128, rounding: Rounding) -> i128 {
+    let (n, d) = if d < 0 { (-(a * b), -d) } else { (a * b, d) };
+    let floor = n.div_euclid(d);
+    let ceil = if n.rem_euclid(d) == 0 { floor } else { floor + 1 };
+    let nearest_floor = match (2 * (n - floor * d)).cmp(&d) {
+        std::cmp::Ordering::Less => true,
+        std::cmp::Ordering::Greater => false,
+        std::cmp::Ordering::Equal => match rounding {
+            Rounding::NearestEven => floor % 2 == 0,
+            _ => n < 0,
+        },
+    };
+    match rounding {
+        Rounding::TowardZero => if n < 0 { ceil } else { floor },
+        Rounding::Down => floor,
+        Rounding::Up => ceil,
+        Rounding::NearestEven | Rounding::NearestAway => if nearest_floor { floor } else { ceil },
+    }
+}
+
+fn check() {
+    // the example of decimal.rs
+    let price = money("19.99");
+    let total = price * 3 + money("4.50");
+    assert_eq!(total, money("64.47"));
+    assert_eq!(total.mul(Decimal::<2>::from_units(19), Rounding::NearestAway), Ok(money("12.25")));
+    assert_eq!(total.div_int(4, Rounding::NearestEven), Ok(money("16.12")));
+    assert_eq!(money("95.5") - money("4.3"), money("91.20"));
+
+    // parsing and formatting
+    for &(text, units, shown) in &[
+        ("12.3", 1230, "12.30"),
+        ("-0.05", -5, "-0.05"),
+        ("+7", 700, "7.00"),
+        ("0.10", 10, "0.10"),
+        ("1.2300000", 123, "1.23"),
+        ("-0", 0, "0.00"),
+        ("1701411834604692317316873037158841057.27", i128::MAX, "1701411834604692317316873037158841057.27"),
+        ("-1701411834604692317316873037158841057.28", i128::MIN, "-1701411834604692317316873037158841057.28"),
+    ] {
+        let value: Money = text.parse().unwrap();
+        assert_eq!(value.units(), units, "{}", text);
+        assert_eq!(value.to_string(), shown);
+        assert_eq!(shown.parse(), Ok(value));
+    }
+    assert_eq!(format!("[{:>8}] [{:<7}]", money("12.3"), money("-1")), "[   12.30] [-1.00  ]");
+    assert_eq!(Decimal::<0>::from_units(-7).to_string(), "-7");
+    assert_eq!(Decimal::<4>::from(3).to_string(), "3.0000");
+    // precision rounds like it does for floats, sign and zero padding like for integers
+    assert_eq!(format!("{:.2}", money("12.34")), "12.34");
+    assert_eq!(format!("{:.1} {:.1} {:.0}", money("12.35"), money("12.25"), money("-0.5")), "12.4 12.2 0");
+    assert_eq!(format!("{:.4} {:.1}", money("-1.5"), Decimal::<0>::from_units(7)), "-1.5000 7.0");
+    assert_eq!(format!("{:08}", money("12.34")), "00012.34");
+    assert_eq!(format!("{:08}", money("-12.34")), "-0012.34");
+    assert_eq!(format!("{:+} {:+}", money("12.34"), money("-1")), "+12.34 -1.00");
+    assert_eq!(format!("{:+09.1}", money("2.25")), "+000002.2");
+    assert_eq!(format!("[{:^9.1}]", money("2.25")), "[   2.2   ]");
+    let errors = [
+        ("", DecimalError::Invalid { offset: 0, message: "expected a digit" }),
+        ("-", DecimalError::Invalid { offset: 1, message: "expected a digit" }),
+        (".5", DecimalError::Invalid { offset: 0, message: "expected a digit" }),
+        ("12.", DecimalError::Invalid { offset: 3, message: "expected a digit after '.'" }),
+        ("1.2.3", DecimalError::Invalid { offset: 3, message: "expected a digit" }),
+        ("12 ", DecimalError::Invalid { offset: 2, message: "expected a digit" }),
+        ("1e3", DecimalError::Invalid { offset: 1, message: "expected a digit" }),
+        ("1.234", DecimalError::TooManyDecimals { scale: 2 }),
+        ("1701411834604692317316873037158841057.28", DecimalError::Overflow),
+    ];
+    for (text, error) in errors.iter().cloned() {
+        assert_eq!(text.parse::<Money>(), Err(error), "{:?}", text);
+    }
+    assert_eq!("1.2.3".parse::<Money>().unwrap_err().to_string(), "4: expected a digit");
+
+    // rounding to fewer decimals
+    let x: Decimal<3> = "12.345".parse().unwrap();
+    assert_eq!(x.rescale::<2>(Rounding::NearestEven), Ok(money("12.34")));
+    assert_eq!(x.rescale::<2>(Rounding::NearestAway), Ok(money("12.35")));
+    assert_eq!((-x).rescale::<2>(Rounding::Down), Ok(money("-12.35")));
+    assert_eq!(x.rescale::<4>(Rounding::TowardZero).unwrap().to_string(), "12.3450");
+    assert_eq!(Money::MAX.rescale::<3>(Rounding::TowardZero), Err(DecimalError::Overflow));
+    assert_eq!(money("2.50").round(Rounding::NearestEven), 2);
+    assert_eq!(money("2.50").round(Rounding::NearestAway), 3);
+    assert_eq!(money("-2.01").round(Rounding::Down), -3);
+
+    // errors instead of panics
+    assert_eq!(money("1").div(Money::ZERO, Rounding::Up), Err(DecimalError::DivisionByZero));
+    assert_eq!(money("1").div_int(0, Rounding::Up), Err(DecimalError::DivisionByZero));
+    assert_eq!(Money::MAX.checked_add(Money::from_units(1)), Err(DecimalError::Overflow));
+    assert_eq!(Money::MIN.checked_sub(Money::from_units(1)), Err(DecimalError::Overflow));
+    assert_eq!(Money::MAX.checked_mul(2), Err(DecimalError::Overflow));
+    assert_eq!(Money::MIN.div_int(-1, Rounding::TowardZero), Err(DecimalError::Overflow));
+    assert_eq!(Money::MAX.mul(Money::from(2), Rounding::TowardZero), Err(DecimalError::Overflow));
+    // the product of the units is larger than i128, the result isn't
+    assert_eq!(Money::MAX.mul(Money::ONE, Rounding::TowardZero), Ok(Money::MAX));
+    assert_eq!(Money::MIN.mul(Decimal::<38>::ONE, Rounding::Up), Ok(Money::MIN));
+    assert_eq!(Money::MAX.div(Money::ONE, Rounding::TowardZero), Ok(Money::MAX));
+    let half = Decimal::<38>::from_units(5 * 10i128.pow(37));
+    assert_eq!(Money::MAX.mul(half, Rounding::NearestEven), Ok(Money::from_units(i128::MAX / 2 + 1)));
+    assert_eq!(Money::MIN.mul(half, Rounding::NearestEven), Ok(Money::from_units(i128::MIN / 2)));
+
+    // operators
+    let prices = [money("0.10"), money("0.20"), money("0.30")];
+    let sum: Money = prices.iter().sum();
+    assert_eq!(sum, money("0.60"));
+    let (a, b) = (0.1, 0.2);
+    assert_ne!(a + b, 0.3);
+    assert_eq!(3 * money("0.10") - money("0.30"), Money::ZERO);
+    let mut balance = money("100");
+    balance -= money("100.01");
+    assert!(balance.is_negative() && balance.abs() == money("0.01"));
+    balance += money("0.01");
+    assert_eq!(-balance, Money::ZERO);
+    assert!(money("-1") < money("0.01") && money("2.5") > money("2.49"));
+
+    // allocations add up to the total
+    assert_eq!(money("100").allocate(3), [money("33.34"), money("33.33"), money("33.33")]);
+    assert_eq!(money("-100").allocate(3), [money("-33.33"), money("-33.33"), money("-33.34")]);
+    assert!(money("1").allocate(0).is_empty());
+
+    let mut random = Random::new(0x853c_49e6_748f_ea9b);
+    for _ in 0..20_000 {
+        let a = random.next() as i64 as i128 >> random.below(64);
+        let b = random.next() as i64 as i128 >> random.below(64);
+        let parts = random.below(10) + 1;
+        let allocated = Money::from_units(a).allocate(parts);
+        assert_eq!(allocated.iter().sum::<Money>(), Money::from_units(a));
+        assert!(allocated.windows(2).all(|w| w[0].units() - w[1].units() <= 1 && w[0] >= w[1]));
+
+        for &rounding in &Rounding::ALL {
+            let x = Money::from_units(a);
+            assert_eq!(x.mul(Decimal::<3>::from_units(b), rounding).unwrap().units(), reference(a, b, 1000, rounding),
+                       "{} * {} {:?}", a, b, rounding);
+            if b != 0 {
+                assert_eq!(x.div(Money::from_units(b), rounding).unwrap().units(), reference(a, 100, b, rounding),
+                           "{} / {} {:?}", a, b, rounding);
+                assert_eq!(x.div_int(b, rounding).unwrap().units(), reference(a, 1, b, rounding));
+            }
+            let rescaled = Decimal::<5>::from_units(a).rescale::<2>(rounding).unwrap();
+            assert_eq!(rescaled.units(), reference(a, 1, 1000, rounding));
+        }
+
+        // products above 128 bits of positive a and c: q = a * c / 10^38 is right when
+        // a * c - q * 10^38, which is the same modulo 2^128, is a remainder below 10^38
+        let a = (random.next() as i128) << 62 | random.next() as i128;
+        let c = ((random.next() as i128) << 63 | random.next() as i128) % 10i128.pow(38);
+        let (x, y) = (Decimal::<0>::from_units(a), Decimal::<38>::from_units(c));
+        let q = x.mul(y, Rounding::Down).unwrap().units();
+        let r = a.wrapping_mul(c).wrapping_sub(q.wrapping_mul(10i128.pow(38))) as u128;
+        assert!(r < 10u128.pow(38), "{} * {}", a, c);
+        let up = q + (r > 0) as i128;
+        let nearest_even = match (2 * r).cmp(&10u128.pow(38)) {
+            std::cmp::Ordering::Less => q,
+            std::cmp::Ordering::Greater => q + 1,
+            std::cmp::Ordering::Equal => q + q % 2,
+        };
+        assert_eq!(x.mul(y, Rounding::Up).unwrap().units(), up);
+        assert_eq!(x.mul(y, Rounding::NearestEven).unwrap().units(), nearest_even);
+        assert_eq!((-x).mul(y, Rounding::TowardZero).unwrap().units(), -q);
+        assert_eq!((-x).mul(y, Rounding::Down).unwrap().units(), -up);
+        assert_eq!(x.mul(-y, Rounding::NearestEven).unwrap().units(), -nearest_even);
+    }
+}
@@ -0,0 +1,332 @@
+// numeric-conversions.rs
+// Boundary checks of convert.rs for all pairs of numeric types, compared with the conversions
+// of the standard library where it has them.
+
+use std::convert::TryFrom;
+use std::fmt::Debug;
+
+mod convert;
+mod crash_report;
+mod random;
+
+use convert::{checked, rounded, saturating, wrapping, ConversionError, Integer, Numeric, Rounding};
+use random::Random;
+
+fn main() {
+    crash_report::install();
+
+    examples();
+    integers();
+    floats_to_integers();
+    integers_to_floats();
+    floats_to_floats();
+    println!("ok");
+}
+
+// The casts of cast-and-parse.rs
+fn examples() {
+    let decimal = 65.4321_f32;
+    assert_eq!(decimal as u8, 65);
+    assert_eq!(checked::<f32, u8>(decimal).unwrap_err().to_string(), "65.4321 has a fractional part which u8 can't hold");
+    assert_eq!(rounded::<f32, u8>(decimal, Rounding::TowardZero), Ok(65));
+    assert_eq!(rounded::<f32, u8>(decimal, Rounding::Up), Ok(66));
+
+    assert_eq!(checked::<i32, u8>(1000).unwrap_err().to_string(), "1000 is out of range of u8 (0 to 255)");
+    assert_eq!(saturating::<i32, u8>(1000, Rounding::TowardZero), Ok(255));
+    assert_eq!(wrapping::<i32, u8>(1000, Rounding::TowardZero), Ok(232));
+    assert_eq!(wrapping::<i8, u8>(-1, Rounding::TowardZero), Ok(255));
+    assert_eq!(checked::<i32, u16>(1000), Ok(1000));
+
+    assert_eq!(checked::<f64, i32>(f64::NAN), Err(ConversionError::NotANumber { target: "i32" }));
+    assert_eq!(checked::<f64, i32>(f64::INFINITY).unwrap_err().to_string(),
+               "inf is out of range of i32 (-2147483648 to 2147483647)");
+    assert_eq!(checked::<u32, f32>(16_777_217).unwrap_err().to_string(), "16777217 can't be represented exactly as f32");
+    assert_eq!(checked::<u32, f32>(16_777_216), Ok(16_777_216.0));
+    assert_eq!(checked::<f64, f32>(0.1).unwrap_err().to_string(), "0.1 can't be represented exactly as f32");
+    // too large for f32 is an overflow, not a loss of precision
+    assert!(matches!(checked::<f64, f32>(1e300), Err(ConversionError::Overflow { target: "f32", .. })));
+    assert!(matches!(checked::<f64, f32>(-1e300), Err(ConversionError::Overflow { target: "f32", .. })));
+    assert!(matches!(checked::<u128, f32>(u128::MAX), Err(ConversionError::Overflow { target: "f32", .. })));
+    assert!(matches!(checked::<i128, f32>(i128::MIN + 1), Err(ConversionError::PrecisionLost { .. })));
+    assert!(matches!(checked::<f64, f32>((f32::MAX as f64).next_up()), Err(ConversionError::Overflow { .. })));
+
+    // the same rounding rules as the float methods
+    for &(x, rounding, expected) in &[
+        (2.5, Rounding::NearestEven, 2),
+        (3.5, Rounding::NearestEven, 4),
+        (-2.5, Rounding::NearestEven, -2),
+        (2.5, Rounding::NearestAway, 3),
+        (-2.5, Rounding::NearestAway, -3),
+        (-2.7, Rounding::TowardZero, -2),
+        (-2.3, Rounding::Down, -3),
+        (-2.7, Rounding::Up, -2),
+        (-0.4, Rounding::NearestAway, 0),
+    ] {
+        assert_eq!(rounded::<f64, i8>(x, rounding), Ok(expected), "{} {:?}", x, rounding);
+    }
+    assert_eq!(wrapping::<f64, u8>(1000.7, Rounding::TowardZero), Ok(232));
+    assert_eq!(wrapping::<f64, u8>(-1.5, Rounding::Down), Ok(254));
+    assert_eq!(wrapping::<f64, i8>(1e300, Rounding::TowardZero), Ok(0));
+    assert!(wrapping::<f64, i8>(f64::NEG_INFINITY, Rounding::TowardZero).is_err());
+}
+
+// Values around the limits of every integer type.
+fn integer_candidates() -> Vec<i128> {
+    let mut candidates = vec![0, 1, -1, 2, -2];
+    for bits in &[7, 8, 15, 16, 31, 32, 63, 64] {
+        let power = 1i128 << bits;
+        candidates.extend_from_slice(&[power - 1, power, power + 1, -power - 1, -power, -power + 1]);
+    }
+    candidates.extend_from_slice(&[i128::MIN, i128::MIN + 1, i128::MAX - 1, i128::MAX]);
+    candidates
+}
+
+// Compared with TryFrom for checked and `as` for wrapping.
+fn check_integer_pair<T, U>(values: &[T], cast: fn(T) -> U)
+    where
+        T: Numeric + PartialOrd + TryFrom<i8>,
+        U: Integer + PartialEq + Debug + TryFrom<T>,
+{
+    let zero = T::try_from(0).ok().unwrap();
+    for &value in values {
+        let expected = U::try_from(value).ok();
+        assert_eq!(checked::<T, U>(value).ok(), expected, "{} {} -> {}", value, T::NAME, U::NAME);
+        let saturated = expected.unwrap_or(if value < zero { U::MIN } else { U::MAX });
+        for &rounding in &Rounding::ALL {
+            assert_eq!(rounded::<T, U>(value, rounding).ok(), expected);
+            assert_eq!(saturating::<T, U>(value, rounding), Ok(saturated), "{} {} -> {}", value, T::NAME, U::NAME);
+        }
+        assert_eq!(wrapping::<T, U>(value, Rounding::TowardZero), Ok(cast(value)), "{} {} -> {}", value, T::NAME, U::NAME);
+    }
+}
+
+// Every candidate that fits the source type, converted to every integer type.
+macro_rules! integer_pairs {
+    ($candidates:expr; $($t:ty),*) => {$(
+        integer_pairs!(@values $candidates.iter().filter_map(|&c| <$t>::try_from(c).ok()).collect(); $t);
+    )*};
+    (@values $values:expr; $t:ty) => {
+        integer_pairs!(@from $values; $t; i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+    };
+    (@from $values:expr; $t:ty; $($u:ty),*) => {{
+        let values: Vec<$t> = $values;
+        $(check_integer_pair::<$t, $u>(&values, |v| v as $u);)*
+    }};
+}
+
+fn integers() {
+    let candidates = integer_candidates();
+    integer_pairs!(candidates; i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+    // the largest values of u128 are not in i128
+    integer_pairs!(@values vec![u128::MAX, u128::MAX - 1, 1 << 127, (1 << 127) - 1]; u128);
+
+    // every value of the small types
+    integer_pairs!(@values (i8::MIN..=i8::MAX).collect(); i8);
+    integer_pairs!(@values (u8::MIN..=u8::MAX).collect(); u8);
+    integer_pairs!(@values (i16::MIN..=i16::MAX).collect(); i16);
+    integer_pairs!(@values (u16::MIN..=u16::MAX).collect(); u16);
+}
+
+// Exact integer value of an integral float, None outside of i128 and u128.
+fn float_as_integer<U: TryFrom<i128> + TryFrom<u128>>(f: f64) -> Option<U> {
+    if f >= -(2f64.powi(127)) && f < 2f64.powi(127) {
+        U::try_from(f as i128).ok()
+    } else if f >= 0.0 && f < 2f64.powi(128) {
+        U::try_from(f as u128).ok()
+    } else {
+        None
+    }
+}
+
+fn float_candidates(random: &mut Random) -> Vec<f64> {
+    let mut candidates = vec![0.0, -0.0, 0.5, -0.5, 1.5, -1.5, 2.5, -2.5, 0.49999999999999994, 1e-300,
+                              f64::INFINITY, f64::NEG_INFINITY, f64::MAX, f64::MIN, f64::MIN_POSITIVE];
+    for bits in &[7, 8, 15, 16, 31, 32, 63, 64, 127, 128] {
+        let power = 2f64.powi(*bits);
+        for &x in &[power, -power, power - 1.0, -power + 1.0, power - 0.5, -power + 0.5, power + 0.5, -power - 0.5] {
+            candidates.extend_from_slice(&[x, x.next_up(), x.next_down()]);
+        }
+    }
+    for _ in 0..2000 {
+        // bit patterns of all magnitudes, and numbers in the range of the small types
+        candidates.push(f64::from_bits(random.next()));
+        candidates.push((random.next() as i64 as f64) / 2f64.powi(random.below(64) as i32));
+    }
+    candidates.retain(|f| !f.is_nan());
+    candidates
+}
+
+fn check_float_to_integer<U>(values: &[f64], cast: fn(f64) -> U)
+    where
+        U: Integer + PartialEq + Debug + TryFrom<i128> + TryFrom<u128>,
+{
+    for &value in values {
+        for &rounding in &Rounding::ALL {
+            let r = match rounding {
+                Rounding::TowardZero => value.trunc(),
+                Rounding::Down => value.floor(),
+                Rounding::Up => value.ceil(),
+                Rounding::NearestEven => value.round_ties_even(),
+                Rounding::NearestAway => value.round(),
+            };
+            let expected = float_as_integer::<U>(r);
+            assert_eq!(rounded::<f64, U>(value, rounding).ok(), expected, "{} {:?} -> {}", value, rounding, U::NAME);
+            if rounding == Rounding::TowardZero {
+                // `as` saturates too
+                assert_eq!(saturating::<f64, U>(value, rounding), Ok(cast(value)), "{} -> {}", value, U::NAME);
+                let exact = if r == value { expected } else { None };
+                assert_eq!(checked::<f64, U>(value).ok(), exact, "{} -> {}", value, U::NAME);
+            }
+        }
+    }
+    assert!(saturating::<f64, U>(f64::NAN, Rounding::TowardZero).is_err());
+    assert!(checked::<f32, U>(f32::NAN).is_err());
+}
+
+fn floats_to_integers() {
+    let values = float_candidates(&mut Random::new(0x2545_f491_4f6c_dd1d));
+    check_float_to_integer::<i8>(&values, |v| v as i8);
+    check_float_to_integer::<i16>(&values, |v| v as i16);
+    check_float_to_integer::<i32>(&values, |v| v as i32);
+    check_float_to_integer::<i64>(&values, |v| v as i64);
+    check_float_to_integer::<i128>(&values, |v| v as i128);
+    check_float_to_integer::<isize>(&values, |v| v as isize);
+    check_float_to_integer::<u8>(&values, |v| v as u8);
+    check_float_to_integer::<u16>(&values, |v| v as u16);
+    check_float_to_integer::<u32>(&values, |v| v as u32);
+    check_float_to_integer::<u64>(&values, |v| v as u64);
+    check_float_to_integer::<u128>(&values, |v| v as u128);
+    check_float_to_integer::<usize>(&values, |v| v as usize);
+
+    // wrapping keeps the low bits of the rounded value like `as` between integers
+    for &value in &values {
+        if value.is_finite() && value.abs() < 2f64.powi(127) {
+            let r = value.floor() as i128;
+            assert_eq!(wrapping::<f64, u8>(value, Rounding::Down), Ok(r as u8), "{}", value);
+            assert_eq!(wrapping::<f64, i64>(value, Rounding::Down), Ok(r as i64), "{}", value);
+        }
+    }
+}
+
+// Every rounding to f32 and f64 of an integer of i64 or u64 range.
+fn check_integer_to_floats<T: Numeric>(value: i128, v: T) {
+    for &rounding in &Rounding::ALL {
+        let r: f32 = rounded(v, rounding).unwrap();
+        let f32_even = |f: f64| (f as f32).to_bits().is_multiple_of(2);
+        check_integer_to_float(value, r as f64, r.next_up() as f64, r.next_down() as f64, f32_even, rounding);
+        let r: f64 = rounded(v, rounding).unwrap();
+        check_integer_to_float(value, r, r.next_up(), r.next_down(), |f| f.to_bits().is_multiple_of(2), rounding);
+    }
+}
+
+// The result is compared with the floats below and above the value, both as integers.
+fn check_integer_to_float(value: i128, r: f64, next_up: f64, next_down: f64, is_even: fn(f64) -> bool,
+                          rounding: Rounding) {
+    let (low, high) = if (r as i128) <= value { (r, next_up) } else { (next_down, r) };
+    let (low_i, high_i) = (low as i128, high as i128);
+    assert!(low_i <= value && value <= high_i);
+    let expected = if low_i == value {
+        low
+    } else {
+        match rounding {
+            Rounding::Down => low,
+            Rounding::Up => high,
+            Rounding::TowardZero => if value > 0 { low } else { high },
+            Rounding::NearestEven | Rounding::NearestAway => match (value - low_i).cmp(&(high_i - value)) {
+                std::cmp::Ordering::Less => low,
+                std::cmp::Ordering::Greater => high,
+                std::cmp::Ordering::Equal if rounding == Rounding::NearestAway => if value > 0 { high } else { low },
+                // even is the one with 0 in the last bit of the mantissa
+                std::cmp::Ordering::Equal => if is_even(low) { low } else { high },
+            },
+        }
+    };
+    assert_eq!(r, expected, "{} {:?}", value, rounding);
+}
+
+fn integers_to_floats() {
+    let mut random = Random::new(7);
+    let mut values: Vec<i128> = integer_candidates().into_iter().filter(|c| c.unsigned_abs() <= 1 << 64).collect();
+    for _ in 0..5000 {
+        values.push(random.next() as i64 as i128 >> random.below(64));
+        values.push((random.next() >> random.below(64)) as i128);
+        // the floats of [2^24, 2^25) and [2^53, 2^54) are 2 apart, odd numbers there are ties
+        let tie = (random.next() >> 40 | 1 << 24 | 1) as i128;
+        values.extend_from_slice(&[tie, -tie]);
+        let tie = (random.next() >> 11 | 1 << 53 | 1) as i128;
+        values.extend_from_slice(&[tie, -tie]);
+    }
+    for &value in &values {
+        if value < 0 {
+            if let Ok(v) = i64::try_from(value) {
+                check_integer_to_floats(value, v);
+            }
+        } else if let Ok(v) = u64::try_from(value) {
+            check_integer_to_floats(value, v);
+        }
+        // nearest even is what `as` does
+        if let Ok(v) = i64::try_from(value) {
+            assert_eq!(rounded::<i64, f32>(v, Rounding::NearestEven), Ok(v as f32));
+            assert_eq!(rounded::<i64, f64>(v, Rounding::NearestEven), Ok(v as f64));
+            assert_eq!(checked::<i64, f64>(v).is_ok(), v as f64 as i128 == value);
+        }
+    }
+
+    // u128::MAX is above the largest f32, which is 2^128 - 2^104
+    assert!(rounded::<u128, f32>(u128::MAX, Rounding::NearestEven).is_err());
+    assert_eq!(rounded::<u128, f32>(u128::MAX, Rounding::Down), Ok(f32::MAX));
+    assert_eq!(saturating::<u128, f32>(u128::MAX, Rounding::Up), Ok(f32::MAX));
+    assert_eq!(rounded::<u128, f64>(u128::MAX, Rounding::NearestEven), Ok(2f64.powi(128)));
+    assert_eq!(rounded::<u128, f64>(u128::MAX, Rounding::Down), Ok(2f64.powi(128).next_down()));
+    assert_eq!(checked::<i128, f32>(i128::MIN), Ok(-(2f32.powi(127))));
+    assert_eq!(rounded::<i128, f32>(i128::MAX, Rounding::TowardZero), Ok(2f32.powi(127).next_down()));
+    assert_eq!(rounded::<i128, f64>(-(1 << 60) - 1, Rounding::Up), Ok(-(2f64.powi(60))));
+}
+
+fn floats_to_floats() {
+    let values = float_candidates(&mut Random::new(11));
+    for &value in &values {
+        assert_eq!(checked::<f32, f64>(value as f32), Ok(value as f32 as f64));
+        assert_eq!(rounded::<f64, f32>(value, Rounding::NearestEven).ok(),
+                   Some(value as f32).filter(|r| r.is_finite() || value.is_infinite()), "{}", value);
+        for &rounding in &Rounding::ALL {
+            match saturating::<f64, f32>(value, rounding) {
+                Ok(r) if r as f64 == value => assert_eq!(checked::<f64, f32>(value), Ok(r)),
+                Ok(r) => {
+                    assert!(checked::<f64, f32>(value).is_err());
+                    let (low, high) = if (r as f64) < value { (r, r.next_up()) } else { (r.next_down(), r) };
+                    assert!((low as f64) < value && value < high as f64 || high == f32::MAX || low == f32::MIN, "{}", value);
+                    match rounding {
+                        Rounding::Down => assert!((r as f64) < value || r == f32::MIN),
+                        Rounding::Up => assert!((r as f64) > value || r == f32::MAX),
+                        Rounding::TowardZero => assert!((r as f64).abs() < value.abs()),
+                        Rounding::NearestEven | Rounding::NearestAway => {
+                            let (to_low, to_high) = (value - low as f64, high as f64 - value);
+                            assert!(r == if to_low < to_high { low } else { high } || to_low == to_high || !high.is_finite() || !low.is_finite());
+                        }
+                    }
+                }
+                Err(e) => panic!("{} {:?}: {}", value, rounding, e),
+            }
+        }
+    }
+
+    // ties between 1 and the next f32
+    let tie = 1.0 + 2f64.powi(-24);
+    assert_eq!(rounded::<f64, f32>(tie, Rounding::NearestEven), Ok(1.0));
+    assert_eq!(rounded::<f64, f32>(tie, Rounding::NearestAway), Ok(1.0f32.next_up()));
+    assert_eq!(rounded::<f64, f32>(-tie, Rounding::NearestAway), Ok(-(1.0f32.next_up())));
+    let odd_tie = 1.0 + 3.0 * 2f64.powi(-24);
+    assert_eq!(rounded::<f64, f32>(odd_tie, Rounding::NearestEven), Ok(1.0 + 2f32.powi(-22)));
+
+    // out of range of f32
+    assert!(rounded::<f64, f32>(1e39, Rounding::NearestEven).is_err());
+    assert_eq!(rounded::<f64, f32>(1e39, Rounding::TowardZero), Ok(f32::MAX));
+    assert_eq!(saturating::<f64, f32>(-1e39, Rounding::NearestAway), Ok(f32::MIN));
+    assert_eq!(checked::<f64, f32>(f64::INFINITY), Ok(f32::INFINITY));
+    // below the smallest f32
+    assert_eq!(rounded::<f64, f32>(1e-46, Rounding::NearestEven), Ok(0.0));
+    assert_eq!(rounded::<f64, f32>(1e-46, Rounding::Up), Ok(f32::from_bits(1)));
+    assert_eq!(rounded::<f64, f32>(-1e-46, Rounding::Down), Ok(-f32::from_bits(1)));
+    assert!(checked::<f64, f32>(f64::NAN).is_err());
+}
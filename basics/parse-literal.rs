@@ -0,0 +1,203 @@
+// parse-literal.rs
+// Parse numeric literals written like in Rust source, see literal.rs.
+//
+// rustc parse-literal.rs
+// ./parse-literal parse 0xffffffffu32 -2_147_483_648i32 1e-3_f64
+// ./parse-literal check
+
+use std::process;
+
+mod args;
+mod convert;
+mod crash_report;
+mod literal;
+mod random;
+
+use convert::ConversionError;
+use literal::{ErrorKind, Literal, LiteralError};
+use random::Random;
+
+fn main() {
+    crash_report::install();
+
+    let matches = args::Command::new("parse-literal")
+        .about("Parse numeric literals with Rust syntax and print their typed values.")
+        .subcommand(args::Command::new("parse")
+            .about("Print the value and type of every literal")
+            .multiple("LITERAL", "Literal like 1_000, 0xffu8 or 2.5f32"))
+        .subcommand(args::Command::new("check")
+            .about("Check the literals of data-types.rs and the error positions"))
+        .parse();
+
+    match matches.subcommand() {
+        Some(("parse", sub)) => {
+            let mut failed = false;
+            for text in sub.values("LITERAL") {
+                match literal::parse(text) {
+                    Ok(value) => println!("{} = {}", text, value),
+                    Err(e) => {
+                        eprintln!("{}", show_error(text, &e));
+                        failed = true;
+                    }
+                }
+            }
+            if failed {
+                process::exit(1);
+            }
+        }
+        Some(("check", _)) => {
+            check();
+            println!("ok");
+        }
+        _ => matches.error(args::ErrorKind::Other(String::from("a command is required"))).exit(),
+    }
+}
+
+// The literal with the cause of the error underlined.
+fn show_error(text: &str, e: &LiteralError) -> String {
+    let indent = text[..e.start].chars().count();
+    let width = text[e.start..e.end].chars().count().max(1);
+    format!("{}\n{}{} {}", text, " ".repeat(indent), "^".repeat(width), e.kind)
+}
+
+fn check() {
+    // data-types.rs
+    for &(text, expected) in &[
+        ("-128i8", Literal::I8(i8::MIN)),
+        ("127i8", Literal::I8(i8::MAX)),
+        ("255u8", Literal::U8(u8::MAX)),
+        ("-32_768i16", Literal::I16(i16::MIN)),
+        ("32_767i16", Literal::I16(i16::MAX)),
+        ("65_535u16", Literal::U16(u16::MAX)),
+        ("-2_147_483_648i32", Literal::I32(i32::MIN)),
+        ("0b0111_1111_1111_1111_1111_1111_1111_1111i32", Literal::I32(i32::MAX)),
+        ("0xffffffffu32", Literal::U32(u32::MAX)),
+        ("5i32", Literal::I32(5)),
+        ("1_000", Literal::I32(1000)),
+        ("1e-3_f64", Literal::F64(0.001)),
+        ("1.0", Literal::F64(1.0)),
+        ("95.5", Literal::F64(95.5)),
+        // more of Rust syntax
+        ("0o777", Literal::I32(0o777)),
+        ("0x_ff_u8", Literal::U8(255)),
+        ("0x1f32", Literal::I32(0x1f32)),
+        ("5f32", Literal::F32(5.0)),
+        ("1.", Literal::F64(1.0)),
+        ("2.5E+3f32", Literal::F32(2500.0)),
+        ("1_000_i64", Literal::I64(1000)),
+        ("-0u8", Literal::U8(0)),
+        ("-0.0", Literal::F64(-0.0)),
+        ("340_282_366_920_938_463_463_374_607_431_768_211_455u128", Literal::U128(u128::MAX)),
+        ("-170141183460469231731687303715884105728i128", Literal::I128(i128::MIN)),
+        ("0.1f32", Literal::F32(0.1)),
+        ("3.4028235e38f32", Literal::F32(f32::MAX)),
+        ("1e-46f32", Literal::F32(0.0)),
+    ] {
+        assert_eq!(literal::parse(text), Ok(expected), "{}", text);
+        // the value is the same as the compiler's
+        assert_eq!(literal::parse(&expected.to_string()), Ok(expected), "{}", expected);
+    }
+    assert_eq!("1e-3_f64".parse::<Literal>().unwrap().to_string(), "0.001f64");
+    assert_eq!(Literal::I32(-5).to_string(), "-5i32");
+    assert_eq!(Literal::F32(1e20).to_string(), "1e20f32");
+
+    // errors point at their cause, start..end in bytes
+    let errors = [
+        ("", 0, 0, ErrorKind::MissingDigits),
+        ("-", 1, 1, ErrorKind::MissingDigits),
+        ("_1", 0, 0, ErrorKind::MissingDigits),
+        ("0x", 2, 2, ErrorKind::MissingDigits),
+        ("0b__", 2, 2, ErrorKind::MissingDigits),
+        ("0b102", 4, 5, ErrorKind::InvalidDigit { digit: '2', radix: 2 }),
+        ("0o7_8", 4, 5, ErrorKind::InvalidDigit { digit: '8', radix: 8 }),
+        ("1e", 1, 2, ErrorKind::MissingExponent),
+        ("1.5e+_f32", 3, 6, ErrorKind::MissingExponent),
+        ("5x", 1, 2, ErrorKind::UnknownSuffix(String::from("x"))),
+        ("5i7", 1, 3, ErrorKind::UnknownSuffix(String::from("i7"))),
+        // the prefix is lower case only
+        ("0XFF", 1, 4, ErrorKind::UnknownSuffix(String::from("XFF"))),
+        ("1.5i32", 3, 6, ErrorKind::IncompatibleSuffix("integer suffix on a float literal")),
+        ("1e3u8", 3, 5, ErrorKind::IncompatibleSuffix("integer suffix on a float literal")),
+        ("0b1f32", 3, 6, ErrorKind::IncompatibleSuffix("binary float literal is not supported")),
+        ("1.2.3", 3, 4, ErrorKind::UnexpectedChar('.')),
+        ("12 ", 2, 3, ErrorKind::UnexpectedChar(' ')),
+        ("1..2", 1, 2, ErrorKind::UnexpectedChar('.')),
+        ("7µs", 1, 3, ErrorKind::UnexpectedChar('µ')),
+        ("340282366920938463463374607431768211456", 0, 39, ErrorKind::TooLarge),
+        ("0x1_0000_0000_0000_0000_0000_0000_0000_0000u128", 0, 43, ErrorKind::TooLarge),
+    ];
+    for (text, start, end, kind) in errors.iter().cloned() {
+        assert_eq!(literal::parse(text), Err(LiteralError { start, end, kind }), "{:?}", text);
+    }
+
+    // out of range of the suffix, or of i32 without one
+    let out_of_range = [
+        ("128i8", "128 is out of range of i8 (-128 to 127)"),
+        ("-129i8", "-129 is out of range of i8 (-128 to 127)"),
+        ("-1u8", "-1 is out of range of u8 (0 to 255)"),
+        ("2_147_483_648", "2147483648 is out of range of i32 (-2147483648 to 2147483647)"),
+        ("0x1_0000_0000u32", "4294967296 is out of range of u32 (0 to 4294967295)"),
+        ("-170141183460469231731687303715884105729i128",
+         "-170141183460469231731687303715884105729 is out of range of i128 \
+          (-170141183460469231731687303715884105728 to 170141183460469231731687303715884105727)"),
+        ("-1u128", "-1 is out of range of u128 (0 to 340282366920938463463374607431768211455)"),
+        ("1e39f32", "1e39 is out of range of f32 (-340282350000000000000000000000000000000 to \
+          340282350000000000000000000000000000000)"),
+    ];
+    for &(text, message) in &out_of_range {
+        match literal::parse(text) {
+            Err(LiteralError { start: 0, end, kind: ErrorKind::OutOfRange(e) }) => {
+                assert_eq!(e.to_string(), message);
+                // the suffix isn't part of the value
+                assert!(end <= text.len() && text[end..].chars().all(|c| c.is_ascii_alphanumeric()), "{}", text);
+            }
+            other => panic!("{}: {:?}", text, other),
+        }
+    }
+    assert!(matches!(literal::parse("-1e400").unwrap_err().kind,
+                     ErrorKind::OutOfRange(ConversionError::Overflow { target: "f64", .. })));
+    assert_eq!(show_error("0b102", &literal::parse("0b102").unwrap_err()),
+               "0b102\n    ^ invalid digit '2' in binary literal");
+    assert_eq!(literal::parse("12 ").unwrap_err().to_string(), "3: unexpected character ' '");
+
+    // the widest type for values of any size, and the same value in other types
+    assert_eq!(literal::parse_widest("3_000_000_000"), Ok(Literal::I128(3_000_000_000)));
+    assert_eq!(literal::parse_widest("340282366920938463463374607431768211455"), Ok(Literal::U128(u128::MAX)));
+    assert_eq!(literal::parse_widest("-5"), Ok(Literal::I128(-5)));
+    assert_eq!(literal::parse_widest("2.5"), Ok(Literal::F64(2.5)));
+    assert!(literal::parse_widest("300u8").is_err());
+    assert!(literal::parse_widest("-340282366920938463463374607431768211455").is_err());
+    let value = literal::parse("300").unwrap();
+    assert_eq!(value.to::<u16>(), Ok(300));
+    assert_eq!(value.to::<f32>(), Ok(300.0));
+    assert_eq!(value.to::<u8>().unwrap_err().to_string(), "300 is out of range of u8 (0 to 255)");
+    assert!(matches!(literal::parse("0.1").unwrap().to::<f32>(), Err(ConversionError::PrecisionLost { .. })));
+    // the example of ./data-types, too large for f32 rather than inexact
+    assert!(matches!(literal::parse_widest("1e39").unwrap().to::<f32>(), Err(ConversionError::Overflow { target: "f32", .. })));
+    assert_eq!(literal::parse("2.0f32").unwrap().to::<i8>(), Ok(2));
+
+    // random values in many spellings parse back to themselves
+    let mut random = Random::new(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..10_000 {
+        let n = random.next() as i64 >> random.below(64);
+        assert_eq!(literal::parse(&format!("{}i64", n)), Ok(Literal::I64(n)));
+        let underscored: String = format!("{}", n.unsigned_abs()).chars()
+            .flat_map(|c| Some(c).into_iter().chain(if random.below(3) == 0 { Some('_') } else { None }))
+            .collect();
+        let sign = if n < 0 { "-" } else { "" };
+        assert_eq!(literal::parse(&format!("{}{}i64", sign, underscored)), Ok(Literal::I64(n)));
+        assert_eq!(literal::parse(&format!("{}0x{:x}_i64", sign, n.unsigned_abs())), Ok(Literal::I64(n)));
+        assert_eq!(literal::parse(&format!("{}0o{:o}i64", sign, n.unsigned_abs())), Ok(Literal::I64(n)));
+        assert_eq!(literal::parse(&format!("{}0b{:b}i64", sign, n.unsigned_abs())), Ok(Literal::I64(n)));
+
+        let x = f64::from_bits(random.next());
+        if x.is_finite() {
+            assert_eq!(literal::parse(&format!("{:e}", x)), Ok(Literal::F64(x)));
+            assert_eq!(literal::parse(&Literal::F64(x).to_string()), Ok(Literal::F64(x)));
+            let y = x as f32;
+            if y.is_finite() {
+                assert_eq!(literal::parse(&format!("{:?}f32", y)), Ok(Literal::F32(y)));
+            }
+        }
+    }
+}
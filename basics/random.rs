@@ -0,0 +1,26 @@
+// random.rs
+// xorshift64 pseudo-random numbers for the checks of the example binaries. The same seed gives the
+// same numbers on every run, so a failing check can be run again. Not for anything which has to
+// be unpredictable.
+
+pub struct Random(u64);
+
+impl Random {
+    // The seed must not be 0, xorshift would stay at 0.
+    pub fn new(seed: u64) -> Random {
+        assert_ne!(seed, 0, "xorshift64 seed must not be 0");
+        Random(seed)
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    // A number in 0..n.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
@@ -0,0 +1,187 @@
+// run-script.rs
+// Run scripts with the control flow of control-flow.rs, see script.rs.
+//
+// rustc run-script.rs
+// ./run-script run control-flow.rs
+// ./run-script check                 runs control-flow.rs and compares the output
+
+use std::fs;
+use std::io::{self, Write};
+use std::process;
+
+mod args;
+mod convert;
+mod crash_report;
+mod lexer;
+mod literal;
+mod script;
+
+// What ./control-flow prints.
+const CONTROL_FLOW_OUTPUT: &str = "\
+number is divisible by 3
+The value of number is: 5
+counter = 1
+counter = 2
+counter = 3
+counter = 4
+counter = 5
+counter = 6
+counter = 7
+counter = 8
+counter = 9
+The result is 20
+3!
+2!
+1!
+LIFTOFF!!!
+the value is: 10
+the value is: 20
+the value is: 30
+the value is: 40
+the value is: 50
+3!
+2!
+1!
+LIFTOFF!!!
+";
+
+fn main() {
+    crash_report::install();
+
+    let matches = args::Command::new("run-script")
+        .about("Run scripts with let, if, loop, while, for and println!.")
+        .subcommand(args::Command::new("run")
+            .about("Run the script and print its output")
+            .positional("FILE", "Script, fn main() around the statements is optional"))
+        .subcommand(args::Command::new("check")
+            .about("Check the interpreter with control-flow.rs and small scripts"))
+        .parse();
+
+    match matches.subcommand() {
+        Some(("run", sub)) => {
+            let path = sub.value("FILE").unwrap();
+            let source = fs::read_to_string(path).unwrap_or_else(|e| exit_with(&format!("{}: {}", path, e), 2));
+            let program = script::parse(&source).unwrap_or_else(|e| exit_with(&format!("{}:{}", path, e), 1));
+            let mut out = io::stdout().lock();
+            let mut print = |line: &str| match writeln!(out, "{}", line) {
+                Ok(()) => {}
+                // output closed early, e.g. by head
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => process::exit(0),
+                Err(e) => exit_with(&e.to_string(), 2),
+            };
+            if let Err(e) = script::run(&program, &mut print) {
+                exit_with(&format!("{}:{}", path, e), 1);
+            }
+        }
+        Some(("check", _)) => {
+            check();
+            println!("ok");
+        }
+        _ => matches.error(args::ErrorKind::Other(String::from("a command is required"))).exit(),
+    }
+}
+
+// The printed lines, and the error which stopped the script.
+fn run(source: &str) -> (Vec<String>, Option<String>) {
+    let mut lines = Vec::new();
+    let result = script::parse(source).and_then(|program| script::run(&program, &mut |line| lines.push(line.to_string())));
+    (lines, result.err().map(|e| e.to_string()))
+}
+
+fn output(source: &str) -> Vec<String> {
+    let (lines, error) = run(source);
+    assert_eq!(error, None, "{}", source);
+    lines
+}
+
+fn error(source: &str) -> String {
+    run(source).1.unwrap_or_else(|| panic!("no error: {}", source))
+}
+
+fn check() {
+    // the example itself, with its comments and fn main
+    let (lines, failure) = run(include_str!("control-flow.rs"));
+    assert_eq!(failure, None);
+    assert_eq!(lines.iter().map(|line| format!("{}\n", line)).collect::<String>(), CONTROL_FLOW_OUTPUT);
+
+    // if and blocks are expressions, shadowed variables come back after the block
+    assert_eq!(output("let x = 5; { let x = x * 2; println!(\"{}\", x); } println!(\"{}\", x);"), ["10", "5"]);
+    assert_eq!(output("let n = if 1 > 2 { 1 } else if false { 2 } else { 3 }; println!(\"{}\", n + { 4 });"), ["7"]);
+    assert_eq!(output("let x; if true { x = 1; } else { x = 2; } println!(\"{}\", x)"), ["1"]);
+    assert_eq!(output("let x: i32; x = 1; println!(\"{}\", x);"), ["1"]);
+    assert_eq!(output("let b: bool = !(1 + 2 * 3 == 7 && 5 % 3 >= 2 || false); println!(\"{}\", b);"), ["false"]);
+
+    // loops, break only leaves the innermost one
+    let source = "
+        let mut found = 0;
+        let mut i = 0;
+        while i < 10 {
+            i += 1;
+            if i % 2 == 0 { continue; }
+            let square = loop { found += 1; break i * i; };
+            println!(\"{} {}\", i, square);
+            if square > 20 { break }
+        }
+        println!(\"found {}\", found);";
+    assert_eq!(output(source), ["1 1", "3 9", "5 25", "found 3"]);
+    assert_eq!(output("for x in [1, 2, 3].iter().rev() { println!(\"{}\", x); }"), ["3", "2", "1"]);
+    assert_eq!(output("for i in 1..=3 { println!(\"{}\", i); } for _ in (0..0).rev() { println!(\"never\"); }"), ["1", "2", "3"]);
+    assert_eq!(output("let a = [0; 3]; let mut n = 0; for x in a { n += x + 1; } println!(\"{} {}\", n, a.len());"), ["3 3"]);
+    assert_eq!(output("let mut sum = 0; for i in (2147483645..=2147483647).rev() { sum -= i % 10; } println!(\"{}\", sum)"), ["-18"]);
+
+    // formats
+    assert_eq!(output("println!(\"{{}} {:?} {:?} {}\", [[1], [2]], (1..4).rev(), true,);"), ["{} [[1], [2]] Rev { iter: 1..4 } true"]);
+    assert_eq!(output("let u = {}; let w = while false {}; println!(\"{:?}{:?}\\t\\u{2713}\\\n    done\", u, w); println!();"),
+               ["()()\t✓done", ""]);
+
+    // errors stop the script at their line, after what it printed
+    let source = "fn main() {\n    let a = [1, 2, 3];\n    println!(\"{}\", a[0]);\n    println!(\"{}\", a[3]);\n}\n";
+    assert_eq!(run(source), (vec![String::from("1")], Some(String::from("4:21: index out of bounds: the len is 3 but the index is 3"))));
+    assert_eq!(error("let x = 2147483647;\nlet y = x + 1;"), "2:11: attempt to add with overflow");
+    assert_eq!(error("let x = 0;\nprintln!(\"{}\", 10 % x);"), "2:19: attempt to calculate the remainder with a divisor of zero");
+    assert_eq!(error("let n = 1 / (2 - 2);"), "1:11: attempt to divide by zero");
+    assert_eq!(error("println!(\"{}\", -2147483648 / -1);"), "1:28: attempt to divide with overflow");
+    assert_eq!(error("let x = 1;\nx = 2;"), "2:3: cannot assign twice to immutable variable `x`");
+    assert_eq!(error("let mut x = 1;\nx = true;"), "2:3: mismatched types: expected i32, found bool");
+    assert_eq!(error("let x: bool;\nx = 1;\nprintln!(\"{}\", x);"), "2:3: mismatched types: expected bool, found i32");
+    assert_eq!(error("{ let y = 1; }\nprintln!(\"{}\", y);"), "2:16: cannot find value `y` in this scope");
+    assert_eq!(error("let x;\nlet y = x;"), "2:9: used binding `x` isn't initialized");
+    assert_eq!(error("if 1 { }"), "1:4: mismatched types: expected bool, found i32");
+    assert_eq!(error("let x = if true { loop { break 1; } };"), "1:9: `if` may be missing an `else` clause");
+    assert_eq!(error("let b = true + 1;"), "1:14: cannot apply `+` to bool and i32");
+    assert_eq!(error("let b = 1 < 2 < 3;"), "1:15: comparison operators cannot be chained");
+    assert_eq!(error("let b = 1 == 1 != true;"), "1:16: comparison operators cannot be chained");
+    assert_eq!(run("println!(\"{}\", (1 < 2) == true && 1 + 1 < 3 || 2 > 1);"), (vec![String::from("true")], None));
+    assert_eq!(error("let a = [1, false];"), "1:13: mismatched types: expected i32, found bool");
+    assert_eq!(error("let a = [[1], [true]];"), "1:15: mismatched types: expected [i32; 1], found [bool; 1]");
+    assert_eq!(error("let a = [[1], [2, 3]];"), "1:15: mismatched types: expected [i32; 1], found [i32; 2]");
+    assert_eq!(error("let mut a = [1];\na = [false];"), "2:3: mismatched types: expected [i32; 1], found [bool; 1]");
+    assert_eq!(error("let b = [1] == [true];"), "1:13: cannot apply `==` to [i32; 1] and [bool; 1]");
+    assert_eq!(output("let mut a = [[0; 2]; 0]; a = [[0; 2]; 0]; println!(\"{:?} {}\", a, [[1, 2]] == [[1, 2]]);"), ["[] true"]);
+    assert_eq!(error("println!(\"{}\", [1]);"), "1:16: `array` doesn't implement Display, use {:?}");
+    assert_eq!(error("for x in 5 { }"), "1:10: `i32` is not an iterator");
+    assert_eq!(error("let x = 5.rev();"), "1:10: no method named `rev` found for i32");
+
+    // mistakes found before anything runs
+    assert_eq!(run("println!(\"never\");\nbreak;"), (vec![], Some(String::from("2:1: `break` outside of a loop"))));
+    assert_eq!(error("while true { break 5; }"), "1:20: `break` with value from a `while` loop");
+    assert_eq!(error("loop { let f = || { continue }; }"), "1:16: expected an expression, found `||`");
+    assert_eq!(error("println!(\"{} {}\", 1);"), "1:10: 2 placeholders in the format string, but 1 arguments");
+    assert_eq!(error("println!(\"{x}\");"), "1:10: only {} and {:?} are supported in format strings");
+    assert_eq!(error("let s = \"text\";"), "1:9: strings are only supported as the format of println!");
+    assert_eq!(error("let x = 1\nlet y = 2;"), "2:1: expected `;`, found `let`");
+    assert_eq!(error("let x = 5u8;"), "1:9: `5u8` is u8, scripts only have i32 integers");
+    assert_eq!(run("println!(\"never\");\nlet x = if false { 1 };"), (vec![], Some(String::from("2:9: `if` may be missing an `else` clause"))));
+    assert_eq!(error("if true { { let y = 1; y } }"), "1:1: `if` may be missing an `else` clause");
+    assert_eq!(output("let mut x = 0; if x == 0 { x = 1 } if true { println!(\"{}\", x) } if false { {} }"), ["1"]);
+    assert_eq!(error("let x = 2147483648;"), "1:9: 2147483648 is out of range of i32 (-2147483648 to 2147483647)");
+    assert_eq!(error("fn helper() {}"), "1:4: only fn main() is supported");
+    assert_eq!(error("fn main() { 1 } 2"), "1:17: expected the end of the script, found `2`");
+    assert_eq!(error("if true {"), "1:10: expected `}`, found the end of the script");
+    assert_eq!(error("let s = \"open"), "1:9: unterminated string");
+}
+
+fn exit_with(message: &str, code: i32) -> ! {
+    eprintln!("run-script: {}", message);
+    process::exit(code);
+}
@@ -0,0 +1,198 @@
+// rust-lexer.rs
+// Split Rust source code into tokens, see lexer.rs.
+//
+// rustc rust-lexer.rs
+// ./rust-lexer tokens hello-rust.rs
+// ./rust-lexer check                     lexes every .rs file of basics and advanced
+//
+// The default directories of check are found by the path of this file given to rustc, which is
+// relative to the directory rustc ran in, so check runs from there or with an absolute path.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+mod args;
+mod convert;
+mod crash_report;
+mod lexer;
+mod literal;
+
+use lexer::{LexError, Token, TokenKind};
+
+fn main() {
+    crash_report::install();
+
+    let matches = args::Command::new("rust-lexer")
+        .about("Split Rust source code into tokens.")
+        .subcommand(args::Command::new("tokens")
+            .about("Print every token of the file with its line, column and kind")
+            .positional("FILE", "Rust source file"))
+        .subcommand(args::Command::new("check")
+            .about("Check the lexer and lex every .rs file of the directories")
+            .multiple("DIR", "Directories to search for .rs files [default: basics and advanced]"))
+        .parse();
+
+    match matches.subcommand() {
+        Some(("tokens", sub)) => {
+            let path = sub.value("FILE").unwrap();
+            let source = fs::read_to_string(path).unwrap_or_else(|e| exit_with(&format!("{}: {}", path, e)));
+            let mut out = io::stdout().lock();
+            for token in lexer::lexer(&source) {
+                let token = token.unwrap_or_else(|e| exit_with(&format!("{}:{}", path, e)));
+                let written = writeln!(out, "{}:{} {:?} {}", token.span.line, token.span.column, token.kind, token.text);
+                match written {
+                    Ok(()) => {}
+                    // output closed early, e.g. by head
+                    Err(e) if e.kind() == io::ErrorKind::BrokenPipe => process::exit(0),
+                    Err(e) => exit_with(&e.to_string()),
+                }
+            }
+        }
+        Some(("check", sub)) => {
+            check();
+            let mut dirs: Vec<PathBuf> = sub.values("DIR").iter().map(PathBuf::from).collect();
+            if dirs.is_empty() {
+                // basics is the directory of this file
+                let basics = Path::new(file!()).parent().unwrap_or(Path::new(""));
+                dirs = vec![basics.join("."), basics.join("../advanced")];
+            }
+            let mut files = Vec::new();
+            for dir in &dirs {
+                rust_files(dir, &mut files).unwrap_or_else(|e| exit_with(&format!("{}: {}", dir.display(), e)));
+            }
+            let mut failed = false;
+            let mut count = 0;
+            for file in &files {
+                match lex_file(file) {
+                    Ok(n) => count += n,
+                    Err(e) => {
+                        eprintln!("{}:{}", file.display(), e);
+                        failed = true;
+                    }
+                }
+            }
+            if failed {
+                process::exit(1);
+            }
+            println!("ok, {} tokens in {} files", count, files.len());
+        }
+        _ => matches.error(args::ErrorKind::Other(String::from("a command is required"))).exit(),
+    }
+}
+
+// .rs files of the directory and its subdirectories in sorted order.
+fn rust_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            rust_files(&path, files)?;
+        } else if file_type.is_file() && path.extension().is_some_and(|e| e == "rs") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+// Number of tokens, and the tokens cover everything but whitespace.
+fn lex_file(path: &Path) -> Result<usize, String> {
+    let source = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let tokens = lexer::tokenize(&source).map_err(|e| e.to_string())?;
+    let mut end = 0;
+    for token in &tokens {
+        assert!(source[end..token.span.start].chars().all(char::is_whitespace), "{}: {:?}", path.display(), token);
+        assert_eq!(&source[token.span.start..token.span.end], token.text);
+        end = token.span.end;
+    }
+    assert!(source[end..].chars().all(char::is_whitespace));
+    Ok(tokens.len())
+}
+
+fn kinds(source: &str) -> Vec<(TokenKind, &str)> {
+    lexer::tokenize(source).unwrap().iter().map(|t| (t.kind, t.text)).collect()
+}
+
+fn error(source: &str) -> String {
+    lexer::tokenize(source).unwrap_err().to_string()
+}
+
+fn check() {
+    use TokenKind::*;
+
+    assert_eq!(kinds("fn longest<'a>(x: &'a str) -> &'a str { x }"), [
+        (Keyword, "fn"), (Ident, "longest"), (Punct, "<"), (Lifetime, "'a"), (Punct, ">"), (Punct, "("),
+        (Ident, "x"), (Punct, ":"), (Punct, "&"), (Lifetime, "'a"), (Ident, "str"), (Punct, ")"),
+        (Punct, "->"), (Punct, "&"), (Lifetime, "'a"), (Ident, "str"), (Punct, "{"), (Ident, "x"), (Punct, "}"),
+    ]);
+    // chars and lifetimes
+    assert_eq!(kinds(r"'a' '\n' '\'' '\u{1F600}' '€' b'x' b'\xff' 'static 'outer: loop"), [
+        (Char, "'a'"), (Char, r"'\n'"), (Char, r"'\''"), (Char, r"'\u{1F600}'"), (Char, "'€'"),
+        (Char, "b'x'"), (Char, r"b'\xff'"), (Lifetime, "'static"), (Lifetime, "'outer"), (Punct, ":"),
+        (Keyword, "loop"),
+    ]);
+    // strings
+    assert_eq!(kinds(r###""a\"b" b"\x00" r"C:\" r#"say "hi""# br##"a"#b"## r#type"###), [
+        (Str, r#""a\"b""#), (Str, r#"b"\x00""#), (RawStr, r#"r"C:\""#), (RawStr, r###"r#"say "hi""#"###),
+        (RawStr, r###"br##"a"#b"##"###), (Ident, "r#type"),
+    ]);
+    assert_eq!(kinds("\"multi\nline \\\n    continued\""), [(Str, "\"multi\nline \\\n    continued\"")]);
+    // comments
+    assert_eq!(kinds("a /* x /* nested */ y */ b // rest\n/// doc\nc"), [
+        (Ident, "a"), (BlockComment, "/* x /* nested */ y */"), (Ident, "b"), (LineComment, "// rest"),
+        (LineComment, "/// doc"), (Ident, "c"),
+    ]);
+    // numbers, literal.rs decides where they end
+    assert_eq!(kinds("0xffu8 1_000 1e-3_f64 2.5 1..=5 x.0 3_000_000_000 1usize-1 0b1111_0000"), [
+        (Number, "0xffu8"), (Number, "1_000"), (Number, "1e-3_f64"), (Number, "2.5"), (Number, "1"),
+        (Punct, "..="), (Number, "5"), (Ident, "x"), (Punct, "."), (Number, "0"), (Number, "3_000_000_000"),
+        (Number, "1usize"), (Punct, "-"), (Number, "1"), (Number, "0b1111_0000"),
+    ]);
+    assert_eq!(kinds("1.max(2)"), [(Number, "1"), (Punct, "."), (Ident, "max"), (Punct, "("), (Number, "2"), (Punct, ")")]);
+    // the longest punctuation
+    assert_eq!(kinds("a>>=b::<c>...d"), [
+        (Ident, "a"), (Punct, ">>="), (Ident, "b"), (Punct, "::"), (Punct, "<"), (Ident, "c"), (Punct, ">"),
+        (Punct, "..."), (Ident, "d"),
+    ]);
+    assert_eq!(kinds("_ _x über"), [(Ident, "_"), (Ident, "_x"), (Ident, "über")]);
+
+    // spans of tokens after multi-line tokens and non-ASCII chars
+    let tokens = lexer::tokenize("/* ä\n */ \"é\nx\" y\n\tz").unwrap();
+    let spans: Vec<(usize, usize, usize, usize)> = tokens.iter()
+        .map(|Token { span, .. }| (span.start, span.end, span.line, span.column))
+        .collect();
+    assert_eq!(spans, [(0, 9, 1, 1), (10, 16, 2, 5), (17, 18, 3, 4), (20, 21, 4, 2)]);
+
+    // errors with their position
+    assert_eq!(error("let s = \"open"), "1:9: unterminated string");
+    assert_eq!(error("a\n  /* /* */"), "2:3: unterminated block comment");
+    assert_eq!(error("r#\"x\"# r##\"x\"#"), "1:8: unterminated raw string");
+    assert_eq!(error("x = '\\q';"), "1:6: unknown character escape '\\q'");
+    assert_eq!(error("'ab'"), "1:4: unterminated character literal");
+    assert_eq!(error("''"), "1:1: empty character literal");
+    assert_eq!(error("'\\n\\n'"), "1:1: character literal may only contain one character");
+    assert_eq!(error("\"\\x80\""), "1:2: \\x escape must be at most \\x7f, use \\u{..}");
+    assert_eq!(error("\"\\u{110000}\""), "1:2: invalid unicode escape");
+    assert_eq!(error("b\"\\u{41}\""), "1:3: unicode escape in byte literal");
+    assert_eq!(error("b\"ä\""), "1:3: non-ASCII character 'ä' in byte string");
+    assert_eq!(error("let x = 0b102;"), "1:13: invalid digit '2' in binary literal");
+    assert_eq!(error("  5i7"), "1:4: invalid suffix 'i7', expected one of i8, i16, i32, i64, i128, isize, u8, \
+                                   u16, u32, u64, u128, usize or f32, f64");
+    assert_eq!(error("340282366920938463463374607431768211456u128"), "1:1: integer literal is too large for any type");
+    assert_eq!(kinds("-128i8 300u8"), [(Punct, "-"), (Number, "128i8"), (Number, "300u8")]);
+    assert_eq!(error("a €"), "1:3: unexpected character '€'");
+
+    // nothing after an error
+    let mut tokens = lexer::lexer("a € b");
+    assert!(tokens.next().unwrap().is_ok());
+    assert!(matches!(tokens.next(), Some(Err(LexError { column: 3, .. }))));
+    assert!(tokens.next().is_none());
+}
+
+fn exit_with(message: &str) -> ! {
+    eprintln!("rust-lexer: {}", message);
+    process::exit(2);
+}
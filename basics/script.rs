@@ -0,0 +1,1021 @@
+// script.rs
+// An interpreter for the constructs of control-flow.rs, which it runs unchanged:
+//
+// let mut counter = 0;
+// let result = loop {
+//     counter += 1;
+//     if counter == 10 { break counter * 2; }
+// };
+// for number in (1..4).rev() { println!("{}!", number); }
+//
+// A script is a list of statements, or fn main() around them. Values are i32 integers, bools,
+// arrays and ranges, strings only appear as the format of println!. Blocks, if, loop, while and
+// for are expressions like in Rust and variables can be shadowed. Types are checked while the
+// script runs, and overflow, division by zero and indexes out of bounds stop it with an error at
+// their line like the panics of Rust do.
+//
+// Not handled: other functions, references, tuples, match, loop labels and formats other than
+// {} and {:?}.
+//
+// Binaries using this module must also declare `mod convert;`, `mod lexer;` and `mod literal;`.
+#![allow(dead_code)]
+
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::lexer::{self, Token, TokenKind};
+use crate::literal::{self, Literal};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i32),
+    Bool(bool),
+    Array(Vec<Value>),
+    // start..end, counting down from end - 1 when reversed
+    Range { start: i64, end: i64, reversed: bool },
+    Unit,
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "i32",
+            Value::Bool(_) => "bool",
+            Value::Array(_) => "array",
+            Value::Range { .. } => "range",
+            Value::Unit => "()",
+        }
+    }
+
+    // The type like Rust writes it, [[i32; 2]; 3] for arrays and [_; 0] when there are no elements.
+    pub fn type_text(&self) -> String {
+        match self {
+            Value::Array(values) => {
+                let element = values.first().map_or(String::from("_"), Value::type_text);
+                format!("[{}; {}]", element, values.len())
+            }
+            other => other.type_name().to_string(),
+        }
+    }
+
+    // Arrays have the same type when their lengths and the types of their elements are the same.
+    fn same_type(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Array(a), Value::Array(b)) => {
+                a.len() == b.len() && a.first().zip(b.first()).is_none_or(|(a, b)| a.same_type(b))
+            }
+            (a, b) => a.type_name() == b.type_name(),
+        }
+    }
+
+    // {:?} works for every value, {} only for integers and bools.
+    fn format(&self, debug: bool) -> Result<String, String> {
+        match self {
+            Value::Int(n) => Ok(n.to_string()),
+            Value::Bool(b) => Ok(b.to_string()),
+            _ if !debug => Err(format!("`{}` doesn't implement Display, use {{:?}}", self.type_name())),
+            Value::Array(values) => {
+                let values: Vec<String> = values.iter().map(|v| v.format(true).unwrap()).collect();
+                Ok(format!("[{}]", values.join(", ")))
+            }
+            Value::Range { start, end, reversed: false } => Ok(format!("{}..{}", start, end)),
+            Value::Range { start, end, reversed: true } => Ok(format!("Rev {{ iter: {}..{} }}", start, end)),
+            Value::Unit => Ok(String::from("()")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptError {
+    // both start at 1, column is in chars
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+#[derive(Debug)]
+pub struct Program(Block);
+
+#[derive(Debug)]
+struct Block {
+    statements: Vec<Statement>,
+    // the value of the block, () without it
+    tail: Option<Box<Expr>>,
+}
+
+#[derive(Debug)]
+enum Statement {
+    Let { name: String, mutable: bool, annotation: Option<&'static str>, value: Option<Expr> },
+    Expr(Expr),
+}
+
+#[derive(Debug)]
+struct Expr {
+    kind: ExprKind,
+    line: usize,
+    column: usize,
+}
+
+#[derive(Debug)]
+enum ExprKind {
+    Int(i32),
+    Bool(bool),
+    Unit,
+    Var(String),
+    Array(Vec<Expr>),
+    // [value; count]
+    Repeat(Box<Expr>, Box<Expr>),
+    Index(Box<Expr>, Box<Expr>),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Range { start: Box<Expr>, end: Box<Expr>, inclusive: bool },
+    // iter, rev and len, none of them takes arguments
+    Method(Box<Expr>, String),
+    Block(Block),
+    If(Box<Expr>, Block, Option<Box<Expr>>),
+    Loop(Block),
+    While(Box<Expr>, Block),
+    // None for the pattern _
+    For(Option<String>, Box<Expr>, Block),
+    Break(Option<Box<Expr>>),
+    Continue,
+    Println(Vec<Piece>, Vec<Expr>),
+    // += is Some("+")
+    Assign(Option<&'static str>, String, Box<Expr>),
+}
+
+#[derive(Debug)]
+enum Piece {
+    Text(String),
+    Display,
+    Debug,
+}
+
+// Operators with their precedence, higher binds tighter.
+// Precedence of == != < <= > >=.
+const COMPARISON: u8 = 3;
+
+const BINARY: [(&str, u8); 13] = [
+    ("||", 1), ("&&", 2),
+    ("==", 3), ("!=", 3), ("<", 3), ("<=", 3), (">", 3), (">=", 3),
+    ("+", 4), ("-", 4), ("*", 5), ("/", 5), ("%", 5),
+];
+
+const ASSIGN: [(&str, Option<&str>); 6] =
+    [("=", None), ("+=", Some("+")), ("-=", Some("-")), ("*=", Some("*")), ("/=", Some("/")), ("%=", Some("%"))];
+
+pub fn parse(source: &str) -> Result<Program, ScriptError> {
+    let tokens = lexer::lexer(source)
+        .filter(|t| !matches!(t, Ok(Token { kind: TokenKind::LineComment, .. }) | Ok(Token { kind: TokenKind::BlockComment, .. })))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| ScriptError { line: e.line, column: e.column, message: e.message })?;
+    let line_start = source.rfind('\n').map_or(0, |i| i + 1);
+    let end = (source.matches('\n').count() + 1, source[line_start..].chars().count() + 1);
+    let mut parser = Parser { tokens, pos: 0, end, loops: Vec::new() };
+    parser.program()
+}
+
+// Runs the program, out gets the text of every println! without the newline. The lines printed
+// before an error stay printed.
+pub fn run(program: &Program, out: &mut dyn FnMut(&str)) -> Result<(), ScriptError> {
+    let mut interpreter = Interpreter { scopes: Vec::new(), out };
+    match interpreter.block(&program.0) {
+        Ok(_) => Ok(()),
+        Err(Flow::Error(e)) => Err(e),
+        // the parser only accepts break and continue inside of loops
+        Err(Flow::Break(_)) | Err(Flow::Continue) => unreachable!(),
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+    // line and column after the last char of the source
+    end: (usize, usize),
+    // loop, while or for of every loop around the current token
+    loops: Vec<&'static str>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.pos)
+    }
+
+    // Punctuation, keyword or identifier text of the next token.
+    fn is(&self, text: &str) -> bool {
+        self.peek().is_some_and(|t| t.text == text)
+    }
+
+    fn is_at(&self, offset: usize, text: &str) -> bool {
+        self.tokens.get(self.pos + offset).is_some_and(|t| t.text == text)
+    }
+
+    fn advance(&mut self) -> Token<'a> {
+        self.pos += 1;
+        self.tokens[self.pos - 1]
+    }
+
+    fn error(&self, message: String) -> ScriptError {
+        let (line, column) = match self.peek() {
+            Some(token) => (token.span.line, token.span.column),
+            None => self.end,
+        };
+        ScriptError { line, column, message }
+    }
+
+    fn found(&self) -> String {
+        match self.peek() {
+            Some(token) => format!("`{}`", token.text),
+            None => String::from("the end of the script"),
+        }
+    }
+
+    fn expect(&mut self, text: &str) -> Result<Token<'a>, ScriptError> {
+        if self.is(text) {
+            Ok(self.advance())
+        } else {
+            Err(self.error(format!("expected `{}`, found {}", text, self.found())))
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, ScriptError> {
+        match self.peek() {
+            Some(token) if token.kind == TokenKind::Ident => Ok(self.advance().text.to_string()),
+            _ => Err(self.error(format!("expected a name, found {}", self.found()))),
+        }
+    }
+
+    fn expr_at(token: &Token, kind: ExprKind) -> Expr {
+        Expr { kind, line: token.span.line, column: token.span.column }
+    }
+
+    fn program(&mut self) -> Result<Program, ScriptError> {
+        if self.is("fn") {
+            self.advance();
+            if !self.is("main") {
+                return Err(self.error(String::from("only fn main() is supported")));
+            }
+            self.advance();
+            self.expect("(")?;
+            self.expect(")")?;
+            let block = self.block()?;
+            if self.peek().is_some() {
+                return Err(self.error(format!("expected the end of the script, found {}", self.found())));
+            }
+            return Ok(Program(block));
+        }
+        Ok(Program(self.statements(false)?))
+    }
+
+    fn block(&mut self) -> Result<Block, ScriptError> {
+        self.expect("{")?;
+        let block = self.statements(true)?;
+        self.expect("}")?;
+        Ok(block)
+    }
+
+    // Statements up to the } of a block or the end of the script.
+    fn statements(&mut self, braced: bool) -> Result<Block, ScriptError> {
+        let mut statements = Vec::new();
+        let at_end = |parser: &Parser| if braced { parser.is("}") || parser.peek().is_none() } else { parser.peek().is_none() };
+        loop {
+            if at_end(self) {
+                return Ok(Block { statements, tail: None });
+            }
+            if self.is(";") {
+                self.advance();
+                continue;
+            }
+            if self.is("let") {
+                statements.push(self.let_statement()?);
+                continue;
+            }
+            // like in Rust an if or a loop ends the statement without a ;
+            let block_like = ["{", "if", "loop", "while", "for"].iter().any(|k| self.is(k));
+            let expr = if block_like { self.block_like()? } else { self.expr()? };
+            if self.is(";") {
+                self.advance();
+            } else if at_end(self) {
+                return Ok(Block { statements, tail: Some(Box::new(expr)) });
+            } else if !block_like {
+                return Err(self.error(format!("expected `;`, found {}", self.found())));
+            }
+            statements.push(Statement::Expr(expr));
+        }
+    }
+
+    fn let_statement(&mut self) -> Result<Statement, ScriptError> {
+        self.expect("let")?;
+        let mutable = self.is("mut");
+        if mutable {
+            self.advance();
+        }
+        let name = self.ident()?;
+        let mut annotation = None;
+        if self.is(":") {
+            self.advance();
+            annotation = match self.peek().map(|t| t.text) {
+                Some("i32") => Some("i32"),
+                Some("bool") => Some("bool"),
+                _ => return Err(self.error(format!("expected the type i32 or bool, found {}", self.found()))),
+            };
+            self.advance();
+        }
+        let mut value = None;
+        if self.is("=") {
+            self.advance();
+            value = Some(self.expr()?);
+        }
+        self.expect(";")?;
+        Ok(Statement::Let { name, mutable, annotation, value })
+    }
+
+    fn expr(&mut self) -> Result<Expr, ScriptError> {
+        if self.is("break") || self.is("continue") {
+            let token = self.advance();
+            let kind = match self.loops.last() {
+                None => return Err(ScriptError {
+                    line: token.span.line,
+                    column: token.span.column,
+                    message: format!("`{}` outside of a loop", token.text),
+                }),
+                Some(_) if token.text == "continue" => ExprKind::Continue,
+                Some(&kind) => {
+                    let end = self.peek().is_none() || [";", "}", ")", "]", ","].iter().any(|p| self.is(p));
+                    if end {
+                        ExprKind::Break(None)
+                    } else if kind != "loop" {
+                        return Err(self.error(format!("`break` with value from a `{}` loop", kind)));
+                    } else {
+                        ExprKind::Break(Some(Box::new(self.expr()?)))
+                    }
+                }
+            };
+            return Ok(Self::expr_at(&token, kind));
+        }
+
+        let target = self.range()?;
+        let assign = self.peek().and_then(|t| ASSIGN.iter().find(|(text, _)| t.kind == TokenKind::Punct && t.text == *text));
+        if let Some(&(_, op)) = assign {
+            let token = self.advance();
+            let name = match target.kind {
+                ExprKind::Var(name) => name,
+                _ => return Err(ScriptError {
+                    line: token.span.line,
+                    column: token.span.column,
+                    message: String::from("only variables can be assigned to"),
+                }),
+            };
+            let value = self.expr()?;
+            return Ok(Self::expr_at(&token, ExprKind::Assign(op, name, Box::new(value))));
+        }
+        Ok(target)
+    }
+
+    fn range(&mut self) -> Result<Expr, ScriptError> {
+        let start = self.binary(1)?;
+        if self.is("..") || self.is("..=") {
+            let token = self.advance();
+            let end = self.binary(1)?;
+            let kind = ExprKind::Range { start: Box::new(start), end: Box::new(end), inclusive: token.text == "..=" };
+            return Ok(Self::expr_at(&token, kind));
+        }
+        Ok(start)
+    }
+
+    // Operators of precedence min and above, left to right.
+    fn binary(&mut self, min: u8) -> Result<Expr, ScriptError> {
+        let mut left = self.unary()?;
+        let mut compared = false;
+        loop {
+            let operator = self.peek().and_then(|t| BINARY.iter().find(|(op, _)| t.kind == TokenKind::Punct && t.text == *op));
+            match operator {
+                Some(&(op, precedence)) if precedence >= min => {
+                    // like in Rust, 1 < 2 < 3 is an error and not (1 < 2) < 3
+                    if precedence == COMPARISON && compared {
+                        return Err(self.error(String::from("comparison operators cannot be chained")));
+                    }
+                    compared = precedence == COMPARISON;
+                    let token = self.advance();
+                    let right = self.binary(precedence + 1)?;
+                    left = Self::expr_at(&token, ExprKind::Binary(op, Box::new(left), Box::new(right)));
+                }
+                _ => return Ok(left),
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, ScriptError> {
+        if self.is("-") && self.tokens.get(self.pos + 1).is_some_and(|t| t.kind == TokenKind::Number) {
+            // -2147483648 is an i32, 2147483648 isn't
+            let minus = self.advance();
+            let number = self.advance();
+            return self.number(&minus, &format!("-{}", number.text));
+        }
+        for &op in &["-", "!"] {
+            if self.is(op) {
+                let token = self.advance();
+                let operand = self.unary()?;
+                return Ok(Self::expr_at(&token, ExprKind::Unary(op, Box::new(operand))));
+            }
+        }
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<Expr, ScriptError> {
+        let mut expr = self.primary()?;
+        loop {
+            if self.is("[") {
+                let token = self.advance();
+                let index = self.expr()?;
+                self.expect("]")?;
+                expr = Self::expr_at(&token, ExprKind::Index(Box::new(expr), Box::new(index)));
+            } else if self.is(".") {
+                let token = self.advance();
+                let name = self.ident()?;
+                self.expect("(")?;
+                self.expect(")")?;
+                expr = Self::expr_at(&token, ExprKind::Method(Box::new(expr), name));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, ScriptError> {
+        let token = match self.peek() {
+            Some(&token) => token,
+            None => return Err(self.error(String::from("expected an expression, found the end of the script"))),
+        };
+        match (token.kind, token.text) {
+            (TokenKind::Number, text) => {
+                self.advance();
+                self.number(&token, text)
+            }
+            (TokenKind::Keyword, "true") | (TokenKind::Keyword, "false") => {
+                self.advance();
+                Ok(Self::expr_at(&token, ExprKind::Bool(token.text == "true")))
+            }
+            (TokenKind::Ident, "println") if self.is_at(1, "!") => self.println(),
+            (TokenKind::Ident, name) => {
+                self.advance();
+                Ok(Self::expr_at(&token, ExprKind::Var(name.to_string())))
+            }
+            (TokenKind::Punct, "(") => {
+                self.advance();
+                if self.is(")") {
+                    self.advance();
+                    return Ok(Self::expr_at(&token, ExprKind::Unit));
+                }
+                let expr = self.expr()?;
+                if self.is(",") {
+                    return Err(self.error(String::from("tuples are not supported")));
+                }
+                self.expect(")")?;
+                Ok(expr)
+            }
+            (TokenKind::Punct, "[") => self.array(),
+            (TokenKind::Punct, "{") | (TokenKind::Keyword, "if") | (TokenKind::Keyword, "loop")
+            | (TokenKind::Keyword, "while") | (TokenKind::Keyword, "for") => self.block_like(),
+            (TokenKind::Str, _) | (TokenKind::RawStr, _) => {
+                Err(self.error(String::from("strings are only supported as the format of println!")))
+            }
+            _ => Err(self.error(format!("expected an expression, found {}", self.found()))),
+        }
+    }
+
+    fn number(&self, token: &Token, text: &str) -> Result<Expr, ScriptError> {
+        let error = |message| ScriptError { line: token.span.line, column: token.span.column, message };
+        match literal::parse(text) {
+            Ok(Literal::I32(n)) => Ok(Self::expr_at(token, ExprKind::Int(n))),
+            Ok(other) => Err(error(format!("`{}` is {}, scripts only have i32 integers", text, other.type_name()))),
+            Err(e) => Err(error(e.kind.to_string())),
+        }
+    }
+
+    fn array(&mut self) -> Result<Expr, ScriptError> {
+        let token = self.expect("[")?;
+        let mut values = Vec::new();
+        if !self.is("]") {
+            let first = self.expr()?;
+            if self.is(";") {
+                self.advance();
+                let count = self.expr()?;
+                self.expect("]")?;
+                return Ok(Self::expr_at(&token, ExprKind::Repeat(Box::new(first), Box::new(count))));
+            }
+            values.push(first);
+            while self.is(",") {
+                self.advance();
+                if self.is("]") {
+                    break;
+                }
+                values.push(self.expr()?);
+            }
+        }
+        self.expect("]")?;
+        Ok(Self::expr_at(&token, ExprKind::Array(values)))
+    }
+
+    fn block_like(&mut self) -> Result<Expr, ScriptError> {
+        let token = *self.peek().unwrap();
+        let kind = match token.text {
+            "{" => ExprKind::Block(self.block()?),
+            "if" => {
+                self.advance();
+                let condition = self.expr()?;
+                let then = self.block()?;
+                let mut otherwise = None;
+                if self.is("else") {
+                    self.advance();
+                    if self.is("if") {
+                        otherwise = Some(Box::new(self.block_like()?));
+                    } else {
+                        let else_token = *self.peek().unwrap_or(&token);
+                        otherwise = Some(Box::new(Self::expr_at(&else_token, ExprKind::Block(self.block()?))));
+                    }
+                }
+                ExprKind::If(Box::new(condition), then, otherwise)
+            }
+            "loop" => {
+                self.advance();
+                ExprKind::Loop(self.loop_body("loop")?)
+            }
+            "while" => {
+                self.advance();
+                let condition = self.expr()?;
+                ExprKind::While(Box::new(condition), self.loop_body("while")?)
+            }
+            _ => {
+                self.expect("for")?;
+                let name = if self.is("_") {
+                    self.advance();
+                    None
+                } else {
+                    Some(self.ident()?)
+                };
+                self.expect("in")?;
+                let iterable = self.expr()?;
+                ExprKind::For(name, Box::new(iterable), self.loop_body("for")?)
+            }
+        };
+        let expr = Self::expr_at(&token, kind);
+        if let ExprKind::If(_, then, None) = &expr.kind {
+            if then.tail.as_deref().is_some_and(has_value) {
+                return Err(error(&expr, String::from("`if` may be missing an `else` clause")));
+            }
+        }
+        Ok(expr)
+    }
+
+    fn loop_body(&mut self, kind: &'static str) -> Result<Block, ScriptError> {
+        self.loops.push(kind);
+        let body = self.block();
+        self.loops.pop();
+        body
+    }
+
+    // println!("format", args...) with {} and {:?}, {{ and }} for the braces.
+    fn println(&mut self) -> Result<Expr, ScriptError> {
+        let token = self.advance();
+        self.expect("!")?;
+        self.expect("(")?;
+        let mut pieces = Vec::new();
+        let mut args = Vec::new();
+        if !self.is(")") {
+            let format = match self.peek() {
+                Some(format) if format.kind == TokenKind::Str && format.text.starts_with('"') => *format,
+                _ => return Err(self.error(format!("expected a format string, found {}", self.found()))),
+            };
+            pieces = parse_format(&unescape(format.text))
+                .map_err(|message| self.error(message))?;
+            self.advance();
+            while self.is(",") {
+                self.advance();
+                if self.is(")") {
+                    break;
+                }
+                args.push(self.expr()?);
+            }
+            let placeholders = pieces.iter().filter(|p| !matches!(p, Piece::Text(_))).count();
+            if placeholders != args.len() {
+                return Err(ScriptError {
+                    line: format.span.line,
+                    column: format.span.column,
+                    message: format!("{} placeholders in the format string, but {} arguments", placeholders, args.len()),
+                });
+            }
+        }
+        self.expect(")")?;
+        Ok(Self::expr_at(&token, ExprKind::Println(pieces, args)))
+    }
+}
+
+// Whether the expression may have a value other than (), which an if without else can't have.
+fn has_value(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Unit | ExprKind::Println(..) | ExprKind::Assign(..) | ExprKind::While(..) | ExprKind::For(..)
+        | ExprKind::Break(_) | ExprKind::Continue => false,
+        // the value of a loop comes from its break, which is only known when it runs
+        ExprKind::Loop(_) => false,
+        ExprKind::Block(block) => block.tail.as_deref().is_some_and(has_value),
+        ExprKind::If(_, then, otherwise) => {
+            then.tail.as_deref().is_some_and(has_value) || otherwise.as_deref().is_some_and(has_value)
+        }
+        _ => true,
+    }
+}
+
+// The text of a string literal checked by lexer.rs, without quotes and escapes.
+fn unescape(quoted: &str) -> String {
+    let mut text = String::new();
+    let mut chars = quoted[1..quoted.len() - 1].chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => text.push('\n'),
+            Some('r') => text.push('\r'),
+            Some('t') => text.push('\t'),
+            Some('0') => text.push('\0'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                text.push(u8::from_str_radix(&hex, 16).unwrap() as char);
+            }
+            Some('u') => {
+                let hex: String = chars.by_ref().skip(1).take_while(|&c| c != '}').filter(|&c| c != '_').collect();
+                text.push(char::from_u32(u32::from_str_radix(&hex, 16).unwrap()).unwrap());
+            }
+            // a \ at the end of a line skips the newline and the indentation
+            Some('\n') | Some('\r') => {
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+            }
+            // \\ \' \"
+            Some(c) => text.push(c),
+            None => {}
+        }
+    }
+    text
+}
+
+fn parse_format(format: &str) -> Result<Vec<Piece>, String> {
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut rest = format;
+    while let Some(c) = rest.chars().next() {
+        let placeholder = if rest.starts_with("{{") || rest.starts_with("}}") {
+            text.push(c);
+            rest = &rest[2..];
+            continue;
+        } else if rest.starts_with("{}") {
+            Piece::Display
+        } else if rest.starts_with("{:?}") {
+            Piece::Debug
+        } else if c == '{' {
+            return Err(String::from("only {} and {:?} are supported in format strings"));
+        } else if c == '}' {
+            return Err(String::from("unmatched `}` in format string, use }}"));
+        } else {
+            text.push(c);
+            rest = &rest[c.len_utf8()..];
+            continue;
+        };
+        rest = &rest[if matches!(placeholder, Piece::Display) { 2 } else { 4 }..];
+        if !text.is_empty() {
+            pieces.push(Piece::Text(std::mem::take(&mut text)));
+        }
+        pieces.push(placeholder);
+    }
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    Ok(pieces)
+}
+
+// Why an expression didn't give a value.
+enum Flow {
+    Break(Value),
+    Continue,
+    Error(ScriptError),
+}
+
+impl From<ScriptError> for Flow {
+    fn from(e: ScriptError) -> Flow {
+        Flow::Error(e)
+    }
+}
+
+struct Binding {
+    name: String,
+    // None after let x; until the first assignment
+    value: Option<Value>,
+    mutable: bool,
+    // i32 or bool of let x: i32, the first assignment must have it
+    annotation: Option<&'static str>,
+}
+
+struct Interpreter<'o> {
+    // the variables of every block, the innermost last
+    scopes: Vec<Vec<Binding>>,
+    out: &'o mut dyn FnMut(&str),
+}
+
+fn error(expr: &Expr, message: String) -> ScriptError {
+    ScriptError { line: expr.line, column: expr.column, message }
+}
+
+fn mismatched(expr: &Expr, expected: &str, found: &Value) -> ScriptError {
+    error(expr, format!("mismatched types: expected {}, found {}", expected, found.type_text()))
+}
+
+impl<'o> Interpreter<'o> {
+    fn block(&mut self, block: &Block) -> Result<Value, Flow> {
+        self.scopes.push(Vec::new());
+        let value = self.statements(block);
+        self.scopes.pop();
+        value
+    }
+
+    fn statements(&mut self, block: &Block) -> Result<Value, Flow> {
+        for statement in &block.statements {
+            match statement {
+                Statement::Let { name, mutable, annotation, value } => {
+                    let value = match value {
+                        Some(expr) => {
+                            let value = self.eval(expr)?;
+                            if let Some(annotation) = annotation {
+                                if value.type_name() != *annotation {
+                                    return Err(mismatched(expr, annotation, &value).into());
+                                }
+                            }
+                            Some(value)
+                        }
+                        None => None,
+                    };
+                    let binding = Binding { name: name.clone(), value, mutable: *mutable, annotation: *annotation };
+                    self.scopes.last_mut().unwrap().push(binding);
+                }
+                Statement::Expr(expr) => {
+                    self.eval(expr)?;
+                }
+            }
+        }
+        match &block.tail {
+            Some(expr) => self.eval(expr),
+            None => Ok(Value::Unit),
+        }
+    }
+
+    // The latest binding of the name, shadowed ones come first.
+    fn binding(&mut self, expr: &Expr, name: &str) -> Result<&mut Binding, ScriptError> {
+        self.scopes.iter_mut().rev()
+            .flat_map(|scope| scope.iter_mut().rev())
+            .find(|binding| binding.name == name)
+            .ok_or_else(|| error(expr, format!("cannot find value `{}` in this scope", name)))
+    }
+
+    fn int(&mut self, expr: &Expr) -> Result<i32, Flow> {
+        match self.eval(expr)? {
+            Value::Int(n) => Ok(n),
+            other => Err(mismatched(expr, "i32", &other).into()),
+        }
+    }
+
+    fn bool(&mut self, expr: &Expr) -> Result<bool, Flow> {
+        match self.eval(expr)? {
+            Value::Bool(b) => Ok(b),
+            other => Err(mismatched(expr, "bool", &other).into()),
+        }
+    }
+
+    fn eval(&mut self, expr: &Expr) -> Result<Value, Flow> {
+        let value = match &expr.kind {
+            ExprKind::Int(n) => Value::Int(*n),
+            ExprKind::Bool(b) => Value::Bool(*b),
+            ExprKind::Unit => Value::Unit,
+            ExprKind::Var(name) => match &self.binding(expr, name)?.value {
+                Some(value) => value.clone(),
+                None => return Err(error(expr, format!("used binding `{}` isn't initialized", name)).into()),
+            },
+            ExprKind::Array(exprs) => {
+                let mut values: Vec<Value> = Vec::new();
+                for e in exprs {
+                    let value = self.eval(e)?;
+                    if let Some(first) = values.first() {
+                        if !first.same_type(&value) {
+                            return Err(mismatched(e, &first.type_text(), &value).into());
+                        }
+                    }
+                    values.push(value);
+                }
+                Value::Array(values)
+            }
+            ExprKind::Repeat(value, count) => {
+                let value = self.eval(value)?;
+                let n = self.int(count)?;
+                if n < 0 {
+                    return Err(error(count, format!("array length {} is negative", n)).into());
+                }
+                Value::Array(vec![value; n as usize])
+            }
+            ExprKind::Index(array, index) => {
+                let values = match self.eval(array)? {
+                    Value::Array(values) => values,
+                    other => return Err(error(expr, format!("cannot index into a value of type {}", other.type_name())).into()),
+                };
+                let i = self.int(index)?;
+                match usize::try_from(i).ok().and_then(|i| values.get(i)) {
+                    Some(value) => value.clone(),
+                    None => {
+                        let message = format!("index out of bounds: the len is {} but the index is {}", values.len(), i);
+                        return Err(error(expr, message).into());
+                    }
+                }
+            }
+            ExprKind::Unary(op, operand) => match (*op, self.eval(operand)?) {
+                ("-", Value::Int(n)) => Value::Int(n.checked_neg()
+                    .ok_or_else(|| error(expr, String::from("attempt to negate with overflow")))?),
+                ("!", Value::Int(n)) => Value::Int(!n),
+                ("!", Value::Bool(b)) => Value::Bool(!b),
+                (op, other) => {
+                    return Err(error(expr, format!("cannot apply unary `{}` to {}", op, other.type_name())).into());
+                }
+            },
+            ExprKind::Binary("&&", left, right) => Value::Bool(self.bool(left)? && self.bool(right)?),
+            ExprKind::Binary("||", left, right) => Value::Bool(self.bool(left)? || self.bool(right)?),
+            ExprKind::Binary(op, left, right) => {
+                let (left, right) = (self.eval(left)?, self.eval(right)?);
+                binary(expr, op, left, right)?
+            }
+            ExprKind::Range { start, end, inclusive } => {
+                let (start, end) = (self.int(start)?, self.int(end)?);
+                Value::Range { start: start as i64, end: end as i64 + *inclusive as i64, reversed: false }
+            }
+            ExprKind::Method(receiver, name) => match (self.eval(receiver)?, name.as_str()) {
+                // the elements, not references to them
+                (Value::Array(values), "iter") => Value::Array(values),
+                (Value::Array(values), "len") => Value::Int(values.len() as i32),
+                (Value::Array(mut values), "rev") => {
+                    values.reverse();
+                    Value::Array(values)
+                }
+                (Value::Range { start, end, reversed }, "rev") => Value::Range { start, end, reversed: !reversed },
+                (other, name) => {
+                    return Err(error(expr, format!("no method named `{}` found for {}", name, other.type_name())).into());
+                }
+            },
+            ExprKind::Block(block) => self.block(block)?,
+            ExprKind::If(condition, then, otherwise) => {
+                if self.bool(condition)? {
+                    let value = self.block(then)?;
+                    // the parser finds the other values, a loop breaking with a value is found here
+                    if otherwise.is_none() && value != Value::Unit {
+                        return Err(error(expr, String::from("`if` may be missing an `else` clause")).into());
+                    }
+                    value
+                } else {
+                    match otherwise {
+                        Some(otherwise) => self.eval(otherwise)?,
+                        None => Value::Unit,
+                    }
+                }
+            }
+            ExprKind::Loop(body) => loop {
+                match self.block(body) {
+                    Ok(_) | Err(Flow::Continue) => {}
+                    Err(Flow::Break(value)) => break value,
+                    Err(e) => return Err(e),
+                }
+            },
+            ExprKind::While(condition, body) => {
+                while self.bool(condition)? {
+                    match self.block(body) {
+                        Ok(_) | Err(Flow::Continue) => {}
+                        Err(Flow::Break(_)) => break,
+                        Err(e) => return Err(e),
+                    }
+                }
+                Value::Unit
+            }
+            ExprKind::For(name, iterable, body) => {
+                let values: Box<dyn Iterator<Item = Value>> = match self.eval(iterable)? {
+                    Value::Array(values) => Box::new(values.into_iter()),
+                    Value::Range { start, end, reversed: false } => Box::new((start..end).map(|n| Value::Int(n as i32))),
+                    Value::Range { start, end, reversed: true } => Box::new((start..end).rev().map(|n| Value::Int(n as i32))),
+                    other => return Err(error(iterable, format!("`{}` is not an iterator", other.type_name())).into()),
+                };
+                for value in values {
+                    let binding = name.as_ref().map(|name| Binding { name: name.clone(), value: Some(value), mutable: false, annotation: None });
+                    self.scopes.push(binding.into_iter().collect());
+                    let result = self.block(body);
+                    self.scopes.pop();
+                    match result {
+                        Ok(_) | Err(Flow::Continue) => {}
+                        Err(Flow::Break(_)) => break,
+                        Err(e) => return Err(e),
+                    }
+                }
+                Value::Unit
+            }
+            ExprKind::Break(value) => {
+                let value = match value {
+                    Some(value) => self.eval(value)?,
+                    None => Value::Unit,
+                };
+                return Err(Flow::Break(value));
+            }
+            ExprKind::Continue => return Err(Flow::Continue),
+            ExprKind::Println(pieces, args) => {
+                let mut line = String::new();
+                let mut args = args.iter();
+                for piece in pieces {
+                    let debug = match piece {
+                        Piece::Text(text) => {
+                            line.push_str(text);
+                            continue;
+                        }
+                        Piece::Display => false,
+                        Piece::Debug => true,
+                    };
+                    let arg = args.next().unwrap();
+                    let value = self.eval(arg)?;
+                    line.push_str(&value.format(debug).map_err(|message| error(arg, message))?);
+                }
+                (self.out)(&line);
+                Value::Unit
+            }
+            ExprKind::Assign(op, name, value) => {
+                let value = self.eval(value)?;
+                let binding = self.binding(expr, name)?;
+                let value = match (op, &binding.value) {
+                    (_, Some(_)) if !binding.mutable => {
+                        return Err(error(expr, format!("cannot assign twice to immutable variable `{}`", name)).into());
+                    }
+                    (None, Some(old)) if !old.same_type(&value) => {
+                        return Err(mismatched(expr, &old.type_text(), &value).into());
+                    }
+                    (None, None) if binding.annotation.is_some_and(|annotation| annotation != value.type_name()) => {
+                        return Err(mismatched(expr, binding.annotation.unwrap(), &value).into());
+                    }
+                    (None, _) => value,
+                    (Some(op), Some(old)) => binary(expr, op, old.clone(), value)?,
+                    (Some(_), None) => {
+                        return Err(error(expr, format!("used binding `{}` isn't initialized", name)).into());
+                    }
+                };
+                binding.value = Some(value);
+                Value::Unit
+            }
+        };
+        Ok(value)
+    }
+}
+
+// Operators other than && and ||, which evaluate their right side only when needed.
+fn binary(expr: &Expr, op: &str, left: Value, right: Value) -> Result<Value, ScriptError> {
+    let overflow = |name: &str| error(expr, format!("attempt to {} with overflow", name));
+    let value = match (left, right) {
+        (Value::Int(a), Value::Int(b)) => match op {
+            "+" => Value::Int(a.checked_add(b).ok_or_else(|| overflow("add"))?),
+            "-" => Value::Int(a.checked_sub(b).ok_or_else(|| overflow("subtract"))?),
+            "*" => Value::Int(a.checked_mul(b).ok_or_else(|| overflow("multiply"))?),
+            "/" if b == 0 => return Err(error(expr, String::from("attempt to divide by zero"))),
+            "/" => Value::Int(a.checked_div(b).ok_or_else(|| overflow("divide"))?),
+            "%" if b == 0 => {
+                return Err(error(expr, String::from("attempt to calculate the remainder with a divisor of zero")));
+            }
+            "%" => Value::Int(a.checked_rem(b).ok_or_else(|| overflow("calculate the remainder"))?),
+            _ => Value::Bool(compare(op, a.cmp(&b))),
+        },
+        (Value::Bool(a), Value::Bool(b)) if !["+", "-", "*", "/", "%"].contains(&op) => Value::Bool(compare(op, a.cmp(&b))),
+        (left, right) if left.same_type(&right) && (op == "==" || op == "!=") => {
+            Value::Bool((left == right) == (op == "=="))
+        }
+        (left, right) => {
+            let message = format!("cannot apply `{}` to {} and {}", op, left.type_text(), right.type_text());
+            return Err(error(expr, message));
+        }
+    };
+    Ok(value)
+}
+
+fn compare(op: &str, ordering: std::cmp::Ordering) -> bool {
+    match op {
+        "==" => ordering.is_eq(),
+        "!=" => ordering.is_ne(),
+        "<" => ordering.is_lt(),
+        "<=" => ordering.is_le(),
+        ">" => ordering.is_gt(),
+        _ => ordering.is_ge(),
+    }
+}
@@ -0,0 +1,144 @@
+// search.rs
+// Index a directory of text files and search it, see inverted_index.rs for the query syntax.
+//
+// rustc search.rs
+// ./search index ..
+// ./search query 'slice AND (string OR str) NOT "hash map"'
+// ./search check            queries, ranking and saving of a small index
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+
+mod args;
+mod crash_report;
+mod inverted_index;
+mod word_count;
+
+use inverted_index::Index;
+
+fn main() {
+    crash_report::install();
+
+    let matches = args::Command::new("search")
+        .about("Full-text search over local documents.")
+        .option("index", Some('i'), "FILE", "Index file [default: search.index]")
+        .subcommand(args::Command::new("index")
+            .about("Index all text files of the directory")
+            .positional("DIR", "Directory with documents"))
+        .subcommand(args::Command::new("query")
+            .about("Print documents matching the query, the best first")
+            .option("top", Some('n'), "N", "Number of documents to print [default: 10]")
+            .multiple("QUERY", "Words, \"phrases\", AND, OR, NOT and parentheses"))
+        .subcommand(args::Command::new("check")
+            .about("Check queries, ranking and indexing with examples"))
+        .parse();
+    let index_path = Path::new(matches.value("index").unwrap_or("search.index"));
+
+    match matches.subcommand() {
+        Some(("index", sub)) => {
+            let dir = Path::new(sub.value("DIR").unwrap());
+            match index(dir, index_path) {
+                Ok((index, skipped)) => {
+                    for path in skipped {
+                        eprintln!("search: {}: not a text file, skipped", path.display());
+                    }
+                    println!("{} documents, {} words", index.documents().len(), index.term_count());
+                }
+                Err(e) => exit_with(&e.to_string()),
+            }
+        }
+        Some(("query", sub)) => {
+            let top: usize = sub.get("top").unwrap_or_else(|e| e.exit()).unwrap_or(10);
+            let query = sub.values("QUERY").join(" ");
+            let index = Index::open(index_path)
+                .unwrap_or_else(|e| exit_with(&format!("{}: {}", index_path.display(), e)));
+            match index.search(&query) {
+                Ok(results) => {
+                    for (document, score) in results.iter().take(top) {
+                        println!("{:8.3}  {}", score, document.path.display());
+                    }
+                    if results.is_empty() {
+                        process::exit(1);
+                    }
+                }
+                Err(e) => exit_with(&format!("query: {}", e)),
+            }
+        }
+        Some(("check", _)) => {
+            check();
+            println!("ok");
+        }
+        _ => matches.error(args::ErrorKind::Other(String::from("a command is required"))).exit(),
+    }
+}
+
+// Index the directory and save the index, which may be inside of the directory. Returns the
+// index and the files which are not text.
+fn index(dir: &Path, index_path: &Path) -> io::Result<(Index, Vec<PathBuf>)> {
+    // the index of an earlier run is not a document
+    let exclude = fs::canonicalize(index_path).ok();
+    let mut index = Index::new();
+    let skipped = index.add_dir(dir, exclude.as_deref())?;
+    index.save(index_path)?;
+    Ok((index, skipped))
+}
+
+fn check() {
+    let dir = env::temp_dir().join(format!("search-check-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("a.txt"), "rust is a systems language").unwrap();
+    fs::write(dir.join("b.txt"), "the index of rust words").unwrap();
+    fs::write(dir.join("c.txt"), "Rust, rust and rust: string slice with cargo").unwrap();
+    fs::write(dir.join("d.txt"), "a slice of string in java with cargo").unwrap();
+    let index_path = dir.join("search.index");
+
+    for _ in 0..2 {
+        let (index, skipped) = index(&dir, &index_path).unwrap();
+        assert_eq!(skipped, Vec::<PathBuf>::new());
+        let paths: Vec<&Path> = index.documents().iter().map(|d| d.path.as_path()).collect();
+        assert_eq!(paths, ["a.txt", "b.txt", "c.txt", "d.txt"].iter().map(|name| dir.join(name)).collect::<Vec<_>>());
+    }
+    // also when the index is given by another path to the same file, and a link back to the
+    // directory is not followed
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&dir, dir.join("loop")).unwrap();
+    let (index, _) = index(&dir, &dir.join(".").join("search.index")).unwrap();
+    assert_eq!(index.documents().len(), 4);
+    // saved and opened again
+    assert_eq!(Index::open(&index_path).unwrap(), index);
+
+    // names of the matching documents, the best first
+    let search = |query: &str| -> Vec<String> {
+        index.search(query).unwrap().iter()
+            .map(|(d, _)| d.path.file_stem().unwrap().to_string_lossy().into_owned())
+            .collect()
+    };
+    // c has rust three times, a and b have the same score and are ordered by path
+    assert_eq!(search("rust"), ["c", "a", "b"]);
+    assert_eq!(search("RUST AND cargo"), ["c"]);
+    assert_eq!(search("rust cargo"), ["c"]);
+    assert_eq!(search("java OR systems"), ["a", "d"]);
+    assert_eq!(search("cargo NOT java"), ["c"]);
+    assert_eq!(search("NOT rust"), ["d"]);
+    assert_eq!(search("(java OR systems) AND NOT language"), ["d"]);
+    assert_eq!(search("\"string slice\""), ["c"]);
+    assert_eq!(search("\"slice string\""), Vec::<String>::new());
+    assert_eq!(search("slice string"), ["c", "d"]);
+    // a rare word weighs more than a common one
+    assert_eq!(search("systems OR cargo"), ["a", "c", "d"]);
+    let errors = [("rust AND", 8, "unexpected end of query"), ("\"open", 0, "unterminated phrase"),
+                  ("(rust", 0, "unclosed '('"), ("OR rust", 0, "unexpected 'OR'"), ("rust )", 5, "unexpected ')'")];
+    for &(query, position, message) in errors.iter() {
+        let error = index.search(query).unwrap_err();
+        assert_eq!((error.position, error.message.as_str()), (position, message), "{}", query);
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+fn exit_with(message: &str) -> ! {
+    eprintln!("search: {}", message);
+    process::exit(2);
+}
@@ -30,6 +30,7 @@ fn main() {
     }
 }
 
+// Only a space ends the word here, word_tokenizer.rs handles all whitespace and punctuation.
 fn first_word(s: &str) -> &str {
     let bytes = s.as_bytes();
 
@@ -0,0 +1,175 @@
+// statistics.rs
+// Descriptive statistics over the Vec<i32> from common-collections.rs.
+// Every function works on a slice, so the caller keeps the ownership of the values.
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatsError {
+    // there are no values
+    Empty,
+    // sample variance needs at least two values
+    NotEnoughValues,
+    // percentile must be in 0..=100
+    InvalidPercentile(f64),
+    // histogram needs at least one bucket
+    InvalidBuckets,
+}
+
+impl fmt::Display for StatsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StatsError::Empty => write!(f, "no values"),
+            StatsError::NotEnoughValues => write!(f, "at least two values are required"),
+            StatsError::InvalidPercentile(p) => write!(f, "percentile {} is not in range 0 to 100", p),
+            StatsError::InvalidBuckets => write!(f, "at least one bucket is required"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Variance {
+    // all values are known, divided by n
+    Population,
+    // values are a sample of a larger set, divided by n - 1
+    Sample,
+}
+
+pub fn mean(values: &[i32]) -> Result<f64, StatsError> {
+    if values.is_empty() {
+        return Err(StatsError::Empty);
+    }
+    // i64 sum can't overflow for any slice which fits in memory
+    let sum: i64 = values.iter().map(|&v| v as i64).sum();
+    Ok(sum as f64 / values.len() as f64)
+}
+
+// Middle value, the mean of the two middle values for even count.
+pub fn median(values: &[i32]) -> Result<f64, StatsError> {
+    sorted(values).map(|sorted| median_of_sorted(&sorted))
+}
+
+fn median_of_sorted(sorted: &[i32]) -> f64 {
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] as f64 + sorted[mid] as f64) / 2.0
+    } else {
+        sorted[mid] as f64
+    }
+}
+
+// All most frequent values in ascending order, more than one when there is a tie.
+pub fn modes(values: &[i32]) -> Result<Vec<i32>, StatsError> {
+    if values.is_empty() {
+        return Err(StatsError::Empty);
+    }
+    let mut counts = HashMap::new();
+    for &value in values {
+        let count = counts.entry(value).or_insert(0);
+        *count += 1;
+    }
+    let max = *counts.values().max().unwrap();
+    let mut modes: Vec<i32> = counts.into_iter()
+        .filter(|&(_, count)| count == max)
+        .map(|(value, _)| value)
+        .collect();
+    modes.sort_unstable();
+    Ok(modes)
+}
+
+pub fn variance(values: &[i32], kind: Variance) -> Result<f64, StatsError> {
+    let mean = mean(values)?;
+    let divisor = match kind {
+        Variance::Population => values.len() as f64,
+        Variance::Sample if values.len() < 2 => return Err(StatsError::NotEnoughValues),
+        Variance::Sample => (values.len() - 1) as f64,
+    };
+    let squares: f64 = values.iter().map(|&v| (v as f64 - mean).powi(2)).sum();
+    Ok(squares / divisor)
+}
+
+pub fn std_dev(values: &[i32], kind: Variance) -> Result<f64, StatsError> {
+    variance(values, kind).map(f64::sqrt)
+}
+
+// Percentile with linear interpolation between the closest ranks, 50 is the median.
+pub fn percentile(values: &[i32], p: f64) -> Result<f64, StatsError> {
+    if !(0.0..=100.0).contains(&p) {
+        return Err(StatsError::InvalidPercentile(p));
+    }
+    let sorted = sorted(values)?;
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    let fraction = rank - lower as f64;
+    Ok(sorted[lower] as f64 + (sorted[upper] as f64 - sorted[lower] as f64) * fraction)
+}
+
+// Histogram bucket, range is start..=end.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bucket {
+    pub start: i64,
+    pub end: i64,
+    pub count: usize,
+}
+
+// Split min..=max into equal buckets, there are fewer of them when the range is too small.
+pub fn histogram(values: &[i32], buckets: usize) -> Result<Vec<Bucket>, StatsError> {
+    if buckets == 0 {
+        return Err(StatsError::InvalidBuckets);
+    }
+    let min = *values.iter().min().ok_or(StatsError::Empty)? as i64;
+    let max = *values.iter().max().unwrap() as i64;
+    let range = (max - min + 1) as u64;
+    let width = range.div_ceil(buckets as u64) as i64;
+
+    let mut histogram: Vec<Bucket> = (0..)
+        .map(|i| min + i * width)
+        .take_while(|&start| start <= max)
+        .map(|start| Bucket { start, end: (start + width - 1).min(max), count: 0 })
+        .collect();
+    for &value in values {
+        histogram[((value as i64 - min) / width) as usize].count += 1;
+    }
+    Ok(histogram)
+}
+
+// Everything at once, values are sorted only once. Sample variance of a single value is an error
+// in variance and std_dev, the rest of the summary is still there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub count: usize,
+    pub min: i32,
+    pub max: i32,
+    pub mean: f64,
+    pub median: f64,
+    pub modes: Vec<i32>,
+    pub variance: Result<f64, StatsError>,
+    pub std_dev: Result<f64, StatsError>,
+}
+
+pub fn summarize(values: &[i32], kind: Variance) -> Result<Summary, StatsError> {
+    let sorted = sorted(values)?;
+    let variance = variance(&sorted, kind);
+    Ok(Summary {
+        count: sorted.len(),
+        min: sorted[0],
+        max: sorted[sorted.len() - 1],
+        mean: mean(&sorted)?,
+        median: median_of_sorted(&sorted),
+        modes: modes(&sorted)?,
+        variance,
+        std_dev: variance.map(f64::sqrt),
+    })
+}
+
+fn sorted(values: &[i32]) -> Result<Vec<i32>, StatsError> {
+    if values.is_empty() {
+        return Err(StatsError::Empty);
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    Ok(sorted)
+}
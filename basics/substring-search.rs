@@ -0,0 +1,125 @@
+// substring-search.rs
+// Check the algorithms of substring_search.rs against each other and measure them on large inputs.
+// Measure an optimized build:
+//
+// rustc -O substring-search.rs
+// ./substring-search --size 16
+
+use std::time::{Duration, Instant};
+
+mod args;
+mod crash_report;
+mod random;
+mod substring_search;
+
+use random::Random;
+use substring_search::Algorithm;
+
+fn main() {
+    crash_report::install();
+
+    let matches = args::Command::new("substring-search")
+        .about("Compare substring search algorithms.")
+        .option("size", Some('s'), "MIB", "Size of each haystack [default: 4]")
+        .option("rounds", Some('r'), "N", "Measure N times, the best time is kept [default: 3]")
+        .parse();
+    let size: usize = matches.get("size").unwrap_or_else(|e| e.exit()).unwrap_or(4);
+    let rounds: usize = matches.get("rounds").unwrap_or_else(|e| e.exit()).unwrap_or(3);
+
+    check();
+
+    let mut random = Random::new(0x2545_f491_4f6c_dd1d);
+    let len = size * 1024 * 1024;
+    let english = english_text(&mut random, len);
+    let dna: Vec<u8> = (0..len).map(|_| b"ACGT"[random.below(4)]).collect();
+    let a_run = vec![b'a'; len];
+    let cases: Vec<(&str, &[u8], Vec<u8>)> = vec![
+        ("english, short word", &english, b"borrow".to_vec()),
+        ("english, long phrase", &english, b"the slice type and the string slice".to_vec()),
+        ("dna, 16 bytes", &dna, dna[len / 2..len / 2 + 16].to_vec()),
+        ("dna, 256 bytes", &dna, dna[len / 3..len / 3 + 256].to_vec()),
+        ("aaa...ab, 64 bytes", &a_run, [vec![b'a'; 63], vec![b'b']].concat()),
+        ("aaa...a, 64 bytes", &a_run, vec![b'a'; 64]),
+    ];
+
+    let mut totals = vec![Duration::default(); Algorithm::ALL.len()];
+    println!("{:<22} {:>10} {:>12} {:>10} {:>10}", "case", "algorithm", "time", "MiB/s", "matches");
+    for (name, haystack, needle) in &cases {
+        for (algorithm, total) in Algorithm::ALL.iter().zip(totals.iter_mut()) {
+            let mut best = Duration::MAX;
+            let mut count = 0;
+            for _ in 0..rounds.max(1) {
+                let start = Instant::now();
+                count = algorithm.find_iter(haystack, needle).count();
+                best = best.min(start.elapsed());
+            }
+            *total += best;
+            let speed = haystack.len() as f64 / 1024.0 / 1024.0 / best.as_secs_f64();
+            println!("{:<22} {:>10} {:>10.2}ms {:>10.0} {:>10}",
+                     name, algorithm.name(), best.as_secs_f64() * 1000.0, speed, count);
+        }
+    }
+
+    println!();
+    for (algorithm, total) in Algorithm::ALL.iter().zip(&totals) {
+        println!("{:<10} {:>10.2}ms in total", algorithm.name(), total.as_secs_f64() * 1000.0);
+    }
+}
+
+// All algorithms must find the same matches as the naive one.
+fn check() {
+    let samples: &[(&[u8], &[u8], &[usize])] = &[
+        (b"hello world", b"o", &[4, 7]),
+        (b"hello world", b"world", &[6]),
+        (b"hello world", b"worlds", &[]),
+        (b"aaaa", b"aa", &[0, 1, 2]),
+        (b"abababab", b"abab", &[0, 2, 4]),
+        (b"", b"a", &[]),
+        (b"ab", b"", &[0, 1, 2]),
+        (b"", b"", &[0]),
+        ("Здравствуйте".as_bytes(), "в".as_bytes(), &[8, 14]),
+    ];
+    for &(haystack, needle, expected) in samples {
+        for algorithm in &Algorithm::ALL {
+            let found: Vec<usize> = algorithm.find_iter(haystack, needle).collect();
+            assert_eq!(found, expected, "{} {:?} in {:?}", algorithm, needle, haystack);
+        }
+    }
+    assert_eq!(Algorithm::TwoWay.find(b"hello world", b"lo w"), Some(3));
+    assert_eq!("two-way".parse(), Ok(Algorithm::TwoWay));
+
+    // random haystacks and needles of few different bytes have many partial matches
+    let mut random = Random::new(42);
+    for _ in 0..20_000 {
+        let alphabet = 1 + random.below(3) as u8;
+        let haystack: Vec<u8> = (0..random.below(40)).map(|_| b'a' + random.below(alphabet as usize) as u8).collect();
+        let needle: Vec<u8> = (0..random.below(8)).map(|_| b'a' + random.below(alphabet as usize) as u8).collect();
+        let expected: Vec<usize> = Algorithm::Naive.find_iter(&haystack, &needle).collect();
+        for algorithm in &Algorithm::ALL[1..] {
+            let found: Vec<usize> = algorithm.find_iter(&haystack, &needle).collect();
+            assert_eq!(found, expected, "{} {:?} in {:?}", algorithm,
+                       String::from_utf8_lossy(&needle), String::from_utf8_lossy(&haystack));
+        }
+    }
+}
+
+// Random words of the Rust book, separated by spaces and sometimes punctuation.
+fn english_text(random: &mut Random, len: usize) -> Vec<u8> {
+    const WORDS: &[&str] = &[
+        "the", "a", "of", "and", "to", "is", "in", "string", "slice", "type", "reference", "ownership",
+        "borrow", "borrowing", "value", "variable", "function", "returns", "memory", "heap", "stack",
+        "compiler", "rust", "data", "scope", "owner", "moved", "copy", "clone", "mutable", "bytes",
+    ];
+    let mut text = Vec::with_capacity(len + 16);
+    while text.len() < len {
+        text.extend_from_slice(WORDS[random.below(WORDS.len())].as_bytes());
+        text.push(match random.below(12) {
+            0 => b'.',
+            1 => b',',
+            2 => b'\n',
+            _ => b' ',
+        });
+    }
+    text.truncate(len);
+    text
+}
@@ -0,0 +1,276 @@
+// substring_search.rs
+// Finding a needle in a haystack of bytes, scanned like the as_bytes() loop of first_word in
+// slice.rs. Every algorithm returns an iterator over the start of every match, overlapping matches
+// included, e.g. "aa" is found at 0, 1 and 2 in "aaaa". An empty needle matches at every position.
+//
+// naive       tries every position, O(n * m) in the worst case
+// kmp         Knuth-Morris-Pratt, never reads a haystack byte twice, O(n + m)
+// horspool    Boyer-Moore-Horspool, skips ahead by the last byte of the window, fast on long needles
+// two-way     Crochemore-Perrin, O(n + m) with constant extra memory, used by str::find
+#![allow(dead_code)]
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Algorithm {
+    Naive,
+    Kmp,
+    Horspool,
+    TwoWay,
+}
+
+impl Algorithm {
+    pub const ALL: [Algorithm; 4] = [Algorithm::Naive, Algorithm::Kmp, Algorithm::Horspool, Algorithm::TwoWay];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Naive => "naive",
+            Algorithm::Kmp => "kmp",
+            Algorithm::Horspool => "horspool",
+            Algorithm::TwoWay => "two-way",
+        }
+    }
+
+    pub fn find_iter<'a>(&self, haystack: &'a [u8], needle: &'a [u8]) -> Box<dyn Iterator<Item=usize> + 'a> {
+        match self {
+            Algorithm::Naive => Box::new(naive(haystack, needle)),
+            Algorithm::Kmp => Box::new(kmp(haystack, needle)),
+            Algorithm::Horspool => Box::new(horspool(haystack, needle)),
+            Algorithm::TwoWay => Box::new(two_way(haystack, needle)),
+        }
+    }
+
+    // First match.
+    pub fn find(&self, haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        self.find_iter(haystack, needle).next()
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Algorithm::ALL.iter()
+            .find(|a| a.name() == s)
+            .copied()
+            .ok_or_else(|| format!("unknown algorithm '{}'", s))
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// Matches of the empty needle, every position including the end.
+fn next_empty(pos: &mut usize, len: usize) -> Option<usize> {
+    if *pos > len {
+        return None;
+    }
+    *pos += 1;
+    Some(*pos - 1)
+}
+
+pub fn naive<'a>(haystack: &'a [u8], needle: &'a [u8]) -> Naive<'a> {
+    Naive { haystack, needle, pos: 0 }
+}
+
+pub struct Naive<'a> {
+    haystack: &'a [u8],
+    needle: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Iterator for Naive<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.pos + self.needle.len() <= self.haystack.len() {
+            let pos = self.pos;
+            self.pos += 1;
+            if &self.haystack[pos..pos + self.needle.len()] == self.needle {
+                return Some(pos);
+            }
+        }
+        None
+    }
+}
+
+pub fn kmp<'a>(haystack: &'a [u8], needle: &'a [u8]) -> Kmp<'a> {
+    // border[i] is the length of the longest proper prefix of needle[..=i] which is also its suffix
+    let mut border = vec![0; needle.len()];
+    let mut k = 0;
+    for i in 1..needle.len() {
+        while k > 0 && needle[i] != needle[k] {
+            k = border[k - 1];
+        }
+        if needle[i] == needle[k] {
+            k += 1;
+        }
+        border[i] = k;
+    }
+    Kmp { haystack, needle, border, pos: 0, matched: 0 }
+}
+
+pub struct Kmp<'a> {
+    haystack: &'a [u8],
+    needle: &'a [u8],
+    border: Vec<usize>,
+    // next haystack byte and how many needle bytes match right before it
+    pos: usize,
+    matched: usize,
+}
+
+impl<'a> Iterator for Kmp<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.needle.is_empty() {
+            return next_empty(&mut self.pos, self.haystack.len());
+        }
+        while self.pos < self.haystack.len() {
+            let byte = self.haystack[self.pos];
+            self.pos += 1;
+            while self.matched > 0 && self.needle[self.matched] != byte {
+                self.matched = self.border[self.matched - 1];
+            }
+            if self.needle[self.matched] == byte {
+                self.matched += 1;
+            }
+            if self.matched == self.needle.len() {
+                self.matched = self.border[self.matched - 1];
+                return Some(self.pos - self.needle.len());
+            }
+        }
+        None
+    }
+}
+
+pub fn horspool<'a>(haystack: &'a [u8], needle: &'a [u8]) -> Horspool<'a> {
+    // how far the window moves when its last byte is the index
+    let mut shift = [needle.len(); 256];
+    for (i, &byte) in needle.iter().enumerate().take(needle.len().saturating_sub(1)) {
+        shift[byte as usize] = needle.len() - 1 - i;
+    }
+    Horspool { haystack, needle, shift, pos: 0 }
+}
+
+pub struct Horspool<'a> {
+    haystack: &'a [u8],
+    needle: &'a [u8],
+    shift: [usize; 256],
+    pos: usize,
+}
+
+impl<'a> Iterator for Horspool<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let m = self.needle.len();
+        if m == 0 {
+            return next_empty(&mut self.pos, self.haystack.len());
+        }
+        while self.pos + m <= self.haystack.len() {
+            let pos = self.pos;
+            let last = self.haystack[pos + m - 1];
+            self.pos += self.shift[last as usize];
+            if last == self.needle[m - 1] && self.haystack[pos..pos + m - 1] == self.needle[..m - 1] {
+                return Some(pos);
+            }
+        }
+        None
+    }
+}
+
+pub fn two_way<'a>(haystack: &'a [u8], needle: &'a [u8]) -> TwoWay<'a> {
+    // critical factorization needle = u v, from the larger of the two maximal suffixes
+    let (i, p) = maximal_suffix(needle, false);
+    let (j, q) = maximal_suffix(needle, true);
+    let (split, period) = if i > j { (i, p) } else { (j, q) };
+    // u is a suffix of v[..period], the needle is periodic with that period
+    let periodic = needle.get(period..period + split) == Some(&needle[..split]);
+    let period = if periodic { period } else { split.max(needle.len() - split) + 1 };
+    TwoWay { haystack, needle, split, period, periodic, pos: 0, memory: 0 }
+}
+
+// Start of the maximal suffix in the lexicographic order, or the reversed one, and its period.
+fn maximal_suffix(x: &[u8], reversed: bool) -> (usize, usize) {
+    // candidate suffix starts at `start`, compared with the one at `j`, `k` bytes are equal
+    let (mut start, mut j, mut k, mut period) = (0, 1, 0, 1);
+    while j + k < x.len() {
+        let (a, b) = (x[j + k], x[start + k]);
+        let (a, b) = if reversed { (b, a) } else { (a, b) };
+        if a < b {
+            // suffix at j is smaller, skip past it, the period grows
+            j += k + 1;
+            k = 0;
+            period = j - start;
+        } else if a == b {
+            if k + 1 == period {
+                j += period;
+                k = 0;
+            } else {
+                k += 1;
+            }
+        } else {
+            // suffix at j is larger, it is the new candidate
+            start = j;
+            j = start + 1;
+            k = 0;
+            period = 1;
+        }
+    }
+    (start, period)
+}
+
+pub struct TwoWay<'a> {
+    haystack: &'a [u8],
+    needle: &'a [u8],
+    split: usize,
+    period: usize,
+    periodic: bool,
+    pos: usize,
+    // bytes at the start of the window known to match, only for a periodic needle
+    memory: usize,
+}
+
+impl<'a> Iterator for TwoWay<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let (needle, m) = (self.needle, self.needle.len());
+        if m == 0 {
+            return next_empty(&mut self.pos, self.haystack.len());
+        }
+        while self.pos + m <= self.haystack.len() {
+            let window = &self.haystack[self.pos..self.pos + m];
+            // right part first, from the split forwards
+            let mut i = self.split.max(self.memory);
+            while i < m && needle[i] == window[i] {
+                i += 1;
+            }
+            if i < m {
+                self.pos += i - self.split + 1;
+                self.memory = 0;
+                continue;
+            }
+            // then the left part backwards, down to what is known to match
+            let mut i = self.split;
+            while i > self.memory && needle[i - 1] == window[i - 1] {
+                i -= 1;
+            }
+            let found = i <= self.memory;
+            let pos = self.pos;
+            self.pos += self.period;
+            if self.periodic {
+                self.memory = m - self.period;
+            }
+            if found {
+                return Some(pos);
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,208 @@
+// text_transform.rs
+// Text transformations next to the strings of common-collections.rs: Pig Latin, title case,
+// snake_case, camelCase, kebab-case and ROT13. Words are made of grapheme clusters, so letters with
+// combining marks and non-ASCII text are never cut in half.
+//
+// Binaries using this module must also declare `mod unicode_slice;`.
+#![allow(dead_code)]
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::unicode_slice;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Transform {
+    PigLatin,
+    Title,
+    Snake,
+    Camel,
+    Kebab,
+    Rot13,
+}
+
+impl Transform {
+    pub const ALL: [Transform; 6] = [
+        Transform::PigLatin,
+        Transform::Title,
+        Transform::Snake,
+        Transform::Camel,
+        Transform::Kebab,
+        Transform::Rot13,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Transform::PigLatin => "pig-latin",
+            Transform::Title => "title",
+            Transform::Snake => "snake",
+            Transform::Camel => "camel",
+            Transform::Kebab => "kebab",
+            Transform::Rot13 => "rot13",
+        }
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        match self {
+            Transform::PigLatin => map_words(text, pig_latin),
+            Transform::Title => map_words(text, capitalize),
+            Transform::Snake => map_lines(text, |line| identifier_words(line).join("_")),
+            Transform::Camel => map_lines(text, camel_case),
+            Transform::Kebab => map_lines(text, |line| identifier_words(line).join("-")),
+            Transform::Rot13 => rot13(text),
+        }
+    }
+}
+
+impl FromStr for Transform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Transform::ALL.iter()
+            .find(|t| t.name() == s)
+            .copied()
+            .ok_or_else(|| format!("unknown transformation '{}'", s))
+    }
+}
+
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// Apply transformations one after another.
+pub fn apply_all(text: &str, transforms: &[Transform]) -> String {
+    transforms.iter().fold(String::from(text), |text, t| t.apply(&text))
+}
+
+const VOWELS: &str = "aeiouáàâäãåæéèêëíìîïóòôöõøœúùûüаеёиоуыэюя";
+
+fn is_vowel(grapheme: &str) -> bool {
+    grapheme.chars().next()
+        .and_then(|c| c.to_lowercase().next())
+        .is_some_and(|c| VOWELS.contains(c))
+}
+
+fn is_letter(grapheme: &str) -> bool {
+    grapheme.chars().next().is_some_and(char::is_alphabetic)
+}
+
+// Replace every word and keep everything else as it is. A word is a run of letters,
+// apostrophes between letters belong to it, e.g. "don't".
+fn map_words<F: Fn(&str) -> String>(text: &str, f: F) -> String {
+    let clusters: Vec<(usize, &str)> = unicode_slice::grapheme_indices(text).collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < clusters.len() {
+        if !is_letter(clusters[i].1) {
+            out.push_str(clusters[i].1);
+            i += 1;
+            continue;
+        }
+        let start = clusters[i].0;
+        let mut j = i + 1;
+        while j < clusters.len()
+            && (is_letter(clusters[j].1)
+            || (clusters[j].1 == "'" && j + 1 < clusters.len() && is_letter(clusters[j + 1].1))) {
+            j += 1;
+        }
+        let end = if j < clusters.len() { clusters[j].0 } else { text.len() };
+        out.push_str(&f(&text[start..end]));
+        i = j;
+    }
+    out
+}
+
+// Replace every line, line endings including \r\n are kept.
+fn map_lines<F: Fn(&str) -> String>(text: &str, f: F) -> String {
+    text.split('\n')
+        .map(|line| match line.strip_suffix('\r') {
+            Some(line) => format!("{}\r", f(line)),
+            None => f(line),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn is_upper(word: &str) -> bool {
+    word.chars().any(char::is_uppercase) && !word.chars().any(char::is_lowercase)
+}
+
+// first -> irst-fay, apple -> apple-hay, Hello -> Ello-hay, HELLO -> ELLO-HAY, v -> vay
+pub fn pig_latin(word: &str) -> String {
+    let first = match unicode_slice::nth_grapheme(word, 0) {
+        Some(first) => first,
+        None => return String::new(),
+    };
+    let rest = &word[first.len()..];
+    let result = if is_vowel(first) {
+        format!("{}-hay", word)
+    } else if rest.is_empty() {
+        format!("{}ay", first)
+    } else {
+        format!("{}-{}ay", rest, first)
+    };
+
+    if is_upper(word) && word.chars().count() > 1 {
+        result.to_uppercase()
+    } else if first.chars().next().is_some_and(char::is_uppercase) {
+        capitalize(&result)
+    } else {
+        result
+    }
+}
+
+// First letter uppercase, the rest lowercase.
+pub fn capitalize(word: &str) -> String {
+    match unicode_slice::nth_grapheme(word, 0) {
+        Some(first) => format!("{}{}", first.to_uppercase(), word[first.len()..].to_lowercase()),
+        None => String::new(),
+    }
+}
+
+// Lowercase words of an identifier or a sentence, e.g. "parseHTTPRequest v2" -> parse, http, request, v2.
+pub fn identifier_words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for part in text.split(|c: char| !c.is_alphanumeric()).filter(|p| !p.is_empty()) {
+        let chars: Vec<char> = part.chars().collect();
+        let mut word = String::new();
+        for (i, &c) in chars.iter().enumerate() {
+            let prev = if i > 0 { Some(chars[i - 1]) } else { None };
+            let next = chars.get(i + 1);
+            // fooBar -> foo Bar, HTTPServer -> HTTP Server
+            let boundary = c.is_uppercase() && match prev {
+                Some(p) if p.is_lowercase() || p.is_numeric() => true,
+                Some(p) if p.is_uppercase() => next.is_some_and(|n| n.is_lowercase()),
+                _ => false,
+            };
+            if boundary && !word.is_empty() {
+                words.push(word.to_lowercase());
+                word.clear();
+            }
+            word.push(c);
+        }
+        if !word.is_empty() {
+            words.push(word.to_lowercase());
+        }
+    }
+    words
+}
+
+fn camel_case(text: &str) -> String {
+    identifier_words(text).iter()
+        .enumerate()
+        .map(|(i, word)| if i == 0 { word.clone() } else { capitalize(word) })
+        .collect()
+}
+
+// Only ASCII letters are rotated, everything else is kept.
+pub fn rot13(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'a'..='z' => (((c as u8 - b'a') + 13) % 26 + b'a') as char,
+            'A'..='Z' => (((c as u8 - b'A') + 13) % 26 + b'A') as char,
+            _ => c,
+        })
+        .collect()
+}
@@ -0,0 +1,104 @@
+// transform.rs
+// Apply text transformations to files or standard input, in the order they are given.
+//
+// rustc transform.rs
+// ./transform --pig-latin --title < input.txt
+// ./transform --check
+
+use std::process;
+
+mod args;
+mod crash_report;
+mod input;
+mod text_transform;
+mod unicode_slice;
+
+use text_transform::Transform;
+
+fn main() {
+    crash_report::install();
+
+    let matches = args::Command::new("transform")
+        .about("Apply text transformations in the order they are given.")
+        .flag("pig-latin", Some('p'), "Pig Latin, first -> irst-fay, apple -> apple-hay")
+        .flag("title", Some('t'), "Title Case Of Every Word")
+        .flag("snake", Some('s'), "snake_case of every line")
+        .flag("camel", Some('c'), "camelCase of every line")
+        .flag("kebab", Some('k'), "kebab-case of every line")
+        .flag("rot13", Some('r'), "ROT13 of ASCII letters")
+        .flag("check", None, "Check the transformations with examples and exit")
+        .multiple("FILE", "Files to read, standard input if none or -")
+        .parse();
+    if matches.flag("check") {
+        check();
+        println!("ok");
+        return;
+    }
+
+    // every used flag is a transformation
+    let transforms: Vec<Transform> = matches.order().iter().map(|name| name.parse().unwrap()).collect();
+    if transforms.is_empty() {
+        matches.error(args::ErrorKind::Other(String::from("at least one transformation is required"))).exit();
+    }
+
+    let mut files = matches.values("FILE");
+    if files.is_empty() {
+        files.push("-");
+    }
+    for path in files {
+        match input::read_to_string(path) {
+            Ok(text) => print!("{}", text_transform::apply_all(&text, &transforms)),
+            Err(e) => {
+                eprintln!("transform: {}: {}", path, e);
+                process::exit(1);
+            }
+        }
+    }
+}
+
+fn check() {
+    let examples = [
+        ("first", "irst-fay"),
+        ("apple", "apple-hay"),
+        ("Hello", "Ello-hay"),
+        ("HELLO", "ELLO-HAY"),
+        // nothing is left to move the consonant behind
+        ("v", "vay"),
+        ("V", "Vay"),
+        ("I", "I-hay"),
+        ("", ""),
+    ];
+    for (word, expected) in examples.iter() {
+        assert_eq!(text_transform::pig_latin(word), *expected, "{}", word);
+    }
+    let text = "A v of the X\n";
+    assert_eq!(text_transform::apply_all(text, &[Transform::PigLatin]), "A-hay vay of-hay he-tay Xay\n");
+
+    // transformation, input and output, non-ASCII letters are letters too
+    let examples = [
+        (Transform::PigLatin, "émile добро n\u{303}o", "émile-hay обро-дay o-n\u{303}ay"),
+        (Transform::Title, "hello wORLD, don't stop", "Hello World, Don't Stop"),
+        (Transform::Title, "émile zola", "Émile Zola"),
+        (Transform::Snake, "parseHTTPRequest v2", "parse_http_request_v2"),
+        (Transform::Snake, "Hello World\r\nfoo-bar\n", "hello_world\r\nfoo_bar\n"),
+        (Transform::Snake, "Größe Straße", "größe_straße"),
+        (Transform::Camel, "hello world", "helloWorld"),
+        (Transform::Camel, "parse_http_request", "parseHttpRequest"),
+        (Transform::Camel, "émile zola", "émileZola"),
+        (Transform::Kebab, "fooBar baz", "foo-bar-baz"),
+        (Transform::Kebab, "HTTPServer_Éclair", "http-server-éclair"),
+        (Transform::Rot13, "Hello, World!", "Uryyb, Jbeyq!"),
+        // only ASCII letters are rotated
+        (Transform::Rot13, "Здравствуйте é z", "Здравствуйте é m"),
+    ];
+    for (transform, text, expected) in examples.iter() {
+        assert_eq!(transform.apply(text), *expected, "{} {:?}", transform, text);
+    }
+    for transform in Transform::ALL.iter() {
+        assert_eq!(transform.apply(""), "", "{}", transform);
+        assert_eq!(transform.apply("\n"), "\n", "{}", transform);
+        assert_eq!(transform.name().parse(), Ok(*transform));
+    }
+    assert_eq!(Transform::Rot13.apply(&Transform::Rot13.apply("Hello, World!")), "Hello, World!");
+    assert_eq!(text_transform::apply_all("Hello World", &[Transform::Snake, Transform::Rot13]), "uryyb_jbeyq");
+}
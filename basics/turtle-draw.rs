@@ -0,0 +1,152 @@
+// turtle-draw.rs
+// Draw the path of a turtle following a script of messages, see turtle.rs and message_text.rs.
+//
+// rustc turtle-draw.rs
+// ./turtle-draw draw square.txt           writes square.svg and square.ppm
+// ./turtle-draw check
+
+use std::fs;
+use std::path::Path;
+use std::process;
+
+mod args;
+mod crash_report;
+mod input;
+mod message;
+mod message_text;
+mod turtle;
+
+use message::Message;
+use turtle::{Drawing, View};
+
+fn main() {
+    crash_report::install();
+
+    let matches = args::Command::new("turtle-draw")
+        .about("Draw the path of a turtle as SVG and PPM images.")
+        .subcommand(args::Command::new("draw")
+            .about("Execute the script and write FILE.svg and FILE.ppm next to it")
+            .option("output", Some('o'), "NAME", "Write NAME.svg and NAME.ppm instead")
+            .option("scale", Some('s'), "PIXELS", "Pixels per step [default: 10]")
+            .option("margin", Some('m'), "PIXELS", "Empty pixels around the path [default: 10]")
+            .positional("FILE", "Script of messages, - for standard input"))
+        .subcommand(args::Command::new("check")
+            .about("Check the images of an example script"))
+        .parse();
+
+    match matches.subcommand() {
+        Some(("draw", sub)) => {
+            let defaults = View::default();
+            let view = View {
+                scale: sub.get("scale").unwrap_or_else(|e| e.exit()).unwrap_or(defaults.scale),
+                margin: sub.get("margin").unwrap_or_else(|e| e.exit()).unwrap_or(defaults.margin),
+            };
+            let path = sub.value("FILE").unwrap();
+            let output = match sub.value("output") {
+                Some(output) => String::from(output),
+                None if path == "-" => String::from("turtle"),
+                None => Path::new(path).with_extension("").to_string_lossy().into_owned(),
+            };
+            draw(path, &output, view);
+        }
+        Some(("check", _)) => {
+            check();
+            println!("ok");
+        }
+        _ => matches.error(args::ErrorKind::Other(String::from("a command is required"))).exit(),
+    }
+}
+
+fn draw(path: &str, output: &str, view: View) {
+    let text = input::read_to_string(path)
+        .unwrap_or_else(|e| exit_with(&format!("{}: {}", path, e)));
+    let messages = message_text::parse(&text)
+        .unwrap_or_else(|e| exit_with(&format!("{}:{}", path, e)));
+    let drawing = Drawing::trace(&messages)
+        .unwrap_or_else(|(i, e)| exit_with(&format!("{}: message {} '{}': {}", path, i + 1, messages[i], e)));
+    let ppm = drawing.to_ppm(view).unwrap_or_else(|e| exit_with(&e));
+
+    let svg_path = format!("{}.svg", output);
+    let ppm_path = format!("{}.ppm", output);
+    fs::write(&svg_path, drawing.to_svg(view)).unwrap_or_else(|e| exit_with(&format!("{}: {}", svg_path, e)));
+    fs::write(&ppm_path, ppm).unwrap_or_else(|e| exit_with(&format!("{}: {}", ppm_path, e)));
+    println!("{} lines, {} labels: {}, {}", drawing.segments.len(), drawing.labels.len(), svg_path, ppm_path);
+}
+
+const EXAMPLE: &str = "\
+# red step right, blue step down, labeled corner
+COLOR 255 0 0
+MOVE 2 0
+COLOR 0 0 255
+MOVE 0 1
+WRITE \"<corner> & co\"
+MOVE -1 -2
+QUIT
+MOVE 100 100
+";
+
+const EXAMPLE_SVG: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" width="7" height="7">
+  <rect width="100%" height="100%" fill="#ffffff"/>
+  <line x1="1" y1="3" x2="5" y2="3" stroke="#ff0000" stroke-linecap="square"/>
+  <line x1="5" y1="3" x2="5" y2="5" stroke="#0000ff" stroke-linecap="square"/>
+  <line x1="5" y1="5" x2="3" y2="1" stroke="#0000ff" stroke-linecap="square"/>
+  <text x="5" y="5" fill="#0000ff" font-family="sans-serif">&lt;corner&gt; &amp; co</text>
+</svg>
+"##;
+
+fn check() {
+    let messages = message_text::parse(EXAMPLE).unwrap();
+    let drawing = Drawing::trace(&messages).unwrap();
+    // nothing is drawn after QUIT
+    assert_eq!(drawing.segments.len(), 3);
+    let view = View { scale: 2, margin: 1 };
+    assert_eq!(drawing.to_svg(view), EXAMPLE_SVG);
+
+    // . is white, r red and b blue, later lines are drawn over earlier ones
+    let expected = [
+        ".......",
+        "...b...",
+        "...b...",
+        ".rrrbb.",
+        "....bb.",
+        ".....b.",
+        ".......",
+    ];
+    let ppm = drawing.to_ppm(view).unwrap();
+    let header = "P6\n7 7\n255\n";
+    assert_eq!(&ppm[..header.len()], header.as_bytes());
+    let pixels: Vec<String> = ppm[header.len()..]
+        .chunks(3)
+        .map(|rgb| match rgb {
+            [255, 255, 255] => '.',
+            [255, 0, 0] => 'r',
+            [0, 0, 255] => 'b',
+            _ => '?',
+        })
+        .collect::<Vec<char>>()
+        .chunks(7)
+        .map(|row| row.iter().collect())
+        .collect();
+    assert_eq!(pixels, expected);
+
+    // the same script gives the same files
+    let again = Drawing::trace(&message_text::parse(EXAMPLE).unwrap()).unwrap();
+    assert_eq!(again.to_ppm(view).unwrap(), ppm);
+
+    // invalid messages are reported with their index
+    let messages = [Message::Move { x: 1, y: 1 }, Message::ChangeColor(0, 300, 0)];
+    let (i, e) = Drawing::trace(&messages).unwrap_err();
+    assert_eq!((i, e.to_string().as_str()), (1, "color component 300 is not in range 0 to 255"));
+
+    // an empty drawing is just the margin around the start
+    let empty = Drawing::default();
+    assert_eq!(&empty.to_ppm(View { scale: 5, margin: 2 }).unwrap()[..9], b"P6\n5 5\n25");
+    let huge = Drawing::trace(&[Message::Move { x: i32::MAX, y: i32::MIN }]).unwrap();
+    assert!(huge.to_ppm(View::default()).is_err());
+    assert!(huge.to_svg(View::default()).contains("x2=\"21474836480\""));
+}
+
+fn exit_with(message: &str) -> ! {
+    eprintln!("turtle-draw: {}", message);
+    process::exit(2);
+}
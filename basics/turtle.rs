@@ -0,0 +1,196 @@
+// turtle.rs
+// Turtle graphics driven by Message from message.rs. The turtle starts at (0, 0) with black color,
+// Move draws a line to the position moved by (x, y) where y grows downwards, ChangeColor changes
+// the color of the following lines, Write puts a label at the current position and Quit ends the
+// drawing. The traced path is written as SVG or as PPM image, labels are only in the SVG.
+//
+// Binaries using this module must also declare `mod message;`.
+#![allow(dead_code)]
+
+use std::fmt::Write;
+use std::iter;
+
+use crate::message::{Color, DispatchError, Message, State};
+
+// Bigger images are refused instead of allocating the memory.
+pub const MAX_PIXELS: u64 = 4096 * 4096;
+
+const BACKGROUND: Color = Color { r: 255, g: 255, b: 255 };
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub from: (i32, i32),
+    pub to: (i32, i32),
+    pub color: Color,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub at: (i32, i32),
+    pub text: String,
+    pub color: Color,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Drawing {
+    pub segments: Vec<Segment>,
+    pub labels: Vec<Label>,
+}
+
+// How drawing coordinates become pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct View {
+    // pixels per unit of Move
+    pub scale: u32,
+    // empty pixels around the drawing
+    pub margin: u32,
+}
+
+impl Default for View {
+    fn default() -> View {
+        View { scale: 10, margin: 10 }
+    }
+}
+
+impl Drawing {
+    // Execute the messages until Quit, the error tells the index of the failed message.
+    pub fn trace(messages: &[Message]) -> Result<Drawing, (usize, DispatchError)> {
+        let mut drawing = Drawing::default();
+        let mut state = State::new();
+        for (i, message) in messages.iter().enumerate() {
+            let from = state.position;
+            state.apply(message).map_err(|e| (i, e))?;
+            match message {
+                Message::Move { .. } => {
+                    drawing.segments.push(Segment { from, to: state.position, color: state.color });
+                }
+                Message::Write(text) => {
+                    drawing.labels.push(Label { at: state.position, text: text.clone(), color: state.color });
+                }
+                Message::ChangeColor(..) => {}
+                Message::Quit => break,
+            }
+        }
+        Ok(drawing)
+    }
+
+    pub fn to_svg(&self, view: View) -> String {
+        let frame = Frame::new(self, view);
+        let mut svg = String::new();
+        writeln!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">",
+                 frame.width, frame.height).unwrap();
+        writeln!(svg, "  <rect width=\"100%\" height=\"100%\" fill=\"{}\"/>", BACKGROUND).unwrap();
+        for segment in &self.segments {
+            let (x1, y1) = frame.pixel(segment.from);
+            let (x2, y2) = frame.pixel(segment.to);
+            writeln!(svg, "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-linecap=\"square\"/>",
+                     x1, y1, x2, y2, segment.color).unwrap();
+        }
+        for label in &self.labels {
+            let (x, y) = frame.pixel(label.at);
+            writeln!(svg, "  <text x=\"{}\" y=\"{}\" fill=\"{}\" font-family=\"sans-serif\">{}</text>",
+                     x, y, label.color, escape_xml(&label.text)).unwrap();
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    // Binary PPM (P6), every segment is a line one pixel wide.
+    pub fn to_ppm(&self, view: View) -> Result<Vec<u8>, String> {
+        let frame = Frame::new(self, view);
+        if frame.width.saturating_mul(frame.height) > MAX_PIXELS {
+            return Err(format!("image of {}x{} pixels is too large", frame.width, frame.height));
+        }
+        let (width, height) = (frame.width as usize, frame.height as usize);
+        let mut pixels = vec![BACKGROUND; width * height];
+        for segment in &self.segments {
+            let from = frame.pixel(segment.from);
+            let to = frame.pixel(segment.to);
+            for (x, y) in line(from, to) {
+                pixels[y as usize * width + x as usize] = segment.color;
+            }
+        }
+
+        let mut ppm = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+        ppm.reserve(pixels.len() * 3);
+        for pixel in pixels {
+            ppm.extend_from_slice(&[pixel.r, pixel.g, pixel.b]);
+        }
+        Ok(ppm)
+    }
+}
+
+// Bounding box of the drawing in pixels, the start (0, 0) is always inside.
+struct Frame {
+    min: (i64, i64),
+    scale: i64,
+    margin: i64,
+    width: u64,
+    height: u64,
+}
+
+impl Frame {
+    fn new(drawing: &Drawing, view: View) -> Frame {
+        let points = drawing.segments.iter()
+            .flat_map(|s| iter::once(s.from).chain(iter::once(s.to)))
+            .chain(drawing.labels.iter().map(|l| l.at))
+            .chain(Some((0, 0)));
+        let (mut min, mut max) = ((i64::MAX, i64::MAX), (i64::MIN, i64::MIN));
+        for (x, y) in points {
+            min = (min.0.min(x as i64), min.1.min(y as i64));
+            max = (max.0.max(x as i64), max.1.max(y as i64));
+        }
+        let (scale, margin) = (view.scale as i64, view.margin as i64);
+        Frame {
+            min,
+            scale,
+            margin,
+            width: (max.0 - min.0).saturating_mul(scale).saturating_add(2 * margin + 1) as u64,
+            height: (max.1 - min.1).saturating_mul(scale).saturating_add(2 * margin + 1) as u64,
+        }
+    }
+
+    fn pixel(&self, (x, y): (i32, i32)) -> (i64, i64) {
+        let pixel = |n: i32, min: i64| (n as i64 - min).saturating_mul(self.scale).saturating_add(self.margin);
+        (pixel(x, self.min.0), pixel(y, self.min.1))
+    }
+}
+
+// Pixels of the line from one end to the other, Bresenham's algorithm.
+fn line(from: (i64, i64), to: (i64, i64)) -> Vec<(i64, i64)> {
+    let (dx, dy) = ((to.0 - from.0).abs(), -(to.1 - from.1).abs());
+    let (sx, sy) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+    let (mut x, mut y) = from;
+    let mut error = dx + dy;
+    let mut pixels = vec![(x, y)];
+    while (x, y) != to {
+        let e2 = 2 * error;
+        if e2 >= dy {
+            error += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            error += dx;
+            y += sy;
+        }
+        pixels.push((x, y));
+    }
+    pixels
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // not allowed in XML at all
+            c if c.is_control() && !matches!(c, '\t' | '\n' | '\r') => escaped.push('\u{fffd}'),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,70 @@
+// unicode-slicing.rs
+// Slicing strings by chars and grapheme clusters without panic, see unicode_slice.rs.
+
+mod unicode_slice;
+
+use unicode_slice::{byte_slice, char_slice, grapheme_slice, graphemes, SliceError};
+
+fn main() {
+    // Byte indices, each Cyrillic letter is two bytes long
+    {
+        let hello = "Здравствуйте";
+        assert_eq!(byte_slice(hello, 0..4), Ok("Зд"));
+        // &hello[0..3] panics, byte 3 is in the middle of 'д'
+        assert_eq!(byte_slice(hello, 0..3), Err(SliceError::NotCharBoundary(3)));
+        assert_eq!(byte_slice(hello, 20..30), Err(SliceError::OutOfBounds { index: 30, len: 24 }));
+        let (start, end) = (4, 2);
+        assert_eq!(byte_slice(hello, start..end), Err(SliceError::InvertedRange { start, end }));
+        assert_eq!(byte_slice("", ..), Ok(""));
+        println!("{:?}", byte_slice(hello, 0..3));
+    }
+
+    // Char indices
+    {
+        let hello = "Здравствуйте";
+        assert_eq!(char_slice(hello, 0..4), Ok("Здра"));
+        assert_eq!(char_slice(hello, 8..), Ok("уйте"));
+        assert_eq!(char_slice(hello, ..=1), Ok("Зд"));
+        assert_eq!(char_slice(hello, 0..13), Err(SliceError::OutOfBounds { index: 13, len: 12 }));
+        assert_eq!(unicode_slice::nth_char(hello, 11), Some('е'));
+        assert_eq!(unicode_slice::nth_char(hello, 12), None);
+        println!("{}", char_slice(hello, 0..4).unwrap());
+    }
+
+    // Grapheme clusters, "नमस्ते" is six chars but three letters
+    {
+        let namaste = "नमस्ते";
+        assert_eq!(namaste.chars().count(), 6);
+        assert_eq!(graphemes(namaste).collect::<Vec<_>>(), vec!["न", "म", "स्ते"]);
+        assert_eq!(grapheme_slice(namaste, 2..3), Ok("स्ते"));
+        assert_eq!(grapheme_slice(namaste, 1..4), Err(SliceError::OutOfBounds { index: 4, len: 3 }));
+        assert_eq!(unicode_slice::truncate_graphemes(namaste, 2), "नम");
+        for g in graphemes(namaste) {
+            println!("{}", g);
+        }
+
+        // combining marks, e + combining acute accent and a stack of marks
+        assert_eq!(graphemes("e\u{301}a").collect::<Vec<_>>(), vec!["e\u{301}", "a"]);
+        assert_eq!(unicode_slice::grapheme_count("Z\u{351}\u{36B}\u{343}a"), 2);
+        // Hangul syllable from conjoining jamo
+        assert_eq!(unicode_slice::grapheme_count("\u{1100}\u{1161}\u{11A8}한"), 2);
+        // CR LF is one cluster
+        assert_eq!(graphemes("a\r\nb").collect::<Vec<_>>(), vec!["a", "\r\n", "b"]);
+    }
+
+    // Emoji sequences
+    {
+        // family joined by ZWJ, skin tone modifier, flag, keycap
+        let family = "👨\u{200D}👩\u{200D}👧";
+        let thumbs = "👍🏽";
+        let flag = "🇸🇰";
+        let keycap = "1\u{FE0F}\u{20E3}";
+        let text = format!("{}{}{}{}🇸🇰🇨", family, thumbs, flag, keycap);
+        assert_eq!(
+            graphemes(&text).collect::<Vec<_>>(),
+            vec![family, thumbs, flag, keycap, flag, "🇨"]
+        );
+        assert_eq!(unicode_slice::nth_grapheme(&text, 1), Some(thumbs));
+        println!("{} graphemes in {} chars", unicode_slice::grapheme_count(&text), text.chars().count());
+    }
+}
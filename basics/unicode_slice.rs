@@ -0,0 +1,341 @@
+// unicode_slice.rs
+// Slicing of strings which never panics. `&hello[0..3]` panics when 3 is not at a char boundary,
+// these functions return an error instead and can also take char or grapheme cluster indices.
+//
+// A grapheme cluster is what a user sees as one character, e.g. "स्ते" in "नमस्ते" is four chars
+// but one cluster. The segmenter follows the extended grapheme cluster rules of Unicode UAX #29
+// (including Indic conjuncts) with built-in tables covering combining marks of common scripts,
+// emoji modifiers, ZWJ sequences, flags, keycaps and Hangul syllables.
+#![allow(dead_code)]
+
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SliceError {
+    // index is past the end, len is in the units of the index (bytes, chars or graphemes)
+    OutOfBounds { index: usize, len: usize },
+    // byte index is inside of a multi-byte char
+    NotCharBoundary(usize),
+    // start of the range is after its end
+    InvertedRange { start: usize, end: usize },
+}
+
+impl fmt::Display for SliceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SliceError::OutOfBounds { index, len } => write!(f, "index {} is out of bounds of length {}", index, len),
+            SliceError::NotCharBoundary(index) => write!(f, "byte index {} is not a char boundary", index),
+            SliceError::InvertedRange { start, end } => write!(f, "range start {} is greater than end {}", start, end),
+        }
+    }
+}
+
+// Byte indices, like `&s[range]` but without panic.
+pub fn byte_slice<R: RangeBounds<usize>>(s: &str, range: R) -> Result<&str, SliceError> {
+    let (start, end) = bounds(&range, s.len())?;
+    for &index in &[start, end] {
+        if !s.is_char_boundary(index) {
+            return Err(SliceError::NotCharBoundary(index));
+        }
+    }
+    Ok(&s[start..end])
+}
+
+// Char (unicode scalar value) indices, `char_slice("Здравствуйте", 0..4)` is "Здра".
+pub fn char_slice<R: RangeBounds<usize>>(s: &str, range: R) -> Result<&str, SliceError> {
+    let offsets: Vec<usize> = s.char_indices().map(|(i, _)| i).chain(Some(s.len())).collect();
+    let (start, end) = bounds(&range, offsets.len() - 1)?;
+    Ok(&s[offsets[start]..offsets[end]])
+}
+
+// Grapheme cluster indices, `grapheme_slice("नमस्ते", 2..3)` is "स्ते".
+pub fn grapheme_slice<R: RangeBounds<usize>>(s: &str, range: R) -> Result<&str, SliceError> {
+    let offsets: Vec<usize> = grapheme_indices(s).map(|(i, _)| i).chain(Some(s.len())).collect();
+    let (start, end) = bounds(&range, offsets.len() - 1)?;
+    Ok(&s[offsets[start]..offsets[end]])
+}
+
+pub fn nth_char(s: &str, n: usize) -> Option<char> {
+    s.chars().nth(n)
+}
+
+pub fn nth_grapheme(s: &str, n: usize) -> Option<&str> {
+    graphemes(s).nth(n)
+}
+
+pub fn grapheme_count(s: &str) -> usize {
+    graphemes(s).count()
+}
+
+// At most n grapheme clusters from the start, never cuts a cluster in half.
+pub fn truncate_graphemes(s: &str, n: usize) -> &str {
+    match grapheme_indices(s).nth(n) {
+        Some((i, _)) => &s[..i],
+        None => s,
+    }
+}
+
+fn bounds<R: RangeBounds<usize>>(range: &R, len: usize) -> Result<(usize, usize), SliceError> {
+    let start = match range.start_bound() {
+        Bound::Included(&i) => i,
+        Bound::Excluded(&i) => i.checked_add(1).ok_or(SliceError::OutOfBounds { index: i, len })?,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&i) => i.checked_add(1).ok_or(SliceError::OutOfBounds { index: i, len })?,
+        Bound::Excluded(&i) => i,
+        Bound::Unbounded => len,
+    };
+    if start > end {
+        return Err(SliceError::InvertedRange { start, end });
+    }
+    if end > len {
+        return Err(SliceError::OutOfBounds { index: end, len });
+    }
+    Ok((start, end))
+}
+
+pub fn graphemes(s: &str) -> Graphemes<'_> {
+    Graphemes { rest: s, offset: 0 }
+}
+
+// Clusters together with their byte offsets.
+pub fn grapheme_indices(s: &str) -> impl Iterator<Item=(usize, &str)> {
+    let mut graphemes = graphemes(s);
+    std::iter::from_fn(move || {
+        let offset = graphemes.offset;
+        graphemes.next().map(|g| (offset, g))
+    })
+}
+
+pub struct Graphemes<'a> {
+    rest: &'a str,
+    offset: usize,
+}
+
+impl<'a> Iterator for Graphemes<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let mut chars = self.rest.char_indices();
+        let (_, first) = chars.next()?;
+        let mut state = State::new(first);
+        let end = chars
+            .find(|&(_, c)| state.is_break_before(c))
+            .map(|(i, _)| i)
+            .unwrap_or(self.rest.len());
+        let (cluster, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        self.offset += end;
+        Some(cluster)
+    }
+}
+
+// Grapheme_Cluster_Break property values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Cr,
+    Lf,
+    Control,
+    Extend,
+    Zwj,
+    RegionalIndicator,
+    Prepend,
+    SpacingMark,
+    L,
+    V,
+    T,
+    Lv,
+    Lvt,
+    ExtendedPictographic,
+    Other,
+}
+
+// Indic_Conjunct_Break property values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Conjunct {
+    Consonant,
+    Linker,
+    Extend,
+    None,
+}
+
+// What is known about the cluster so far.
+struct State {
+    prev: Kind,
+    // odd number of regional indicators at the end, the next one completes a flag
+    odd_regional: bool,
+    // ExtPict Extend* seen, Some(true) when followed by ZWJ
+    pictographic: Option<bool>,
+    // Consonant [Extend Linker]* seen, true when there was a Linker
+    conjunct: Option<bool>,
+}
+
+impl State {
+    fn new(first: char) -> State {
+        let mut state = State { prev: Kind::Other, odd_regional: false, pictographic: None, conjunct: None };
+        state.push(first);
+        state
+    }
+
+    fn push(&mut self, c: char) {
+        let kind = kind(c);
+        self.odd_regional = kind == Kind::RegionalIndicator && !self.odd_regional;
+        self.pictographic = match (kind, self.pictographic) {
+            (Kind::ExtendedPictographic, _) => Some(false),
+            (Kind::Extend, Some(false)) => Some(false),
+            (Kind::Zwj, Some(false)) => Some(true),
+            _ => None,
+        };
+        self.conjunct = match (conjunct(c), self.conjunct) {
+            (Conjunct::Consonant, _) => Some(false),
+            (Conjunct::Linker, Some(_)) => Some(true),
+            (Conjunct::Extend, Some(linked)) => Some(linked),
+            _ => None,
+        };
+        self.prev = kind;
+    }
+
+    // Rules GB3 to GB999, updates the state with c.
+    fn is_break_before(&mut self, c: char) -> bool {
+        let next = kind(c);
+        let is_break = match (self.prev, next) {
+            (Kind::Cr, Kind::Lf) => false,
+            (Kind::Cr, _) | (Kind::Lf, _) | (Kind::Control, _) => true,
+            (_, Kind::Cr) | (_, Kind::Lf) | (_, Kind::Control) => true,
+            (Kind::L, Kind::L) | (Kind::L, Kind::V) | (Kind::L, Kind::Lv) | (Kind::L, Kind::Lvt) => false,
+            (Kind::Lv, Kind::V) | (Kind::Lv, Kind::T) | (Kind::V, Kind::V) | (Kind::V, Kind::T) => false,
+            (Kind::Lvt, Kind::T) | (Kind::T, Kind::T) => false,
+            (_, Kind::Extend) | (_, Kind::Zwj) | (_, Kind::SpacingMark) | (Kind::Prepend, _) => false,
+            _ if self.conjunct == Some(true) && conjunct(c) == Conjunct::Consonant => false,
+            (Kind::Zwj, Kind::ExtendedPictographic) if self.pictographic == Some(true) => false,
+            (Kind::RegionalIndicator, Kind::RegionalIndicator) if self.odd_regional => false,
+            _ => true,
+        };
+        self.push(c);
+        is_break
+    }
+}
+
+fn in_ranges(c: char, ranges: &[(u32, u32)]) -> bool {
+    let c = c as u32;
+    ranges
+        .binary_search_by(|&(start, end)| {
+            if end < c {
+                std::cmp::Ordering::Less
+            } else if start > c {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+fn kind(c: char) -> Kind {
+    match c {
+        '\r' => Kind::Cr,
+        '\n' => Kind::Lf,
+        '\u{200D}' => Kind::Zwj,
+        '\u{1F1E6}'..='\u{1F1FF}' => Kind::RegionalIndicator,
+        '\u{1100}'..='\u{115F}' | '\u{A960}'..='\u{A97C}' => Kind::L,
+        '\u{1160}'..='\u{11A7}' | '\u{D7B0}'..='\u{D7C6}' => Kind::V,
+        '\u{11A8}'..='\u{11FF}' | '\u{D7CB}'..='\u{D7FB}' => Kind::T,
+        '\u{AC00}'..='\u{D7A3}' if (c as u32 - 0xAC00).is_multiple_of(28) => Kind::Lv,
+        '\u{AC00}'..='\u{D7A3}' => Kind::Lvt,
+        _ if in_ranges(c, CONTROL) => Kind::Control,
+        _ if in_ranges(c, EXTEND) => Kind::Extend,
+        _ if in_ranges(c, SPACING_MARK) => Kind::SpacingMark,
+        _ if in_ranges(c, PREPEND) => Kind::Prepend,
+        _ if in_ranges(c, EXTENDED_PICTOGRAPHIC) => Kind::ExtendedPictographic,
+        _ => Kind::Other,
+    }
+}
+
+fn conjunct(c: char) -> Conjunct {
+    if in_ranges(c, CONJUNCT_LINKER) {
+        Conjunct::Linker
+    } else if in_ranges(c, CONJUNCT_CONSONANT) {
+        Conjunct::Consonant
+    } else if c == '\u{200D}' || in_ranges(c, EXTEND) {
+        Conjunct::Extend
+    } else {
+        Conjunct::None
+    }
+}
+
+// Tables are sorted, non-overlapping, inclusive ranges of code points.
+
+const CONTROL: &[(u32, u32)] = &[
+    (0x0000, 0x0009), (0x000B, 0x000C), (0x000E, 0x001F), (0x007F, 0x009F), (0x00AD, 0x00AD),
+    (0x061C, 0x061C), (0x180E, 0x180E), (0x200B, 0x200B), (0x200E, 0x200F), (0x2028, 0x202E),
+    (0x2060, 0x206F), (0xFEFF, 0xFEFF), (0xFFF0, 0xFFFB), (0xE0000, 0xE001F), (0xE0080, 0xE00FF),
+];
+
+// Nonspacing and enclosing marks of common scripts, ZWNJ, variation selectors, emoji modifiers and tags.
+const EXTEND: &[(u32, u32)] = &[
+    (0x0300, 0x036F), (0x0483, 0x0489), (0x0591, 0x05BD), (0x05BF, 0x05BF), (0x05C1, 0x05C2),
+    (0x05C4, 0x05C5), (0x05C7, 0x05C7), (0x0610, 0x061A), (0x064B, 0x065F), (0x0670, 0x0670),
+    (0x06D6, 0x06DC), (0x06DF, 0x06E4), (0x06E7, 0x06E8), (0x06EA, 0x06ED), (0x0711, 0x0711),
+    (0x0730, 0x074A), (0x0900, 0x0902), (0x093A, 0x093A), (0x093C, 0x093C), (0x0941, 0x0948),
+    (0x094D, 0x094D), (0x0951, 0x0957), (0x0962, 0x0963), (0x0981, 0x0981), (0x09BC, 0x09BC),
+    (0x09BE, 0x09BE), (0x09C1, 0x09C4), (0x09CD, 0x09CD), (0x09D7, 0x09D7), (0x09E2, 0x09E3),
+    (0x0A01, 0x0A02), (0x0A3C, 0x0A3C), (0x0A41, 0x0A51), (0x0A70, 0x0A71), (0x0A75, 0x0A75),
+    (0x0A81, 0x0A82), (0x0ABC, 0x0ABC), (0x0AC1, 0x0AC8), (0x0ACD, 0x0ACD), (0x0AE2, 0x0AE3),
+    (0x0B01, 0x0B01), (0x0B3C, 0x0B3C), (0x0B3E, 0x0B3F), (0x0B41, 0x0B44), (0x0B4D, 0x0B4D),
+    (0x0B82, 0x0B82), (0x0BBE, 0x0BBE), (0x0BC0, 0x0BC0), (0x0BCD, 0x0BCD), (0x0BD7, 0x0BD7),
+    (0x0C00, 0x0C00), (0x0C3E, 0x0C40), (0x0C46, 0x0C56), (0x0CBC, 0x0CBC), (0x0CCC, 0x0CCD),
+    (0x0D00, 0x0D01), (0x0D41, 0x0D44), (0x0D4D, 0x0D4D), (0x0E31, 0x0E31), (0x0E34, 0x0E3A),
+    (0x0E47, 0x0E4E), (0x0EB1, 0x0EB1), (0x0EB4, 0x0EBC), (0x0EC8, 0x0ECE), (0x0F18, 0x0F19),
+    (0x0F35, 0x0F35), (0x0F37, 0x0F37), (0x0F39, 0x0F39), (0x0F71, 0x0F7E), (0x0F80, 0x0F84),
+    (0x1AB0, 0x1AFF), (0x1DC0, 0x1DFF), (0x200C, 0x200C), (0x20D0, 0x20F0), (0x302A, 0x302F),
+    (0x3099, 0x309A), (0xFE00, 0xFE0F), (0xFE20, 0xFE2F), (0xFF9E, 0xFF9F), (0x1F3FB, 0x1F3FF),
+    (0xE0020, 0xE007F), (0xE0100, 0xE01EF),
+];
+
+const SPACING_MARK: &[(u32, u32)] = &[
+    (0x0903, 0x0903), (0x093B, 0x093B), (0x093E, 0x0940), (0x0949, 0x094C), (0x094E, 0x094F),
+    (0x0982, 0x0983), (0x09BF, 0x09C0), (0x09C7, 0x09C8), (0x09CB, 0x09CC), (0x0A03, 0x0A03),
+    (0x0A3E, 0x0A40), (0x0A83, 0x0A83), (0x0ABE, 0x0AC0), (0x0AC9, 0x0AC9), (0x0ACB, 0x0ACC),
+    (0x0B02, 0x0B03), (0x0B40, 0x0B40), (0x0B47, 0x0B4C), (0x0BBF, 0x0BBF), (0x0BC1, 0x0BCC),
+    (0x0C01, 0x0C03), (0x0C41, 0x0C44), (0x0C82, 0x0C83), (0x0CBE, 0x0CBE), (0x0CC0, 0x0CC4),
+    (0x0D02, 0x0D03), (0x0D3F, 0x0D40), (0x0D46, 0x0D4C), (0x0E33, 0x0E33), (0x0EB3, 0x0EB3),
+];
+
+const PREPEND: &[(u32, u32)] = &[
+    (0x0600, 0x0605), (0x06DD, 0x06DD), (0x070F, 0x070F), (0x0890, 0x0891), (0x08E2, 0x08E2),
+    (0x0D4E, 0x0D4E), (0x110BD, 0x110BD), (0x110CD, 0x110CD),
+];
+
+// Pictographic symbols and emoji, regional indicators and modifiers are matched before this table.
+const EXTENDED_PICTOGRAPHIC: &[(u32, u32)] = &[
+    (0x00A9, 0x00A9), (0x00AE, 0x00AE), (0x203C, 0x203C), (0x2049, 0x2049), (0x2122, 0x2122),
+    (0x2139, 0x2139), (0x2194, 0x2199), (0x21A9, 0x21AA), (0x231A, 0x231B), (0x2328, 0x2328),
+    (0x2388, 0x2388), (0x23CF, 0x23CF), (0x23E9, 0x23F3), (0x23F8, 0x23FA), (0x24C2, 0x24C2),
+    (0x25AA, 0x25AB), (0x25B6, 0x25B6), (0x25C0, 0x25C0), (0x25FB, 0x25FE), (0x2600, 0x27BF),
+    (0x2934, 0x2935), (0x2B05, 0x2B07), (0x2B1B, 0x2B1C), (0x2B50, 0x2B50), (0x2B55, 0x2B55),
+    (0x3030, 0x3030), (0x303D, 0x303D), (0x3297, 0x3297), (0x3299, 0x3299), (0x1F000, 0x1F0FF),
+    (0x1F10D, 0x1F10F), (0x1F12F, 0x1F12F), (0x1F16C, 0x1F171), (0x1F17E, 0x1F17F), (0x1F18E, 0x1F18E),
+    (0x1F191, 0x1F19A), (0x1F1AD, 0x1F1E5), (0x1F201, 0x1F20F), (0x1F21A, 0x1F21A), (0x1F22F, 0x1F22F),
+    (0x1F232, 0x1F23A), (0x1F23C, 0x1F23F), (0x1F249, 0x1F3FA), (0x1F400, 0x1F53D), (0x1F546, 0x1F64F),
+    (0x1F680, 0x1F6FF), (0x1F774, 0x1F77F), (0x1F7D5, 0x1F7FF), (0x1F80C, 0x1F80F), (0x1F848, 0x1F84F),
+    (0x1F85A, 0x1F85F), (0x1F888, 0x1F88F), (0x1F8AE, 0x1F8FF), (0x1F90C, 0x1F93A), (0x1F93C, 0x1F945),
+    (0x1F947, 0x1FAFF), (0x1FC00, 0x1FFFD),
+];
+
+// Indic_Conjunct_Break=Linker, viramas which join consonants into a conjunct.
+const CONJUNCT_LINKER: &[(u32, u32)] = &[
+    (0x094D, 0x094D), (0x09CD, 0x09CD), (0x0ACD, 0x0ACD), (0x0B4D, 0x0B4D), (0x0C4D, 0x0C4D),
+    (0x0D4D, 0x0D4D),
+];
+
+// Indic_Conjunct_Break=Consonant of Devanagari, Bengali, Gujarati, Oriya, Telugu and Malayalam.
+const CONJUNCT_CONSONANT: &[(u32, u32)] = &[
+    (0x0915, 0x0939), (0x0958, 0x095F), (0x0978, 0x097F), (0x0995, 0x09A8), (0x09AA, 0x09B0),
+    (0x09B2, 0x09B2), (0x09B6, 0x09B9), (0x09DC, 0x09DD), (0x09DF, 0x09DF), (0x09F0, 0x09F1),
+    (0x0A95, 0x0AA8), (0x0AAA, 0x0AB0), (0x0AB2, 0x0AB3), (0x0AB5, 0x0AB9), (0x0AF9, 0x0AF9),
+    (0x0B15, 0x0B28), (0x0B2A, 0x0B30), (0x0B32, 0x0B33), (0x0B35, 0x0B39), (0x0B5C, 0x0B5D),
+    (0x0B5F, 0x0B5F), (0x0B71, 0x0B71), (0x0C15, 0x0C28), (0x0C2A, 0x0C39), (0x0C58, 0x0C5A),
+    (0x0D15, 0x0D3A),
+];
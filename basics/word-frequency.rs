@@ -0,0 +1,106 @@
+// word-frequency.rs
+// Count words in files or standard input and print the most frequent ones.
+//
+// rustc word-frequency.rs
+// ./word-frequency --top 5 --english --format csv README.md
+// ./word-frequency --check
+
+use std::collections::HashSet;
+use std::process;
+
+mod args;
+mod crash_report;
+mod input;
+mod word_count;
+
+use word_count::{Format, WordCounts};
+
+fn main() {
+    crash_report::install();
+
+    let matches = args::Command::new("word-frequency")
+        .about("Count words in files or standard input and print the most frequent ones.")
+        .option("top", Some('n'), "N", "Number of words to print [default: 10]")
+        .option("format", Some('f'), "FORMAT", "Output format text, csv or json [default: text]")
+        .option("stopwords", Some('s'), "FILE", "File with words to ignore, may be repeated")
+        .flag("english", Some('e'), "Ignore common English words")
+        .flag("check", None, "Check the counting and the reports with examples and exit")
+        .multiple("FILE", "Files to read, standard input if none or -")
+        .parse();
+    if matches.flag("check") {
+        check();
+        println!("ok");
+        return;
+    }
+
+    let top: usize = matches.get("top").unwrap_or_else(|e| e.exit()).unwrap_or(10);
+    let format: Format = matches.get("format").unwrap_or_else(|e| e.exit()).unwrap_or(Format::Text);
+
+    let mut stopwords = HashSet::new();
+    if matches.flag("english") {
+        stopwords.extend(word_count::parse_stopwords(word_count::ENGLISH_STOPWORDS));
+    }
+    for path in matches.values("stopwords") {
+        stopwords.extend(word_count::parse_stopwords(&read_or_exit(path)));
+    }
+
+    let mut files = matches.values("FILE");
+    if files.is_empty() {
+        files.push("-");
+    }
+
+    let mut counts = WordCounts::new();
+    for path in files {
+        counts.add_text(&read_or_exit(path), &stopwords);
+    }
+    print!("{}", counts.report(top, format));
+}
+
+fn read_or_exit(path: &str) -> String {
+    match input::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("word-frequency: {}: {}", path, e);
+            process::exit(1);
+        }
+    }
+}
+
+fn check() {
+    let words = |text| word_count::words(text).collect::<Vec<String>>();
+    assert_eq!(words("Don't stop-and-go!"), ["don't", "stop-and-go"]);
+    assert_eq!(words("'quoted' -dash- rock'n'roll..."), ["quoted", "dash", "rock'n'roll"]);
+    assert_eq!(words("Hello, HELLO hello.\tÉmile ÉMILE"), ["hello", "hello", "hello", "émile", "émile"]);
+    assert_eq!(words("a\"b\\c 42 -- ' !"), ["a", "b", "c", "42"]);
+    assert!(words("").is_empty());
+
+    let stopwords = word_count::parse_stopwords("# comment the\nThe and\n");
+    assert_eq!(stopwords, ["the", "and"].iter().map(|w| w.to_string()).collect());
+
+    let mut counts = WordCounts::new();
+    counts.add_text("The cat and the hat. The end!", &HashSet::new());
+    assert_eq!((counts.total(), counts.len(), counts.get("the"), counts.get("dog")), (7, 5, 3, 0));
+    // most frequent first, ties alphabetically
+    assert_eq!(counts.top(4), [("the", 3), ("and", 1), ("cat", 1), ("end", 1)]);
+    assert_eq!(counts.top(100).len(), 5);
+    let mut other = WordCounts::new();
+    other.add_text("cat cat", &stopwords);
+    counts.merge(other);
+    assert_eq!(counts.top(2), [("cat", 3), ("the", 3)]);
+
+    let mut counts = WordCounts::new();
+    counts.add_text("b a b", &HashSet::new());
+    assert_eq!(counts.report(5, Format::Text), "b         2   66.67%\na         1   33.33%\n3 words, 2 distinct\n");
+    assert_eq!(counts.report(1, Format::Csv), "word,count,percent\nb,2,66.67\n");
+    assert_eq!(counts.report(5, Format::Json),
+               "{\"total\":3,\"distinct\":2,\"words\":[{\"word\":\"b\",\"count\":2,\"percent\":66.67},\
+                {\"word\":\"a\",\"count\":1,\"percent\":33.33}]}\n");
+    let empty = WordCounts::new();
+    assert_eq!(empty.report(5, Format::Text), "0 words, 0 distinct\n");
+    assert_eq!(empty.report(5, Format::Json), "{\"total\":0,\"distinct\":0,\"words\":[]}\n");
+    assert_eq!(word_count::json_string("say \"hi\"\\\n\u{1}é"), "\"say \\\"hi\\\"\\\\\\n\\u0001é\"");
+    assert_eq!(word_count::csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+    assert_eq!(word_count::csv_field("don't"), "don't");
+    assert_eq!("json".parse(), Ok(Format::Json));
+    assert!("xml".parse::<Format>().is_err());
+}
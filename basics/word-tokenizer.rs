@@ -0,0 +1,107 @@
+// word-tokenizer.rs
+// Words of a string as slices with their positions, see word_tokenizer.rs.
+
+mod word_tokenizer;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use word_tokenizer::{first_word, last_word, nth_word, tokens, Token};
+
+// Counts allocations, so we can check that tokenizing doesn't allocate
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn main() {
+    // Same as first_word in slice.rs, but any whitespace and punctuation ends a word
+    {
+        let my_string = String::from("hello world");
+        assert_eq!(first_word(&my_string), "hello");
+        assert_eq!(first_word("hello"), "hello");
+        assert_eq!(first_word("  \t hello, world"), "hello");
+        assert_eq!(first_word("(hello)"), "hello");
+        assert_eq!(nth_word("hello world", 1), Some("world"));
+        assert_eq!(nth_word("hello world", 2), None);
+        assert_eq!(last_word("hello world!\n"), Some("world"));
+        println!("{}, world!", first_word(&my_string));
+    }
+
+    // Empty strings and strings without words
+    {
+        assert_eq!(first_word(""), "");
+        assert_eq!(nth_word("", 0), None);
+        assert_eq!(last_word(""), None);
+        assert_eq!(tokens(" ,.;!? \n\t«»—").next(), None);
+        assert_eq!(word_tokenizer::word_count("..."), 0);
+    }
+
+    // Offsets are in bytes, columns in chars
+    {
+        let text = "Grüße, мир!\nこんにちは、世界。\r\n  don't  l’été";
+        let words: Vec<Token> = tokens(text).collect();
+        let expected = [
+            ("Grüße", 1, 1),
+            ("мир", 1, 8),
+            ("こんにちは", 2, 1),
+            ("世界", 2, 7),
+            ("don't", 3, 3),
+            ("l’été", 3, 10),
+        ];
+        assert_eq!(words.len(), expected.len());
+        for (token, &(text, line, column)) in words.iter().zip(expected.iter()) {
+            assert_eq!((token.text, token.line, token.column), (text, line, column));
+        }
+        for token in &words {
+            // every word is a slice of the text, not a copy
+            assert_eq!(&text[token.offset..token.offset + token.text.len()], token.text);
+            assert!(std::ptr::eq(token.text.as_ptr(), text[token.offset..].as_ptr()));
+            println!("{}:{} (byte {}) {}", token.line, token.column, token.offset, token.text);
+        }
+        assert_eq!(words[1].offset, "Grüße, ".len());
+        assert_eq!(last_word(text), Some("l’été"));
+    }
+
+    // Unicode whitespace and punctuation
+    {
+        // no-break space, em space, ideographic space, line separator
+        let text = "one\u{A0}two\u{2003}three\u{3000}four\u{2028}five";
+        assert_eq!(tokens(text).map(|t| t.text).collect::<Vec<_>>(), ["one", "two", "three", "four", "five"]);
+        assert_eq!(tokens(text).last().map(|t| t.line), Some(2));
+        assert_eq!(tokens("¿Qué? «Sí»… ¡Olé!").map(|t| t.text).collect::<Vec<_>>(), ["Qué", "Sí", "Olé"]);
+        assert_eq!(tokens("state-of-the-art").count(), 4);
+        // apostrophes around a word are not part of it
+        assert_eq!(tokens("'quoted' rock'n'roll").map(|t| t.text).collect::<Vec<_>>(), ["quoted", "rock'n'roll"]);
+        // letters with combining marks stay together, Devanagari danda is punctuation
+        assert_eq!(tokens("e\u{301}te\u{301} नमस्ते। दुनिया").map(|t| t.text).collect::<Vec<_>>(),
+                   ["e\u{301}te\u{301}", "नमस्ते", "दुनिया"]);
+    }
+
+    // Nothing is allocated while tokenizing
+    {
+        let text = "The quick brown fox — jumps over\nthe lazy dog. Ünïcödé, 世界!";
+        let before = ALLOCATIONS.load(Ordering::SeqCst);
+        let count = tokens(text).count();
+        let total: usize = tokens(text).map(|t| t.text.len() + t.line + t.column).sum();
+        let third = nth_word(text, 2);
+        let last = last_word(text);
+        let allocations = ALLOCATIONS.load(Ordering::SeqCst) - before;
+        assert_eq!(allocations, 0);
+        assert_eq!((count, third, last), (11, Some("brown"), Some("世界")));
+        println!("{} words, {} allocations ({})", count, allocations, total);
+    }
+}
@@ -0,0 +1,192 @@
+// word_count.rs
+// Word counting from common-collections.rs grown into a reusable module: normalization of words,
+// stopwords, top-N report and output in text, CSV or JSON format.
+#![allow(dead_code)]
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+// Small list of common English words for `--english`.
+pub const ENGLISH_STOPWORDS: &str = "a an and are as at be but by for from has have he her his i in is it its \
+    of on or she so that the their them there they this to was were which will with you your";
+
+// Split text into lowercase words, punctuation around and between words is dropped but
+// apostrophes and hyphens inside of words are kept, e.g. "Don't stop-and-go!" -> don't, stop-and-go.
+pub fn words(text: &str) -> impl Iterator<Item=String> + '_ {
+    text.split(is_separator)
+        .map(|word| word.trim_matches(|c| c == '\'' || c == '-'))
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+}
+
+// Character which is never part of a word.
+pub fn is_separator(c: char) -> bool {
+    !(c.is_alphanumeric() || c == '\'' || c == '-')
+}
+
+// Stopwords separated by any whitespace, lines starting with # are comments.
+pub fn parse_stopwords(text: &str) -> HashSet<String> {
+    text.lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .flat_map(|line| words(line).collect::<Vec<_>>())
+        .collect()
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WordCounts {
+    counts: HashMap<String, usize>,
+    total: usize,
+}
+
+impl WordCounts {
+    pub fn new() -> WordCounts {
+        WordCounts::default()
+    }
+
+    pub fn add_text(&mut self, text: &str, stopwords: &HashSet<String>) {
+        for word in words(text) {
+            if !stopwords.contains(&word) {
+                self.add_word(word, 1);
+            }
+        }
+    }
+
+    fn add_word(&mut self, word: String, count: usize) {
+        let entry = self.counts.entry(word).or_insert(0);
+        *entry += count;
+        self.total += count;
+    }
+
+    // Add counts of another counter, e.g. one built by a different thread.
+    pub fn merge(&mut self, other: WordCounts) {
+        for (word, count) in other.counts {
+            self.add_word(word, count);
+        }
+    }
+
+    pub fn get(&self, word: &str) -> usize {
+        *self.counts.get(word).unwrap_or(&0)
+    }
+
+    // Number of counted words.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    // Number of different words.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    // The most frequent words, ties are ordered alphabetically so the result is stable.
+    pub fn top(&self, n: usize) -> Vec<(&str, usize)> {
+        let mut entries: Vec<(&str, usize)> = self.counts.iter()
+            .map(|(word, count)| (word.as_str(), *count))
+            .collect();
+        entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        entries.truncate(n);
+        entries
+    }
+
+    pub fn percent(&self, count: usize) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            count as f64 * 100.0 / self.total as f64
+        }
+    }
+
+    pub fn report(&self, n: usize, format: Format) -> String {
+        let top = self.top(n);
+        let mut out = String::new();
+        match format {
+            Format::Text => {
+                let width = top.iter().map(|(word, _)| word.chars().count()).max().unwrap_or(0);
+                for (word, count) in &top {
+                    out.push_str(&format!("{:width$}  {:>8}  {:>6.2}%\n", word, count, self.percent(*count), width = width));
+                }
+                out.push_str(&format!("{} words, {} distinct\n", self.total, self.len()));
+            }
+            Format::Csv => {
+                out.push_str("word,count,percent\n");
+                for (word, count) in &top {
+                    out.push_str(&format!("{},{},{:.2}\n", csv_field(word), count, self.percent(*count)));
+                }
+            }
+            Format::Json => {
+                out.push_str(&format!("{{\"total\":{},\"distinct\":{},\"words\":[", self.total, self.len()));
+                for (i, (word, count)) in top.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    out.push_str(&format!("{{\"word\":{},\"count\":{},\"percent\":{:.2}}}",
+                                          json_string(word), count, self.percent(*count)));
+                }
+                out.push_str("]}\n");
+            }
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Csv,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            _ => Err(String::from("unknown format, expected one of text, csv, json")),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Format::Text => write!(f, "text"),
+            Format::Csv => write!(f, "csv"),
+            Format::Json => write!(f, "json"),
+        }
+    }
+}
+
+// Field quoted when it contains a comma, a quote or a line break.
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        String::from(value)
+    }
+}
+
+// JSON string literal with quotes, backslashes and control characters escaped.
+pub fn json_string(value: &str) -> String {
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
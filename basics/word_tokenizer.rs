@@ -0,0 +1,122 @@
+// word_tokenizer.rs
+// first_word from slice.rs generalized: every word of a string as a slice of it, with its byte
+// offset, line and column. Nothing is copied and nothing is allocated.
+//
+// Words are separated by any Unicode whitespace or punctuation. An apostrophe between two letters
+// belongs to the word, so "don't" and "l’été" are single words.
+#![allow(dead_code)]
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Token<'a> {
+    pub text: &'a str,
+    // byte offset in the tokenized string
+    pub offset: usize,
+    // line and column (in chars) both start at 1
+    pub line: usize,
+    pub column: usize,
+}
+
+pub fn tokens(s: &str) -> Tokens<'_> {
+    Tokens { s, pos: 0, line: 1, column: 1 }
+}
+
+// First word, empty string when there is none.
+pub fn first_word(s: &str) -> &str {
+    nth_word(s, 0).unwrap_or("")
+}
+
+pub fn nth_word(s: &str, n: usize) -> Option<&str> {
+    tokens(s).nth(n).map(|token| token.text)
+}
+
+pub fn last_word(s: &str) -> Option<&str> {
+    tokens(s).last().map(|token| token.text)
+}
+
+pub fn word_count(s: &str) -> usize {
+    tokens(s).count()
+}
+
+pub struct Tokens<'a> {
+    s: &'a str,
+    pos: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn peek(&self, at: usize) -> Option<char> {
+        self.s[at..].chars().next()
+    }
+
+    // Move over one char, returns it.
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek(self.pos)?;
+        self.pos += c.len_utf8();
+        match c {
+            // \r\n is one line break
+            '\r' if self.peek(self.pos) == Some('\n') => self.column += 1,
+            '\n' | '\r' | '\u{0B}' | '\u{0C}' | '\u{85}' | '\u{2028}' | '\u{2029}' => {
+                self.line += 1;
+                self.column = 1;
+            }
+            _ => self.column += 1,
+        }
+        Some(c)
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        while !self.peek(self.pos).is_some_and(is_word_char) {
+            self.bump()?;
+        }
+        let (offset, line, column) = (self.pos, self.line, self.column);
+        loop {
+            match self.peek(self.pos) {
+                Some(c) if is_word_char(c) => {}
+                Some(c) if is_apostrophe(c)
+                    && self.peek(self.pos + c.len_utf8()).is_some_and(is_word_char) => {}
+                _ => break,
+            }
+            self.bump();
+        }
+        Some(Token { text: &self.s[offset..self.pos], offset, line, column })
+    }
+}
+
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '\u{2019}'
+}
+
+pub fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && !c.is_control() && !is_punctuation(c)
+}
+
+// Unicode punctuation (general category P*) of common scripts.
+pub fn is_punctuation(c: char) -> bool {
+    if c.is_ascii() {
+        return c.is_ascii_punctuation();
+    }
+    let c = c as u32;
+    PUNCTUATION.iter().any(|&(start, end)| start <= c && c <= end)
+}
+
+const PUNCTUATION: &[(u32, u32)] = &[
+    (0x00A1, 0x00A1), (0x00A7, 0x00A7), (0x00AB, 0x00AB), (0x00B6, 0x00B7), (0x00BB, 0x00BB),
+    (0x00BF, 0x00BF), (0x037E, 0x037E), (0x0387, 0x0387), (0x055A, 0x055F), (0x0589, 0x058A),
+    (0x05BE, 0x05BE), (0x05C0, 0x05C0), (0x05C3, 0x05C3), (0x05C6, 0x05C6), (0x05F3, 0x05F4),
+    (0x0609, 0x060A), (0x060C, 0x060D), (0x061B, 0x061B), (0x061D, 0x061F), (0x066A, 0x066D),
+    (0x06D4, 0x06D4), (0x0964, 0x0965), (0x0970, 0x0970), (0x0E4F, 0x0E4F), (0x0E5A, 0x0E5B),
+    (0x10FB, 0x10FB), (0x1360, 0x1368), (0x166E, 0x166E), (0x2010, 0x2027), (0x2030, 0x2043),
+    (0x2045, 0x2051), (0x2053, 0x205E), (0x207D, 0x207E), (0x208D, 0x208E), (0x2308, 0x230B),
+    (0x2329, 0x232A), (0x2768, 0x2775), (0x27C5, 0x27C6), (0x27E6, 0x27EF), (0x2983, 0x2998),
+    (0x29D8, 0x29DB), (0x29FC, 0x29FD), (0x2E00, 0x2E4F), (0x3001, 0x3003), (0x3008, 0x3011),
+    (0x3014, 0x301F), (0x3030, 0x3030), (0x303D, 0x303D), (0x30A0, 0x30A0), (0x30FB, 0x30FB),
+    (0xFE10, 0xFE19), (0xFE30, 0xFE52), (0xFE54, 0xFE61), (0xFE63, 0xFE63), (0xFE68, 0xFE68),
+    (0xFE6A, 0xFE6B), (0xFF01, 0xFF03), (0xFF05, 0xFF0A), (0xFF0C, 0xFF0F), (0xFF1A, 0xFF1B),
+    (0xFF1F, 0xFF20), (0xFF3B, 0xFF3D), (0xFF3F, 0xFF3F), (0xFF5B, 0xFF5B), (0xFF5D, 0xFF5D),
+    (0xFF5F, 0xFF65),
+];